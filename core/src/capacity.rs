@@ -0,0 +1,83 @@
+// data capacity of every symbol in bytes, the ISO/IEC 18004:2006 §6.4.10 table 7 data bits
+// rounded down to whole bytes. ec levels are L, M, Q and H as 0 to 3
+
+// bytes a normal version 1 to 40 holds, 0 for any other version or ec level
+pub fn normal(version: usize, ec: usize) -> usize {
+    match version {
+        1..=40 => DATA_LENGTHS[version - 1].get(ec).copied().unwrap_or(0),
+        _ => 0
+    }
+}
+
+// bytes a Micro version 1 to 4 holds, 0 for any other version and for ec levels it doesn't have
+pub fn micro(version: usize, ec: usize) -> usize {
+    match version {
+        1..=4 => DATA_LENGTHS[version + 39].get(ec).copied().unwrap_or(0),
+        _ => 0
+    }
+}
+
+// normal versions 1 to 40 then Micro 1 to 4, with 0 where an ec level doesn't exist
+pub const DATA_LENGTHS: [[usize; 4]; 44] = [
+    [19, 16, 13, 9],
+    [34, 28, 22, 16],
+    [55, 44, 34, 26],
+    [80, 64, 48, 36],
+    [108, 86, 62, 46],
+    [136, 108, 76, 60],
+    [156, 124, 88, 66],
+    [194, 154, 110, 86],
+    [232, 182, 132, 100],
+    [274, 216, 154, 122],
+    [324, 254, 180, 140],
+    [370, 290, 206, 158],
+    [428, 334, 244, 180],
+    [461, 365, 261, 197],
+    [523, 415, 295, 223],
+    [589, 453, 325, 253],
+    [647, 507, 367, 283],
+    [721, 563, 397, 313],
+    [795, 627, 445, 341],
+    [861, 669, 485, 385],
+    [932, 714, 512, 406],
+    [1006, 782, 568, 442],
+    [1094, 860, 614, 464],
+    [1174, 914, 664, 514],
+    [1276, 1000, 718, 538],
+    [1370, 1062, 754, 596],
+    [1468, 1128, 808, 628],
+    [1531, 1193, 871, 661],
+    [1631, 1267, 911, 701],
+    [1735, 1373, 985, 745],
+    [1843, 1455, 1033, 793],
+    [1955, 1541, 1115, 845],
+    [2071, 1631, 1171, 901],
+    [2191, 1725, 1231, 961],
+    [2306, 1812, 1286, 986],
+    [2434, 1914, 1354, 1054],
+    [2566, 1992, 1426, 1096],
+    [2702, 2102, 1502, 1142],
+    [2812, 2216, 1582, 1222],
+    [2956, 2334, 1666, 1276],
+    [2, 0, 0, 0],
+    [5, 4, 0, 0],
+    [10, 8, 0, 0],
+    [16, 14, 10, 0]
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds() {
+        assert_eq!(normal(1, 0), 19);
+        assert_eq!(normal(40, 3), 1276);
+        assert_eq!(micro(4, 2), 10);
+        assert_eq!(micro(2, 2), 0);
+        assert_eq!(normal(0, 0), 0);
+        assert_eq!(normal(41, 0), 0);
+        assert_eq!(micro(5, 0), 0);
+        assert_eq!(normal(1, 4), 0);
+    }
+}
@@ -0,0 +1,57 @@
+use alloc::vec::Vec;
+use crate::header::{self, HeaderFormat};
+
+// every chunk prefixed with its header, data is what the chunks were cut from. total only
+// counts data chunks, parity chunks after them get the indices past it. None with more chunks
+// than the format can number
+pub fn with_headers(data: &[u8], chunks: &[&[u8]], total: usize, format: HeaderFormat, flags: u8) -> Option<Vec<Vec<u8>>> {
+    if chunks.len() > format.max_chunks() {
+        return None
+    }
+    let checksum = if format.has_checksum() { crc32fast::hash(data) } else { 0 };
+    Some(chunks.iter().enumerate().map(|(i, part)| {
+        let mut qr_data = Vec::with_capacity(format.size() + header::CHUNK_CRC_SIZE + part.len());
+        format.write(&mut qr_data, i, total, flags, checksum);
+        if flags & header::FLAG_CHUNK_CRC != 0 {
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(&qr_data);
+            hasher.update(part);
+            qr_data.extend_from_slice(&hasher.finalize().to_be_bytes());
+        }
+        qr_data.extend_from_slice(part);
+        qr_data
+    }).collect())
+}
+
+// data cut into chunks of at most chunk_size bytes, each with its header in front. the chunk
+// size is what's left of a symbol's capacity once the header is taken out, see capacity
+pub fn split(data: &[u8], chunk_size: usize, format: HeaderFormat, flags: u8) -> Option<Vec<Vec<u8>>> {
+    if chunk_size == 0 {
+        return None
+    }
+    let chunks: Vec<&[u8]> = data.chunks(chunk_size).collect();
+    with_headers(data, &chunks, chunks.len(), format, flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChunkHeader, IndexWidth};
+    use super::*;
+
+    #[test]
+    fn split_roundtrip() {
+        let data = b"the quick brown fox jumps over the lazy dog";
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let payloads = split(data, 10, format, header::FLAG_CHUNK_CRC).unwrap();
+        assert_eq!(payloads.len(), 5);
+        let mut joined = Vec::new();
+        for (i, payload) in payloads.iter().enumerate() {
+            let (header, chunk) = ChunkHeader::parse(format, payload).unwrap();
+            assert_eq!((header.index, header.total, header.checksum), (i, Some(5), Some(crc32fast::hash(data))));
+            joined.extend_from_slice(chunk);
+        }
+        assert_eq!(joined, data);
+        assert_eq!(split(data, 0, format, 0), None);
+        assert_eq!(split(data, 2, HeaderFormat::Compact, 0), None);
+    }
+}
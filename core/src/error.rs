@@ -0,0 +1,53 @@
+use alloc::vec::Vec;
+use core::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    // a payload too short to even hold its header
+    EmptyChunk,
+    // the chunk with this index was never given
+    MissingChunk(usize),
+    // chunks disagree on the total count or checksum, or an index is past the total
+    InconsistentHeader,
+    // everything was there but the reassembled data doesn't match the stored crc32
+    ChecksumMismatch,
+    // a payload that doesn't follow the expected encoding at all
+    Malformed,
+    // the reassembled data was marked as compressed but couldn't be decompressed
+    Decompression,
+    // the data needs a feature (like a compression method) this build doesn't have
+    Unsupported,
+    // the data is encrypted, so it has to be reassembled with a key
+    Encrypted,
+    // the key is wrong or the encrypted data was tampered with
+    Decryption,
+    // an image that couldn't be scanned as a qr code, by position in the input
+    Unreadable(usize),
+    // the decoded data doesn't match the sha-256 digest stored with it
+    DigestMismatch,
+    // the chunk with this index doesn't match its own crc32, it was misread or damaged
+    CorruptChunk(usize),
+    // two different chunks with the same index, e.g. codes of two backups mixed together. first
+    // and second are their data as they were given, without the header
+    ConflictingChunk { index: usize, first: Vec<u8>, second: Vec<u8> }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::EmptyChunk => write!(f, "chunk payload is empty"),
+            DecodeError::MissingChunk(i) => write!(f, "chunk {} is missing", i),
+            DecodeError::InconsistentHeader => write!(f, "chunk headers do not agree with each other"),
+            DecodeError::ChecksumMismatch => write!(f, "reassembled data does not match its checksum"),
+            DecodeError::Malformed => write!(f, "payload is malformed"),
+            DecodeError::Decompression => write!(f, "reassembled data could not be decompressed"),
+            DecodeError::Unsupported => write!(f, "payload needs a feature that is not enabled"),
+            DecodeError::Encrypted => write!(f, "payload is encrypted and no key was given"),
+            DecodeError::Decryption => write!(f, "payload could not be decrypted"),
+            DecodeError::Unreadable(i) => write!(f, "image {} does not contain a readable qr code", i),
+            DecodeError::DigestMismatch => write!(f, "decoded data does not match its sha-256 digest"),
+            DecodeError::CorruptChunk(i) => write!(f, "chunk {} does not match its crc32", i),
+            DecodeError::ConflictingChunk { index, .. } => write!(f, "two different chunks claim index {}", index)
+        }
+    }
+} impl Error for DecodeError {}
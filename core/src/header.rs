@@ -0,0 +1,312 @@
+use alloc::vec::Vec;
+use crate::DecodeError;
+
+// set when every payload carries a crc32 of its own right after the header
+pub const FLAG_CHUNK_CRC: u8 = 0b0010_0000;
+pub const CHUNK_CRC_SIZE: usize = 4;
+
+// how many bytes are used to store chunk indices and counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexWidth {
+    #[default]
+    U8,
+    U16,
+    U32
+}
+
+impl IndexWidth {
+    pub fn bytes(&self) -> usize {
+        match self {
+            IndexWidth::U8 => 1,
+            IndexWidth::U16 => 2,
+            IndexWidth::U32 => 4
+        }
+    }
+
+    // largest value that fits, as usize
+    pub fn max_value(&self) -> usize {
+        match self {
+            IndexWidth::U8 => u8::MAX as usize,
+            IndexWidth::U16 => u16::MAX as usize,
+            IndexWidth::U32 => u32::MAX as usize
+        }
+    }
+
+    fn write(&self, buf: &mut Vec<u8>, value: usize) {
+        buf.extend_from_slice(&(value as u32).to_be_bytes()[4 - self.bytes()..]);
+    }
+
+    fn read(&self, bytes: &[u8]) -> usize {
+        bytes[..self.bytes()].iter().fold(0, |acc, &b| (acc << 8) | b as usize)
+    }
+}
+
+// the versioned header starts with this marker in the high nibble, the wire format version
+// is in the low nibble
+const VERSION_MARKER: u8 = 0xb0;
+
+// revisions of the versioned header layout. a decoder reads the version from every payload, so
+// codes written with an older wire format stay readable once newer ones exist
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum WireFormat {
+    // the version byte, then index and total as big-endian u16, a flags byte and the crc32 of
+    // the whole payload, like HeaderFormat::Extended(IndexWidth::U16)
+    #[default]
+    V1
+}
+
+impl WireFormat {
+    pub const LATEST: WireFormat = WireFormat::V1;
+
+    pub fn version(&self) -> u8 {
+        match self {
+            WireFormat::V1 => 1
+        }
+    }
+
+    pub fn from_version(version: u8) -> Option<Self> {
+        match version {
+            1 => Some(WireFormat::V1),
+            _ => None
+        }
+    }
+
+    // the wire format a payload was written with. Malformed without the version marker,
+    // Unsupported for a version newer than this build knows
+    pub fn of(payload: &[u8]) -> Result<Self, DecodeError> {
+        let &first = payload.first().ok_or(DecodeError::EmptyChunk)?;
+        if first & 0xf0 != VERSION_MARKER {
+            return Err(DecodeError::Malformed)
+        }
+        Self::from_version(first & 0x0f).ok_or(DecodeError::Unsupported)
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            WireFormat::V1 => 1 + HeaderFormat::Extended(IndexWidth::U16).size()
+        }
+    }
+}
+
+// layout of the bytes prepended to every chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderFormat {
+    // a single index byte, the original format
+    #[default]
+    Legacy,
+    // index, total chunk count (both big endian with the given width), a flags byte,
+    // then the crc32 of the whole payload as it was chunked
+    Extended(IndexWidth),
+    // index in the high nibble and total count minus one in the low nibble of a single byte,
+    // small enough for Micro QR codes but limited to 16 chunks
+    Compact,
+    // a byte with the wire format version in front, see WireFormat. payloads are read with the
+    // version they carry, whichever one is given here. codes from before versioning are read with
+    // the format they were written with, there's no telling them apart from the bytes alone
+    Versioned(WireFormat)
+}
+
+impl HeaderFormat {
+    // bytes taken by the header in every chunk
+    pub fn size(&self) -> usize {
+        match self {
+            HeaderFormat::Legacy | HeaderFormat::Compact => 1,
+            HeaderFormat::Extended(width) => 2 * width.bytes() + 1 + 4,
+            HeaderFormat::Versioned(wire) => wire.size()
+        }
+    }
+
+    // highest chunk count this format can describe
+    pub fn max_chunks(&self) -> usize {
+        match self {
+            HeaderFormat::Legacy => IndexWidth::U8.max_value() + 1,
+            HeaderFormat::Extended(width) => width.max_value(),
+            HeaderFormat::Compact => 16,
+            HeaderFormat::Versioned(WireFormat::V1) => IndexWidth::U16.max_value()
+        }
+    }
+
+    // whether the header has room for flags (compression and such)
+    pub fn has_flags(&self) -> bool {
+        matches!(self, HeaderFormat::Extended(_) | HeaderFormat::Versioned(_))
+    }
+
+    // whether the header carries the crc32 of the whole payload
+    pub fn has_checksum(&self) -> bool {
+        matches!(self, HeaderFormat::Extended(_) | HeaderFormat::Versioned(_))
+    }
+
+    // appends the header fields as this format stores them, leaving out whatever it has no room for
+    pub fn write(&self, buf: &mut Vec<u8>, index: usize, total: usize, flags: u8, checksum: u32) {
+        match self {
+            HeaderFormat::Legacy => IndexWidth::U8.write(buf, index),
+            HeaderFormat::Extended(width) => {
+                width.write(buf, index);
+                width.write(buf, total);
+                buf.push(flags);
+                buf.extend_from_slice(&checksum.to_be_bytes());
+            }
+            HeaderFormat::Compact => buf.push(((index as u8) << 4) | (total.saturating_sub(1) as u8 & 0x0f)),
+            HeaderFormat::Versioned(wire) => {
+                buf.push(VERSION_MARKER | wire.version());
+                match wire {
+                    WireFormat::V1 => HeaderFormat::Extended(IndexWidth::U16).write(buf, index, total, flags, checksum)
+                }
+            }
+        }
+    }
+
+    // the header at the start of a payload and the chunk data after it, see ChunkHeader::parse
+    pub fn read<'a>(&self, payload: &'a [u8]) -> Result<(ChunkHeader, &'a [u8]), DecodeError> {
+        // a versioned payload is read with the wire format it was written with
+        let format = match self {
+            HeaderFormat::Versioned(_) => HeaderFormat::Versioned(WireFormat::of(payload)?),
+            format => *format
+        };
+        if payload.len() < format.size() {
+            return Err(DecodeError::EmptyChunk)
+        }
+        let (header, data) = payload.split_at(format.size());
+        let parsed = format.fields(header);
+        if parsed.flags & FLAG_CHUNK_CRC == 0 {
+            return Ok((parsed, data))
+        }
+
+        // the crc covers the header and data, so a misread index is caught as well
+        if data.len() < CHUNK_CRC_SIZE {
+            return Err(DecodeError::EmptyChunk)
+        }
+        let (crc, data) = data.split_at(CHUNK_CRC_SIZE);
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(header);
+        hasher.update(data);
+        if hasher.finalize().to_be_bytes() != crc {
+            return Err(DecodeError::CorruptChunk(parsed.index))
+        }
+        Ok((parsed, data))
+    }
+
+    // the values of a header of exactly self.size() bytes
+    fn fields(&self, header: &[u8]) -> ChunkHeader {
+        match self {
+            HeaderFormat::Legacy => ChunkHeader { index: header[0] as usize, total: None, flags: 0, checksum: None },
+            HeaderFormat::Extended(width) => {
+                let n = 2 * width.bytes();
+                ChunkHeader {
+                    index: width.read(header),
+                    total: Some(width.read(&header[n/2..])),
+                    flags: header[n],
+                    checksum: Some(u32::from_be_bytes([header[n+1], header[n+2], header[n+3], header[n+4]]))
+                }
+            }
+            HeaderFormat::Compact => ChunkHeader { index: (header[0] >> 4) as usize, total: Some((header[0] & 0x0f) as usize + 1), flags: 0, checksum: None },
+            HeaderFormat::Versioned(WireFormat::V1) => HeaderFormat::Extended(IndexWidth::U16).fields(&header[1..])
+        }
+    }
+}
+
+// the header in front of every chunk's data. total, flags and checksum are only stored by the
+// formats with room for them, see HeaderFormat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChunkHeader {
+    pub index: usize,
+    // data chunks in the set, parity chunks come after them
+    pub total: Option<usize>,
+    pub flags: u8,
+    // crc32 of the whole payload as it was chunked
+    pub checksum: Option<u32>
+}
+
+impl ChunkHeader {
+    // writes the header to the start of buf, returning its size. None when buf is too short or a
+    // value doesn't fit the format, like an index past 255 or flags with the legacy header
+    pub fn encode(&self, format: HeaderFormat, buf: &mut [u8]) -> Option<usize> {
+        let total = self.total.unwrap_or(0);
+        let fits = match format {
+            HeaderFormat::Legacy => self.index <= IndexWidth::U8.max_value() && self.total.is_none(),
+            HeaderFormat::Compact => self.index < 16 && (1..=16).contains(&total),
+            _ => self.index <= format.max_chunks() && total <= format.max_chunks() && self.total.is_some()
+        };
+        let stored = format.has_flags() || (self.flags == 0 && self.checksum.is_none());
+        if !fits || !stored {
+            return None
+        }
+        let mut bytes = Vec::with_capacity(format.size());
+        format.write(&mut bytes, self.index, total, self.flags, self.checksum.unwrap_or(0));
+        buf.get_mut(..bytes.len())?.copy_from_slice(&bytes);
+        Some(bytes.len())
+    }
+
+    // the header at the start of a payload and the chunk data after it. with FLAG_CHUNK_CRC set
+    // the chunk's own crc32 is checked and left out of the data
+    pub fn parse(format: HeaderFormat, payload: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        format.read(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use super::*;
+
+    const FORMATS: [HeaderFormat; 6] = [
+        HeaderFormat::Legacy,
+        HeaderFormat::Compact,
+        HeaderFormat::Extended(IndexWidth::U8),
+        HeaderFormat::Extended(IndexWidth::U16),
+        HeaderFormat::Extended(IndexWidth::U32),
+        HeaderFormat::Versioned(WireFormat::V1)
+    ];
+
+    #[test]
+    fn roundtrip() {
+        for format in FORMATS {
+            let max = format.max_chunks().min(u32::MAX as usize);
+            for (index, total) in [(0, 1), (1, 2), (max - 1, max), (15, 16)] {
+                let header = match format {
+                    HeaderFormat::Legacy => ChunkHeader { index, ..Default::default() },
+                    HeaderFormat::Compact => ChunkHeader { index: index.min(15), total: Some(total.min(16)), ..Default::default() },
+                    _ => ChunkHeader { index, total: Some(total), flags: 0x41, checksum: Some(0xdead_beef) }
+                };
+                let mut buf = [0xff; 16];
+                let size = header.encode(format, &mut buf).unwrap();
+                assert_eq!(size, format.size());
+                buf[size] = 42;
+                assert_eq!(ChunkHeader::parse(format, &buf[..size + 1]), Ok((header, &[42][..])), "{:?}", format);
+            }
+        }
+    }
+
+    #[test]
+    fn layout() {
+        let header = ChunkHeader { index: 2, total: Some(5), flags: 0x01, checksum: Some(0x0a0b_0c0d) };
+        let mut buf = [0; 16];
+        assert_eq!(ChunkHeader { total: None, flags: 0, checksum: None, ..header }.encode(HeaderFormat::Legacy, &mut buf), Some(1));
+        assert_eq!(buf[0], 2);
+        assert_eq!(ChunkHeader { flags: 0, checksum: None, ..header }.encode(HeaderFormat::Compact, &mut buf), Some(1));
+        assert_eq!(buf[0], 0x24);
+        assert_eq!(header.encode(HeaderFormat::Extended(IndexWidth::U16), &mut buf), Some(9));
+        assert_eq!(buf[..9], [0, 2, 0, 5, 1, 0x0a, 0x0b, 0x0c, 0x0d]);
+        assert_eq!(header.encode(HeaderFormat::Versioned(WireFormat::V1), &mut buf), Some(10));
+        assert_eq!(buf[..10], [0xb1, 0, 2, 0, 5, 1, 0x0a, 0x0b, 0x0c, 0x0d]);
+    }
+
+    #[test]
+    fn rejected() {
+        let mut buf = [0; 16];
+        let header = ChunkHeader { index: 256, total: Some(300), ..Default::default() };
+        assert_eq!(header.encode(HeaderFormat::Extended(IndexWidth::U8), &mut buf), None);
+        assert_eq!(header.encode(HeaderFormat::Extended(IndexWidth::U16), &mut buf[..4]), None);
+        assert_eq!(ChunkHeader { index: 1, total: None, flags: 1, checksum: None }.encode(HeaderFormat::Legacy, &mut buf), None);
+        assert_eq!(ChunkHeader { index: 0, total: Some(17), ..Default::default() }.encode(HeaderFormat::Compact, &mut buf), None);
+        assert_eq!(ChunkHeader { index: 0, total: None, ..Default::default() }.encode(HeaderFormat::Extended(IndexWidth::U8), &mut buf), None);
+
+        assert_eq!(ChunkHeader::parse(HeaderFormat::Extended(IndexWidth::U8), &[0, 1, 0]), Err(DecodeError::EmptyChunk));
+        // a chunk crc that doesn't match
+        let mut payload = vec![0; 20];
+        let size = ChunkHeader { index: 3, total: Some(4), flags: FLAG_CHUNK_CRC, checksum: Some(0) }.encode(HeaderFormat::Extended(IndexWidth::U8), &mut payload).unwrap();
+        assert_eq!(size, 7);
+        assert_eq!(ChunkHeader::parse(HeaderFormat::Extended(IndexWidth::U8), &payload), Err(DecodeError::CorruptChunk(3)));
+    }
+}
@@ -0,0 +1,12 @@
+// chunking, headers and capacities of mqrcode, with nothing but alloc. qrcode itself needs std,
+// so rendering and the codes themselves stay in the main crate
+#![no_std]
+extern crate alloc;
+
+pub mod capacity;
+pub mod chunk;
+pub mod error;
+pub mod header;
+
+pub use error::DecodeError;
+pub use header::{ChunkHeader, HeaderFormat, IndexWidth, WireFormat};
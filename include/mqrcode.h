@@ -0,0 +1,78 @@
+#ifndef MQRCODE_H
+#define MQRCODE_H
+
+/* Generated with cbindgen --config cbindgen.toml --features ffi --output include/mqrcode.h, do not edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum MqrEcLevel {
+  MQR_EC_LEVEL_L = 0,
+  MQR_EC_LEVEL_M = 1,
+  MQR_EC_LEVEL_Q = 2,
+  MQR_EC_LEVEL_H = 3,
+} MqrEcLevel;
+
+typedef enum MqrHeader {
+  MQR_HEADER_LEGACY = 0,
+  MQR_HEADER_COMPACT = 1,
+  MQR_HEADER_EXTENDED_U8 = 2,
+  MQR_HEADER_EXTENDED_U16 = 3,
+  MQR_HEADER_EXTENDED_U32 = 4,
+  MQR_HEADER_VERSIONED = 5,
+} MqrHeader;
+
+typedef enum MqrStatus {
+  MQR_STATUS_OK = 0,
+  MQR_STATUS_INVALID_ARGUMENT = -1,
+  MQR_STATUS_DATA_TOO_LONG = -2,
+  MQR_STATUS_ENCODE_FAILED = -3,
+  MQR_STATUS_MISSING_CHUNK = -4,
+  MQR_STATUS_CHECKSUM_MISMATCH = -5,
+  MQR_STATUS_DECODE_FAILED = -6,
+} MqrStatus;
+
+typedef struct MqrBuffer {
+  uint8_t *data;
+  uintptr_t len;
+} MqrBuffer;
+
+typedef struct MqrCode {
+  struct MqrBuffer payload;
+  struct MqrBuffer modules;
+  uintptr_t width;
+} MqrCode;
+
+typedef struct MqrCodes {
+  struct MqrCode *codes;
+  uintptr_t count;
+} MqrCodes;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+enum MqrStatus mqr_encode(const uint8_t *data,
+                          uintptr_t len,
+                          int16_t version,
+                          int32_t ec,
+                          int32_t header,
+                          struct MqrCodes *out);
+
+void mqr_free(struct MqrCodes *codes);
+
+enum MqrStatus mqr_decode(const uint8_t *const *payloads,
+                          const uintptr_t *lens,
+                          uintptr_t count,
+                          int32_t header,
+                          struct MqrBuffer *out);
+
+void mqr_free_buffer(struct MqrBuffer *buffer);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MQRCODE_H */
@@ -0,0 +1,103 @@
+use std::{io::Write, path::Path};
+use image::{RgbaImage, ImageResult, Delay, Frame, codecs::gif::{GifEncoder, Repeat}};
+use crate::MqrError;
+
+// container of an animated export. gif is always available, but its palette and size
+// are poor for dense codes, apng and webp need their features
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnimationFormat {
+    #[default]
+    Gif,
+    #[cfg(feature = "apng")]
+    Apng,
+    #[cfg(feature = "webp")]
+    WebP
+}
+
+impl AnimationFormat {
+    // picked by file extension, .png is taken as apng
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gif" => Some(AnimationFormat::Gif),
+            #[cfg(feature = "apng")]
+            "png" | "apng" => Some(AnimationFormat::Apng),
+            #[cfg(feature = "webp")]
+            "webp" => Some(AnimationFormat::WebP),
+            _ => None
+        }
+    }
+}
+
+// every rendered code as one frame, for screen to camera transfer.
+// fps must be at least 1, looping repeats the animation forever instead of playing it once
+pub(crate) fn write<W: Write>(writer: W, format: AnimationFormat, images: Vec<RgbaImage>, fps: u32, looping: bool) -> Result<(), MqrError> {
+    match format {
+        AnimationFormat::Gif => Ok(write_gif(writer, images, fps, looping)?),
+        #[cfg(feature = "apng")]
+        AnimationFormat::Apng => write_apng(writer, &images, fps, looping),
+        #[cfg(feature = "webp")]
+        AnimationFormat::WebP => write_webp(writer, &images, fps, looping)
+    }
+}
+
+fn write_gif<W: Write>(writer: W, images: Vec<RgbaImage>, fps: u32, looping: bool) -> ImageResult<()> {
+    let mut encoder = GifEncoder::new(writer);
+    encoder.set_repeat(if looping { Repeat::Infinite } else { Repeat::Finite(0) })?;
+    let delay = Delay::from_numer_denom_ms(1000, fps);
+    encoder.encode_frames(images.into_iter().map(|image| Frame::from_parts(image, 0, 0, delay)))
+}
+
+// every frame has to be the size of the first, which only fails for codes of mixed versions from with_layout
+#[cfg(feature = "apng")]
+fn write_apng<W: Write>(writer: W, images: &[RgbaImage], fps: u32, looping: bool) -> Result<(), MqrError> {
+    let (width, height) = images.first().map_or((1, 1), |image| image.dimensions());
+
+    let encoding = |e: png::EncodingError| MqrError::Io(std::io::Error::other(e));
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // 0 plays loops forever
+    encoder.set_animated(images.len().max(1) as u32, if looping { 0 } else { 1 }).map_err(encoding)?;
+    encoder.set_frame_delay(1, fps.min(u16::MAX as u32) as u16).map_err(encoding)?;
+    let mut writer = encoder.write_header().map_err(encoding)?;
+    for image in images {
+        writer.write_image_data(image.as_raw()).map_err(encoding)?;
+    }
+    writer.finish().map_err(encoding)
+}
+
+// the encoder always writes a looping animation, playing it once is patched into the ANIM chunk
+#[cfg(feature = "webp")]
+fn write_webp<W: Write>(mut writer: W, images: &[RgbaImage], fps: u32, looping: bool) -> Result<(), MqrError> {
+    use webp_animation::{Encoder, EncoderOptions};
+    let encoding = |e: webp_animation::Error| MqrError::Io(std::io::Error::other(format!("webp encoding failed: {:?}", e)));
+    let dimensions = images.first().map_or((1, 1), |image| image.dimensions());
+
+    let mut encoder = Encoder::new_with_options(dimensions, EncoderOptions::default()).map_err(encoding)?;
+    // above 1000 fps a frame would last 0 ms, which players treat as a still
+    let frame_ms = (1000 / fps.max(1) as i32).max(1);
+    for (i, image) in images.iter().enumerate() {
+        encoder.add_frame(image.as_raw(), i as i32 * frame_ms).map_err(encoding)?;
+    }
+    let mut data = encoder.finalize(images.len() as i32 * frame_ms).map_err(encoding)?.to_vec();
+    if !looping {
+        set_loop_count(&mut data, 1);
+    }
+    Ok(writer.write_all(&data)?)
+}
+
+// RIFF chunks follow the 12 byte file header, ANIM holds a background color and then the loop
+// count. a single frame is written as a still without one
+#[cfg(feature = "webp")]
+fn set_loop_count(data: &mut [u8], count: u16) {
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let size = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]]) as usize;
+        if &data[pos..pos + 4] == b"ANIM" && size >= 6 && pos + 14 <= data.len() {
+            data[pos + 12..pos + 14].copy_from_slice(&count.to_le_bytes());
+            return
+        }
+        pos = pos.saturating_add(8).saturating_add(size + size % 2);
+    }
+}
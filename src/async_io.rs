@@ -0,0 +1,47 @@
+// async wrappers for services running on tokio. encoding and rendering are cpu bound, so
+// they run on the blocking thread pool instead of stalling the executor
+use std::{io::{self, Read}, pin::Pin, task::{Context, Poll}};
+use futures_core::Stream;
+use qrcode::{EcLevel, QrCode, Version, types::QrError};
+use tokio::sync::mpsc;
+use crate::{MqrError, MultiQrEncoder};
+
+// codes waiting to be taken off a CodeStream, the encoder pauses once this many are ready
+const BUFFERED_CODES: usize = 4;
+
+// the codes of a MultiQrEncoder as a Stream, encoded on the blocking pool a few codes ahead of
+// the consumer. dropping the stream stops the encoding
+pub struct CodeStream {
+    codes: mpsc::Receiver<io::Result<QrCode>>
+}
+
+impl CodeStream {
+    // must be called from within a tokio runtime
+    pub fn new<R: Read + Send + 'static>(source: R, version: Version, ec: EcLevel) -> Result<Self, QrError> {
+        let encoder = MultiQrEncoder::new(source, version, ec)?;
+        let (sender, codes) = mpsc::channel(BUFFERED_CODES);
+        tokio::task::spawn_blocking(move || {
+            for code in encoder {
+                // the receiver was dropped, nobody wants the rest
+                if sender.blocking_send(code).is_err() {
+                    break
+                }
+            }
+        });
+        Ok(CodeStream { codes })
+    }
+}
+
+impl Stream for CodeStream {
+    type Item = io::Result<QrCode>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.codes.poll_recv(cx)
+    }
+}
+
+// runs f on the blocking pool, a panic in f comes back as an io error
+pub(crate) async fn blocking<T, F>(f: F) -> Result<T, MqrError>
+where T: Send + 'static, F: FnOnce() -> Result<T, MqrError> + Send + 'static {
+    tokio::task::spawn_blocking(f).await.map_err(io::Error::from)?
+}
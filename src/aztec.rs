@@ -0,0 +1,305 @@
+// aztec symbols (ISO/IEC 24778), found from the bullseye in the middle so they need no quiet
+// zone. payloads are stored as binary shifts from the upper mode, with at least 23% of the
+// codewords plus 3 spent on error correction
+use qrcode::types::QrError;
+use crate::{BitMatrix, MqrError, SymbolEncoder};
+use crate::reed_solomon::Field;
+
+// binary shift from the upper mode
+const BINARY_SHIFT: u32 = 31;
+// bytes a single binary shift can announce
+const MAX_SHIFT: usize = 31 + 2047;
+
+// every code of a set has the same number of layers, 32 full range ones by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aztec {
+    layers: usize,
+    compact: bool
+}
+
+impl Aztec {
+    // compact symbols have 1 to 4 layers and a smaller bullseye
+    pub fn compact(layers: usize) -> Option<Self> {
+        (1..=4).contains(&layers).then_some(Aztec { layers, compact: true })
+    }
+
+    // full range symbols have 1 to 32 layers
+    pub fn full(layers: usize) -> Option<Self> {
+        (1..=32).contains(&layers).then_some(Aztec { layers, compact: false })
+    }
+
+    // modules per side
+    pub fn size(&self) -> usize {
+        let base = self.base_size();
+        if self.compact { base } else { base + 1 + 2 * ((base / 2 - 1) / 15) }
+    }
+
+    // side without the reference grid lines of full range symbols
+    fn base_size(&self) -> usize {
+        (if self.compact { 11 } else { 14 }) + 4 * self.layers
+    }
+
+    fn total_bits(&self) -> usize {
+        ((if self.compact { 88 } else { 112 }) + 16 * self.layers) * self.layers
+    }
+
+    fn word_size(&self) -> usize {
+        match self.layers {
+            1..=2 => 6,
+            3..=8 => 8,
+            9..=22 => 10,
+            _ => 12
+        }
+    }
+
+    fn field(&self) -> Field {
+        match self.word_size() {
+            6 => Field::new(0x43, 6),
+            8 => Field::new(0x12d, 8),
+            10 => Field::new(0x409, 10),
+            _ => Field::new(0x1069, 12)
+        }
+    }
+
+    // codewords left for data
+    fn data_words(&self) -> usize {
+        let total = self.total_bits() / self.word_size();
+        let words = total - (total * 23 / 100 + 3);
+        // the compact mode message counts data words in 6 bits
+        if self.compact { words.min(64) } else { words }
+    }
+}
+
+impl Default for Aztec {
+    fn default() -> Self {
+        Aztec { layers: 32, compact: false }
+    }
+}
+
+impl SymbolEncoder for Aztec {
+    // bit stuffing can take one bit of every codeword, so only word size - 1 bits each are counted
+    fn capacity(&self) -> usize {
+        let bits = self.data_words() * (self.word_size() - 1);
+        (0..).take_while(|&n| binary_bits(n + 1) <= bits).count()
+    }
+
+    fn quiet_zone(&self) -> usize {
+        0
+    }
+
+    fn encode(&self, payload: &[u8]) -> Result<BitMatrix, MqrError> {
+        if payload.len() > self.capacity() {
+            return Err(QrError::DataTooLong.into())
+        }
+        let mut bits = Vec::with_capacity(binary_bits(payload.len()));
+        for part in payload.chunks(MAX_SHIFT) {
+            push_bits(&mut bits, BINARY_SHIFT, 5);
+            if part.len() <= 31 {
+                push_bits(&mut bits, part.len() as u32, 5);
+            } else {
+                push_bits(&mut bits, 0, 5);
+                push_bits(&mut bits, (part.len() - 31) as u32, 11);
+            }
+            for &byte in part {
+                push_bits(&mut bits, byte as u32, 8);
+            }
+        }
+
+        let word_size = self.word_size();
+        let mut words = stuff(&bits, word_size);
+        // the mode message can't describe a symbol without data, an empty payload gets one pad word
+        if words.is_empty() {
+            words = stuff(&[true], word_size);
+        }
+        let data_words = words.len();
+        let total_words = self.total_bits() / word_size;
+        words.extend(self.field().check_words(&words, total_words - data_words));
+        // leftover bits of the layers go in front of the first codeword
+        let mut message = vec![false; self.total_bits() % word_size];
+        for &word in &words {
+            push_bits(&mut message, word as u32, word_size);
+        }
+
+        let mode = self.mode_message(data_words);
+        Ok(self.draw(&message, &mode))
+    }
+}
+
+impl Aztec {
+    // layers and data codewords, protected by check words over GF(16)
+    fn mode_message(&self, data_words: usize) -> Vec<bool> {
+        let mut bits = Vec::new();
+        let check_words = if self.compact {
+            push_bits(&mut bits, self.layers as u32 - 1, 2);
+            push_bits(&mut bits, data_words as u32 - 1, 6);
+            5
+        } else {
+            push_bits(&mut bits, self.layers as u32 - 1, 5);
+            push_bits(&mut bits, data_words as u32 - 1, 11);
+            6
+        };
+        let words: Vec<u16> = bits.chunks(4).map(|nibble| nibble.iter().fold(0, |acc, &bit| acc << 1 | bit as u16)).collect();
+        for check in Field::new(0x13, 4).check_words(&words, check_words) {
+            push_bits(&mut bits, check as u32, 4);
+        }
+        bits
+    }
+
+    // the layers spiral inwards two modules deep, skipping the reference grid of full range symbols
+    fn draw(&self, message: &[bool], mode: &[bool]) -> BitMatrix {
+        let base = self.base_size();
+        let size = self.size();
+        let center = size / 2;
+        let mut modules = vec![false; size * size];
+        let mut set = |x: usize, y: usize| modules[y * size + x] = true;
+
+        let alignment: Vec<usize> = if self.compact {
+            (0..base).collect()
+        } else {
+            let mut map = vec![0; base];
+            let original = base / 2;
+            for i in 0..original {
+                let offset = i + i / 15;
+                map[original - i - 1] = center - offset - 1;
+                map[original + i] = center + offset + 1;
+            }
+            map
+        };
+
+        let layers = self.layers;
+        let mut row_offset = 0;
+        for i in 0..layers {
+            let row_size = (layers - i) * 4 + if self.compact { 9 } else { 12 };
+            for j in 0..row_size {
+                let column_offset = j * 2;
+                for k in 0..2 {
+                    let bit = |side: usize| message[row_offset + side * row_size * 2 + column_offset + k];
+                    if bit(0) {
+                        set(alignment[i * 2 + k], alignment[i * 2 + j]);
+                    }
+                    if bit(1) {
+                        set(alignment[i * 2 + j], alignment[base - 1 - i * 2 - k]);
+                    }
+                    if bit(2) {
+                        set(alignment[base - 1 - i * 2 - k], alignment[base - 1 - i * 2 - j]);
+                    }
+                    if bit(3) {
+                        set(alignment[base - 1 - i * 2 - j], alignment[i * 2 + k]);
+                    }
+                }
+            }
+            row_offset += row_size * 8;
+        }
+
+        // the mode message runs around the bullseye
+        if self.compact {
+            for i in 0..7 {
+                let offset = center - 3 + i;
+                if mode[i] { set(offset, center - 5) }
+                if mode[i + 7] { set(center + 5, offset) }
+                if mode[20 - i] { set(offset, center + 5) }
+                if mode[27 - i] { set(center - 5, offset) }
+            }
+        } else {
+            for i in 0..10 {
+                let offset = center - 5 + i + i / 5;
+                if mode[i] { set(offset, center - 7) }
+                if mode[i + 10] { set(center + 7, offset) }
+                if mode[29 - i] { set(offset, center + 7) }
+                if mode[39 - i] { set(center - 7, offset) }
+            }
+        }
+
+        let bullseye = if self.compact { 5 } else { 7 };
+        for i in (0..bullseye).step_by(2) {
+            for j in center - i..=center + i {
+                set(j, center - i);
+                set(j, center + i);
+                set(center - i, j);
+                set(center + i, j);
+            }
+        }
+        // orientation marks in three corners of the mode message ring
+        for (x, y) in [(center - bullseye, center - bullseye), (center - bullseye + 1, center - bullseye), (center - bullseye, center - bullseye + 1),
+            (center + bullseye, center - bullseye), (center + bullseye, center - bullseye + 1), (center + bullseye, center + bullseye - 1)] {
+            set(x, y);
+        }
+
+        if !self.compact {
+            // reference grid lines every 16 modules out from the center
+            for j in (0..base / 2 - 1).step_by(15).map(|i| i / 15 * 16) {
+                for k in (center & 1..size).step_by(2) {
+                    set(center - j, k);
+                    set(center + j, k);
+                    set(k, center - j);
+                    set(k, center + j);
+                }
+            }
+        }
+        BitMatrix::from_fn(size, |x, y| modules[y * size + x])
+    }
+}
+
+// bits taken by n bytes of binary shifts
+fn binary_bits(n: usize) -> usize {
+    let shifts = n.div_ceil(MAX_SHIFT);
+    let last = n - shifts.saturating_sub(1) * MAX_SHIFT;
+    let length_bits = if last <= 31 { 5 } else { 16 };
+    8 * n + 10 + (shifts.saturating_sub(1)) * 21 + length_bits - 5
+}
+
+fn push_bits(bits: &mut Vec<bool>, value: u32, count: usize) {
+    bits.extend((0..count).rev().map(|i| value >> i & 1 != 0));
+}
+
+// codewords of word_size bits. a word of all ones or all zeros would be ambiguous, so its last
+// bit is flipped and the bit it replaced starts the next word. the end is padded with ones
+fn stuff(bits: &[bool], word_size: usize) -> Vec<u16> {
+    let mask = (1u16 << word_size) - 2;
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < bits.len() {
+        let word = (0..word_size).fold(0u16, |acc, j| acc << 1 | bits.get(i + j).copied().unwrap_or(true) as u16);
+        if word & mask == mask {
+            words.push(word & mask);
+            i += word_size - 1;
+        } else if word & mask == 0 {
+            words.push(word | 1);
+            i += word_size - 1;
+        } else {
+            words.push(word);
+            i += word_size;
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bit_stuffing() {
+        let ones = vec![true; 12];
+        assert_eq!(stuff(&ones, 6), [0b111110, 0b111110, 0b111110]);
+        let zeros = vec![false; 6];
+        assert_eq!(stuff(&zeros, 6), [0b000001, 0b011111]);
+        assert_eq!(binary_bits(31), 10 + 8 * 31);
+        assert_eq!(binary_bits(32), 21 + 8 * 32);
+        assert_eq!(binary_bits(MAX_SHIFT + 1), 21 + 10 + 8 * (MAX_SHIFT + 1));
+    }
+
+    // the vectors of zxing's EncoderTest.testModeMessage
+    #[test]
+    fn known_mode_messages() {
+        for (aztec, data_words, expected) in [
+            (Aztec::compact(2), 29, ".X .XXX.. ...X XX.. ..X .XX. .XX.X"),
+            (Aztec::compact(4), 64, "XX XXXXXX .X.. ...X ..XX .X.. XX.."),
+            (Aztec::full(21), 660, "X.X.. .X.X..X..XX .XXX ..X.. .XXX. .X... ..XXX"),
+            (Aztec::full(32), 4096, "XXXXX XXXXXXXXXXX X.X. ..... XXX.X ..X.. X.XXX")
+        ] {
+            let expected: Vec<bool> = expected.chars().filter(|&c| c != ' ').map(|c| c == 'X').collect();
+            assert_eq!(aztec.unwrap().mode_message(data_words), expected);
+        }
+    }
+}
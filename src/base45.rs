@@ -0,0 +1,47 @@
+// base45 (RFC 9285). two bytes become three characters of the qr alphanumeric set, so base45
+// text stored in alphanumeric mode costs 16.5 bits per two bytes against 21.3 for base64 in byte mode
+const ALPHABET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(2) * 3);
+    for pair in bytes.chunks(2) {
+        let mut n = pair.iter().fold(0usize, |n, &b| n << 8 | b as usize);
+        // a trailing single byte takes two characters instead of three
+        for _ in 0..pair.len() + 1 {
+            text.push(ALPHABET[n % 45] as char);
+            n /= 45;
+        }
+    }
+    text
+}
+
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    let values: Vec<usize> = text.bytes().map(|c| ALPHABET.iter().position(|&a| a == c)).collect::<Option<_>>()?;
+    let mut bytes = Vec::with_capacity(values.len() / 3 * 2 + 1);
+    for group in values.chunks(3) {
+        let n = group.iter().rev().fold(0, |n, &v| n * 45 + v);
+        match group.len() {
+            3 if n <= 0xffff => bytes.extend_from_slice(&(n as u16).to_be_bytes()),
+            2 if n <= 0xff => bytes.push(n as u8),
+            _ => return None
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc_vectors() {
+        assert_eq!(encode(b"AB"), "BB8");
+        assert_eq!(encode(b"Hello!!"), "%69 VD92EX0");
+        assert_eq!(encode(b"base-45"), "UJCLQE7W581");
+        assert_eq!(decode("QED8WEX0").unwrap(), b"ietf!");
+        assert_eq!(decode("BB8").unwrap(), b"AB");
+        assert!(decode("GGW").is_none());
+        assert!(decode("ab").is_none());
+        assert!(decode("A").is_none());
+    }
+}
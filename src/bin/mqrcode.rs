@@ -0,0 +1,154 @@
+use std::{env, fs, io::{self, Write}, path::{Path, PathBuf}, process::ExitCode};
+use mqrcode::{decode, HeaderFormat, IndexWidth, MultiQrCode, RenderOptions, SaveOptions, WireFormat};
+use qrcode::{EcLevel, Version};
+
+const USAGE: &str = "usage:
+  mqrcode encode <file> [--version N] [--micro N] [--ec L|M|Q|H] [--header legacy|compact|extended|versioned]
+                        [--manifest] [--invert] [--format png|jpg|bmp|tiff] [--out DIR]
+  mqrcode decode <images...> [--header legacy|compact|extended|versioned] [--manifest] [--out FILE]
+
+encode writes one image per code into DIR (default: current directory).
+--invert draws light modules on a dark background.
+decode scans every image and writes the data to FILE, or stdout without --out.";
+
+// every flag takes a value except the ones listed here
+const SWITCHES: [&str; 2] = ["--manifest", "--invert"];
+
+struct Args {
+    positional: Vec<String>,
+    flags: Vec<(String, Option<String>)>
+}
+
+impl Args {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut parsed = Args { positional: Vec::new(), flags: Vec::new() };
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg);
+            } else if SWITCHES.contains(&arg.as_str()) {
+                parsed.flags.push((arg, None));
+            } else {
+                let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                parsed.flags.push((arg, Some(value)));
+            }
+        }
+        Ok(parsed)
+    }
+
+    // the last value given for flag
+    fn value(&self, flag: &str) -> Option<&str> {
+        self.flags.iter().rev().find(|(name, _)| name == flag).and_then(|(_, value)| value.as_deref())
+    }
+
+    fn switch(&self, flag: &str) -> bool {
+        self.flags.iter().any(|(name, _)| name == flag)
+    }
+
+    fn check(&self, known: &[&str]) -> Result<(), String> {
+        match self.flags.iter().find(|(name, _)| !known.contains(&name.as_str())) {
+            Some((name, _)) => Err(format!("unknown option {}", name)),
+            None => Ok(())
+        }
+    }
+
+    fn header(&self) -> Result<HeaderFormat, String> {
+        match self.value("--header").unwrap_or("legacy") {
+            "legacy" => Ok(HeaderFormat::Legacy),
+            "compact" => Ok(HeaderFormat::Compact),
+            "extended" => Ok(HeaderFormat::Extended(IndexWidth::U16)),
+            "versioned" => Ok(HeaderFormat::Versioned(WireFormat::LATEST)),
+            other => Err(format!("unknown header format {}", other))
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("encode") => Args::parse(args).and_then(encode),
+        Some("decode") => Args::parse(args).and_then(decode),
+        Some("-h" | "--help" | "help") => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS
+        }
+        _ => Err(USAGE.to_string())
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("mqrcode: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn encode(args: Args) -> Result<(), String> {
+    args.check(&["--version", "--micro", "--ec", "--header", "--manifest", "--invert", "--format", "--out"])?;
+    let [file] = args.positional.as_slice() else {
+        return Err(format!("encode takes exactly one file\n\n{}", USAGE))
+    };
+    let version = match (args.value("--version"), args.value("--micro")) {
+        (Some(_), Some(_)) => return Err("--version and --micro can't be combined".to_string()),
+        (Some(n), None) => Version::Normal(parse_number(n, 1..=40)?),
+        (None, Some(n)) => Version::Micro(parse_number(n, 1..=4)?),
+        (None, None) => mqrcode::DEFAULT_QR_VERSION
+    };
+    let ec = match args.value("--ec").unwrap_or("L") {
+        "L" | "l" => EcLevel::L,
+        "M" | "m" => EcLevel::M,
+        "Q" | "q" => EcLevel::Q,
+        "H" | "h" => EcLevel::H,
+        other => return Err(format!("unknown ec level {}", other))
+    };
+    let format = args.value("--format").unwrap_or("png");
+    let out = PathBuf::from(args.value("--out").unwrap_or("."));
+
+    let data = fs::read(file).map_err(|e| format!("can't read {}: {}", file, e))?;
+    // sized by the actual encoded length, so text can't overflow a code the way fixed slack can
+    let mut builder = MultiQrCode::builder().version(version).ec_level(ec).header(args.header()?).optimize_segments(true);
+    if args.switch("--manifest") {
+        builder = builder.manifest(true);
+        if let Some(name) = Path::new(file).file_name().and_then(|name| name.to_str()) {
+            builder = builder.filename(name);
+        }
+    }
+    let qr = builder.build(&data).map_err(|e| e.to_string())?;
+    fs::create_dir_all(&out).map_err(|e| format!("can't create {}: {}", out.display(), e))?;
+    let options = SaveOptions { render: RenderOptions { invert: args.switch("--invert"), ..Default::default() }, ..Default::default() };
+    qr.save_with(out.join(format!("qr.{}", format)), &options).map_err(|e| e.to_string())?;
+    eprintln!("wrote {} codes to {}", qr.len(), out.display());
+    Ok(())
+}
+
+fn decode(args: Args) -> Result<(), String> {
+    args.check(&["--header", "--manifest", "--out"])?;
+    if args.positional.is_empty() {
+        return Err(format!("decode takes at least one image\n\n{}", USAGE))
+    }
+    let mut payloads = Vec::new();
+    for path in &args.positional {
+        let image = image::open(path).map_err(|e| format!("can't open {}: {}", path, e))?.to_luma8();
+        let found = decode::scan(&image);
+        if found.is_empty() {
+            return Err(format!("no readable qr code in {}", path))
+        }
+        payloads.extend(found);
+    }
+
+    let data = if args.switch("--manifest") {
+        let (manifest, data) = decode::reassemble_manifest(&payloads).map_err(|e| e.to_string())?;
+        eprintln!("{}", manifest);
+        data
+    } else {
+        decode::reassemble_with(&payloads, args.header()?).map_err(|e| e.to_string())?
+    };
+    match args.value("--out") {
+        Some(out) => fs::write(out, &data).map_err(|e| format!("can't write {}: {}", out, e)),
+        None => io::stdout().write_all(&data).map_err(|e| e.to_string())
+    }
+}
+
+fn parse_number(value: &str, range: std::ops::RangeInclusive<i16>) -> Result<i16, String> {
+    value.parse().ok().filter(|n| range.contains(n))
+        .ok_or_else(|| format!("{} is not a version from {} to {}", value, range.start(), range.end()))
+}
@@ -0,0 +1,269 @@
+use std::{fmt, sync::Arc};
+use qrcode::{Version, EcLevel};
+#[cfg(feature = "sha256")]
+use crate::digest;
+use crate::{charset, header, parity, stripe, Manifest, MultiQrCode, MqrError, Payload, ProgressSink, EncodeOptions, HeaderFormat, Compression, Encryption, Integrity, QrEncoder, SymbolEncoder, SymbolSet, DEFAULT_QR_VERSION, DEFAULT_EC_LEVEL};
+
+// every encoding option in one place, starting from the same defaults as MultiQrCode::default.
+// rendering stays per export, see SaveOptions
+#[derive(Clone)]
+pub struct MultiQrCodeBuilder {
+    version: Version,
+    ec: EcLevel,
+    // None uses byte_mode_slack for the version
+    slack: Option<usize>,
+    options: EncodeOptions,
+    progress: Option<Arc<dyn ProgressSink>>
+}
+
+impl Default for MultiQrCodeBuilder {
+    fn default() -> Self {
+        MultiQrCodeBuilder { version: DEFAULT_QR_VERSION, ec: DEFAULT_EC_LEVEL, slack: None, options: EncodeOptions::default(), progress: None }
+    }
+}
+
+impl fmt::Debug for MultiQrCodeBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiQrCodeBuilder")
+            .field("version", &self.version)
+            .field("ec", &self.ec)
+            .field("slack", &self.slack)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MultiQrCodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn ec_level(mut self, ec: EcLevel) -> Self {
+        self.ec = ec;
+        self
+    }
+
+    pub fn slack(mut self, slack: usize) -> Self {
+        self.slack = Some(slack);
+        self
+    }
+
+    pub fn header(mut self, header: HeaderFormat) -> Self {
+        self.options.header = header;
+        self
+    }
+
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.options.compression = compression;
+        self
+    }
+
+    pub fn encryption(mut self, encryption: Encryption) -> Self {
+        self.options.encryption = encryption;
+        self
+    }
+
+    pub fn boost_ec(mut self, boost_ec: bool) -> Self {
+        self.options.boost_ec = boost_ec;
+        self
+    }
+
+    pub fn utf8_eci(mut self, utf8_eci: bool) -> Self {
+        self.options.utf8_eci = utf8_eci;
+        self
+    }
+
+    pub fn kanji(mut self, kanji: bool) -> Self {
+        self.options.kanji = kanji;
+        self
+    }
+
+    pub fn chunk_crc(mut self, chunk_crc: bool) -> Self {
+        self.options.chunk_crc = chunk_crc;
+        self
+    }
+
+    // sets chunk_crc and sha256 together, both need a header format with flags
+    pub fn integrity(mut self, integrity: Integrity) -> Self {
+        self.options.chunk_crc = integrity.chunk_crc();
+        self.options.sha256 = integrity.sha256();
+        self
+    }
+
+    pub fn parity(mut self, parity: usize) -> Self {
+        self.options.parity = parity;
+        self
+    }
+
+    pub fn striped(mut self, striped: bool) -> Self {
+        self.options.striped = striped;
+        self
+    }
+
+    pub fn manifest(mut self, manifest: bool) -> Self {
+        self.options.manifest = manifest;
+        self
+    }
+
+    pub fn filename<S: Into<String>>(mut self, filename: S) -> Self {
+        self.options.filename = Some(filename.into());
+        self
+    }
+
+    pub fn sha256(mut self, sha256: bool) -> Self {
+        self.options.sha256 = sha256;
+        self
+    }
+
+    // data bytes per code instead of as many as fit, see EncodeOptions::chunk_size
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.options.chunk_size = Some(size);
+        self
+    }
+
+    pub fn optimize_segments(mut self, optimize_segments: bool) -> Self {
+        self.options.optimize_segments = optimize_segments;
+        self
+    }
+
+    pub fn logo(mut self, logo: bool) -> Self {
+        self.options.logo = logo;
+        self
+    }
+
+    // told about every code built, see ProgressSink
+    pub fn progress<S: ProgressSink + 'static>(mut self, sink: S) -> Self {
+        self.progress = Some(Arc::new(sink));
+        self
+    }
+
+    // replaces every EncodeOptions field at once
+    pub fn options(mut self, options: EncodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    // the payload's envelope and data, read back with MultiQrCode::reassemble_payload
+    pub fn build_payload(&self, payload: &Payload) -> Result<MultiQrCode, MqrError> {
+        self.build(payload.to_bytes())
+    }
+
+    pub fn build<D: AsRef<[u8]>>(&self, data: D) -> Result<MultiQrCode, MqrError> {
+        let slack = self.slack.unwrap_or(crate::byte_mode_slack(self.version));
+        let encoder = QrEncoder::with_options(self.version, self.ec, slack, &self.options);
+        self.encode_with(data, |data, flags, manifest| {
+            MultiQrCode::encode_with_progress(data, &encoder, &self.options, flags, manifest, self.progress.as_deref())
+        })
+    }
+
+    // build for another symbology, see SymbolEncoder. version, ec level and slack are the
+    // encoder's, and the options that shape the qr codes themselves (boost_ec, optimize_segments,
+    // kanji, utf8_eci and logo) fail with InvalidOptions. a QrEncoder has its own boost_ec and utf8_eci
+    pub fn build_symbols<D: AsRef<[u8]>, E: SymbolEncoder + ?Sized>(&self, data: D, encoder: &E) -> Result<SymbolSet, MqrError> {
+        let options = &self.options;
+        if options.boost_ec || options.optimize_segments || options.kanji || options.utf8_eci || options.logo {
+            return Err(MqrError::InvalidOptions("boost_ec, optimize_segments, kanji, utf8_eci and logo only apply to the qr codes of build"))
+        }
+        self.encode_with(data, |data, flags, manifest| {
+            let payloads = crate::encode_payloads(data, encoder, options, flags, manifest)?;
+            SymbolSet::encode(payloads, encoder, self.progress.as_deref())
+        })
+    }
+
+    // checks the options and applies them to data, then hands it to encode with the header
+    // flags and the manifest, if any, that go with it
+    fn encode_with<D, T, F>(&self, data: D, encode: F) -> Result<T, MqrError>
+    where D: AsRef<[u8]>, F: FnOnce(&[u8], u8, Option<Manifest>) -> Result<T, MqrError> {
+        let options = &self.options;
+        // the manifest holds the flags instead of the chunk headers
+        let has_flags = options.header.has_flags() || options.manifest;
+        if options.manifest && (options.header != HeaderFormat::Legacy || options.chunk_crc || options.parity > 0) {
+            return Err(MqrError::InvalidOptions("a manifest replaces the chunk headers, so it needs the legacy header and no chunk crcs or parity"))
+        }
+        let length = data.as_ref().len();
+        #[cfg(not(feature = "sha256"))]
+        if options.sha256 {
+            return Err(MqrError::InvalidOptions("sha-256 digests need the sha256 feature"))
+        }
+        #[cfg(not(feature = "kanji"))]
+        if options.kanji {
+            return Err(MqrError::InvalidOptions("kanji mode needs the kanji feature"))
+        }
+        #[cfg(feature = "sha256")]
+        let digest = if options.sha256 {
+            if options.encryption != Encryption::None {
+                return Err(MqrError::InvalidOptions("encrypted data is already authenticated, a sha-256 digest would only leak information about it"))
+            }
+            if !has_flags {
+                return Err(MqrError::InvalidOptions("sha-256 digests need a header format with flags"))
+            }
+            Some(digest::sha256(data.as_ref()))
+        } else {
+            None
+        };
+        let flags = options.compression.flag() | options.encryption.flag();
+        if flags != 0 && !has_flags {
+            return Err(MqrError::InvalidOptions("compression and encryption need a header format with flags"))
+        }
+
+        #[cfg(feature = "kanji")]
+        let (data, flags) = if options.kanji {
+            if flags != 0 || options.utf8_eci {
+                return Err(MqrError::InvalidOptions("kanji mode can't be combined with compression, encryption or utf-8 eci"))
+            }
+            if !has_flags {
+                return Err(MqrError::InvalidOptions("kanji mode needs a header format with flags"))
+            }
+            (std::borrow::Cow::Owned(charset::to_shift_jis(data.as_ref())?), charset::FLAG_SHIFT_JIS)
+        } else {
+            (std::borrow::Cow::Borrowed(data.as_ref()), flags)
+        };
+
+        let flags = flags | if options.chunk_crc { header::FLAG_CHUNK_CRC } else { 0 };
+        if options.chunk_crc && !options.header.has_flags() {
+            return Err(MqrError::InvalidOptions("chunk crcs need a header format with flags"))
+        }
+
+        let flags = flags | if options.parity > 0 { parity::FLAG_PARITY } else { 0 };
+        if options.parity > 0 && !options.header.has_flags() {
+            return Err(MqrError::InvalidOptions("parity codes need a header format with flags"))
+        }
+        if options.parity > 0 && (options.optimize_segments || charset::is_shift_jis(flags)) {
+            return Err(MqrError::InvalidOptions("parity codes need evenly sized chunks, which optimized segments don't give"))
+        }
+
+        let flags = flags | if options.striped { stripe::FLAG_STRIPED } else { 0 };
+        if options.striped && (!options.header.has_flags() || options.optimize_segments || charset::is_shift_jis(flags)) {
+            return Err(MqrError::InvalidOptions("striped chunks need a header format with flags and evenly sized chunks, which optimized segments don't give"))
+        }
+
+        if options.chunk_size == Some(0) {
+            return Err(MqrError::InvalidOptions("chunk_size must be at least 1"))
+        }
+        if options.chunk_size.is_some() && (options.optimize_segments || charset::is_shift_jis(flags)) {
+            return Err(MqrError::InvalidOptions("a fixed chunk size can't be combined with optimized segments, which size chunks by their encoded length"))
+        }
+
+        let data = options.compression.compress(data.as_ref())?;
+        let data = options.encryption.encrypt(data)?;
+        #[cfg(feature = "sha256")]
+        let (data, flags) = match digest {
+            Some(digest) => (std::borrow::Cow::Owned([&digest[..], &data].concat()), flags | digest::FLAG_SHA256),
+            None => (data, flags)
+        };
+        let manifest = options.manifest.then(|| Manifest {
+            length,
+            flags,
+            #[cfg(feature = "sha256")]
+            sha256: digest,
+            filename: options.filename.clone(),
+            ..Default::default()
+        });
+        encode(&data, flags, manifest)
+    }
+}
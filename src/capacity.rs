@@ -0,0 +1,48 @@
+// data capacity of every symbol in bytes for qrcode's versions and ec levels, the table itself
+// is in mqrcode-core
+use qrcode::{EcLevel, Version};
+use mqrcode_core::capacity;
+
+pub struct Capacity;
+
+impl Capacity {
+    // 0 for versions that don't exist and for ec levels a Micro version doesn't have
+    pub fn for_(version: Version, ec: EcLevel) -> usize {
+        match version {
+            Version::Normal(n) => capacity::normal(n.max(0) as usize, ec as usize),
+            Version::Micro(n) => capacity::micro(n.max(0) as usize, ec as usize)
+        }
+    }
+}
+
+pub(crate) use mqrcode_core::capacity::DATA_LENGTHS;
+
+#[cfg(test)]
+mod tests {
+    use qrcode::bits::Bits;
+    use super::*;
+
+    #[test]
+    fn iso_table() {
+        assert_eq!(Capacity::for_(Version::Normal(1), EcLevel::L), 19);
+        assert_eq!(Capacity::for_(Version::Normal(40), EcLevel::H), 1276);
+        // M1 and M3 end in a half codeword, which doesn't count
+        assert_eq!(Capacity::for_(Version::Micro(1), EcLevel::L), 2);
+        assert_eq!(Capacity::for_(Version::Micro(3), EcLevel::M), 8);
+        assert_eq!(Capacity::for_(Version::Micro(4), EcLevel::Q), 10);
+        assert_eq!(Capacity::for_(Version::Micro(2), EcLevel::Q), 0);
+        assert_eq!(Capacity::for_(Version::Micro(4), EcLevel::H), 0);
+        for version in [Version::Normal(0), Version::Normal(41), Version::Micro(0), Version::Micro(5), Version::Normal(-1)] {
+            assert_eq!(Capacity::for_(version, EcLevel::L), 0);
+        }
+
+        // qrcode has the same table in bits
+        let versions = (1..=40).map(Version::Normal).chain((1..=4).map(Version::Micro));
+        for version in versions {
+            for ec in [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+                let bits = Bits::new(version).max_len(ec).unwrap_or(0);
+                assert_eq!(Capacity::for_(version, ec), bits / 8, "{:?} {:?}", version, ec);
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{DecodeError, compression};
+#[cfg(feature = "kanji")]
+use crate::MqrError;
+
+// set when the data is utf-8 text that was stored as shift jis so kanji mode could be used. a
+// value of the compression bits rather than a bit of its own, kanji data is never compressed
+pub(crate) const FLAG_SHIFT_JIS: u8 = 0b0000_0011;
+
+// ECI assignment number of utf-8
+pub(crate) const ECI_UTF8: u32 = 26;
+
+// kanji mode only covers shift jis, so utf-8 text is converted before chunking
+#[cfg(feature = "kanji")]
+pub(crate) fn to_shift_jis(data: &[u8]) -> Result<Vec<u8>, MqrError> {
+    let text = std::str::from_utf8(data).map_err(|_| MqrError::InvalidOptions("kanji mode needs utf-8 text"))?;
+    let (encoded, _, unmappable) = encoding_rs::SHIFT_JIS.encode(text);
+    if unmappable {
+        return Err(MqrError::InvalidOptions("kanji mode needs text that shift jis can represent"))
+    }
+    Ok(encoded.into_owned())
+}
+
+pub(crate) fn is_shift_jis(flags: u8) -> bool {
+    flags & compression::FLAG_MASK == FLAG_SHIFT_JIS
+}
+
+// undo the shift jis conversion if the header flags say it was applied
+pub(crate) fn decode(flags: u8, data: Vec<u8>) -> Result<Vec<u8>, DecodeError> {
+    if !is_shift_jis(flags) {
+        return Ok(data)
+    }
+    #[cfg(feature = "kanji")]
+    {
+        encoding_rs::SHIFT_JIS.decode_without_bom_handling_and_without_replacement(&data)
+            .map(|text| text.into_owned().into_bytes())
+            .ok_or(DecodeError::Malformed)
+    }
+    #[cfg(not(feature = "kanji"))]
+    Err(DecodeError::Unsupported)
+}
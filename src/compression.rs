@@ -0,0 +1,74 @@
+use std::{borrow::Cow, io};
+use crate::{DecodeError, charset};
+
+// bits of the header flags byte holding the compression method: 1 is deflate, 2 is zstd and 3
+// is shift jis text, see charset::FLAG_SHIFT_JIS
+pub(crate) const FLAG_MASK: u8 = 0b0000_0011;
+
+// most bytes a compressed payload may inflate to. the header doesn't store the original length,
+// and a few codes of zeros would otherwise expand into gigabytes
+pub const MAX_DECOMPRESSED_SIZE: usize = 64 << 20;
+
+// applied to the whole payload before it is split into chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    #[cfg(feature = "deflate")]
+    Deflate,
+    #[cfg(feature = "zstd")]
+    Zstd
+}
+
+impl Compression {
+    pub(crate) fn flag(&self) -> u8 {
+        match self {
+            Compression::None => 0,
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => 1,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => 2
+        }
+    }
+
+    pub(crate) fn compress<'a>(&self, data: &'a [u8]) -> io::Result<Cow<'a, [u8]>> {
+        match self {
+            Compression::None => Ok(Cow::Borrowed(data)),
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => {
+                use std::io::Write;
+                let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(data)?;
+                encoder.finish().map(Cow::Owned)
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::stream::encode_all(data, 19).map(Cow::Owned)
+        }
+    }
+}
+
+// undo whatever compression the header flags say was applied. Malformed past MAX_DECOMPRESSED_SIZE
+pub(crate) fn decompress(flags: u8, data: Vec<u8>) -> Result<Vec<u8>, DecodeError> {
+    match flags & FLAG_MASK {
+        // shift jis is undone by charset::decode
+        0 | charset::FLAG_SHIFT_JIS => Ok(data),
+        #[cfg(feature = "deflate")]
+        1 => read_bounded(flate2::read::DeflateDecoder::new(&data[..])),
+        #[cfg(feature = "zstd")]
+        2 => read_bounded(zstd::stream::read::Decoder::new(&data[..]).map_err(|_| DecodeError::Decompression)?),
+        // compressed with a method this build doesn't have
+        _ => Err(DecodeError::Unsupported)
+    }
+}
+
+// reads one byte past the limit to tell a full buffer from a longer stream
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+fn read_bounded<R: io::Read>(reader: R) -> Result<Vec<u8>, DecodeError> {
+    use std::io::Read;
+    let mut res = Vec::new();
+    reader.take(MAX_DECOMPRESSED_SIZE as u64 + 1).read_to_end(&mut res).map_err(|_| DecodeError::Decompression)?;
+    if res.len() > MAX_DECOMPRESSED_SIZE {
+        return Err(DecodeError::Malformed)
+    }
+    Ok(res)
+}
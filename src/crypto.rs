@@ -0,0 +1,148 @@
+use std::{borrow::Cow, fmt};
+use crate::MqrError;
+#[cfg(feature = "aes-gcm")]
+use crate::DecodeError;
+
+// header flags bit marking the payload as AES-256-GCM encrypted
+pub(crate) const FLAG_AES_GCM: u8 = 0b0000_0100;
+// set together with FLAG_AES_GCM when the key was derived from a passphrase
+pub(crate) const FLAG_PASSPHRASE: u8 = 0b0000_1000;
+
+// size of the nonce stored in front of the ciphertext, the 16 byte auth tag follows the ciphertext
+pub const NONCE_SIZE: usize = 12;
+pub const SALT_SIZE: usize = 16;
+// m_cost, t_cost and p_cost as u32 big endian, then the salt
+pub const KDF_HEADER_SIZE: usize = 3 * 4 + SALT_SIZE;
+
+// argon2id cost parameters, stored next to the salt so only the passphrase is needed to decrypt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    // memory in KiB
+    pub m_cost: u32,
+    // iterations
+    pub t_cost: u32,
+    // parallelism
+    pub p_cost: u32
+}
+
+// the argon2 crate's defaults, OWASP's recommended minimum for argon2id
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams { m_cost: 19 * 1024, t_cost: 2, p_cost: 1 }
+    }
+}
+
+// the most a set may ask of the decoding side, 1 GiB of memory, 64 passes and 16 lanes. the
+// costs are read from the scanned data, so without a cap a forged header could ask for
+// terabytes or hours of hashing before the passphrase is even checked
+#[cfg(feature = "passphrase")]
+pub const MAX_KDF_PARAMS: KdfParams = KdfParams { m_cost: 1 << 20, t_cost: 64, p_cost: 16 };
+
+// applied after compression, right before the payload is split into chunks.
+// the nonce (and kdf parameters and salt for passphrases) is stored at the very start of the stream,
+// so it always lands in the first chunk
+#[derive(Clone, PartialEq, Eq, Default)]
+pub enum Encryption {
+    #[default]
+    None,
+    #[cfg(feature = "aes-gcm")]
+    AesGcm { key: [u8; 32] },
+    #[cfg(feature = "passphrase")]
+    Passphrase { passphrase: String, kdf: KdfParams }
+}
+
+// keys stay out of debug output
+impl fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encryption::None => write!(f, "None"),
+            #[cfg(feature = "aes-gcm")]
+            Encryption::AesGcm { .. } => write!(f, "AesGcm {{ .. }}"),
+            #[cfg(feature = "passphrase")]
+            Encryption::Passphrase { kdf, .. } => write!(f, "Passphrase {{ kdf: {:?}, .. }}", kdf)
+        }
+    }
+}
+
+impl Encryption {
+    pub(crate) fn flag(&self) -> u8 {
+        match self {
+            Encryption::None => 0,
+            #[cfg(feature = "aes-gcm")]
+            Encryption::AesGcm { .. } => FLAG_AES_GCM,
+            #[cfg(feature = "passphrase")]
+            Encryption::Passphrase { .. } => FLAG_AES_GCM | FLAG_PASSPHRASE
+        }
+    }
+
+    pub(crate) fn encrypt<'a>(&self, data: Cow<'a, [u8]>) -> Result<Cow<'a, [u8]>, MqrError> {
+        match self {
+            Encryption::None => Ok(data),
+            #[cfg(feature = "aes-gcm")]
+            Encryption::AesGcm { key } => seal(key, &data).map(Cow::Owned),
+            #[cfg(feature = "passphrase")]
+            Encryption::Passphrase { passphrase, kdf } => {
+                use aes_gcm::aead::{OsRng, rand_core::RngCore};
+                let mut salt = [0u8; SALT_SIZE];
+                OsRng.fill_bytes(&mut salt);
+                let key = derive_key(passphrase, &salt, kdf).ok_or(MqrError::Encryption)?;
+
+                let mut res = Vec::with_capacity(KDF_HEADER_SIZE + NONCE_SIZE + data.len() + 16);
+                for cost in [kdf.m_cost, kdf.t_cost, kdf.p_cost] {
+                    res.extend_from_slice(&cost.to_be_bytes());
+                }
+                res.extend_from_slice(&salt);
+                res.extend(seal(&key, &data)?);
+                Ok(Cow::Owned(res))
+            }
+        }
+    }
+}
+
+// nonce followed by the ciphertext and tag
+#[cfg(feature = "aes-gcm")]
+fn seal(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, MqrError> {
+    use aes_gcm::{Aes256Gcm, aead::{Aead, AeadCore, KeyInit, OsRng}};
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(key.into()).encrypt(&nonce, data).map_err(|_| MqrError::Encryption)?;
+    let mut res = Vec::with_capacity(NONCE_SIZE + ciphertext.len());
+    res.extend_from_slice(&nonce);
+    res.extend(ciphertext);
+    Ok(res)
+}
+
+#[cfg(feature = "passphrase")]
+// None for costs argon2 rejects or above MAX_KDF_PARAMS
+fn derive_key(passphrase: &str, salt: &[u8], kdf: &KdfParams) -> Option<[u8; 32]> {
+    if kdf.m_cost > MAX_KDF_PARAMS.m_cost || kdf.t_cost > MAX_KDF_PARAMS.t_cost || kdf.p_cost > MAX_KDF_PARAMS.p_cost {
+        return None
+    }
+    let params = argon2::Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, Some(32)).ok()?;
+    let mut key = [0u8; 32];
+    argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key).ok()?;
+    Some(key)
+}
+
+// reverse of Encryption::AesGcm, a wrong key and tampered data look the same
+#[cfg(feature = "aes-gcm")]
+pub(crate) fn decrypt(data: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, DecodeError> {
+    use aes_gcm::{Aes256Gcm, Nonce, aead::{Aead, KeyInit}};
+    if data.len() < NONCE_SIZE {
+        return Err(DecodeError::Malformed)
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_SIZE);
+    Aes256Gcm::new(key.into()).decrypt(Nonce::from_slice(nonce), ciphertext).map_err(|_| DecodeError::Decryption)
+}
+
+// reverse of Encryption::Passphrase, reading the kdf parameters and salt from the stream
+#[cfg(feature = "passphrase")]
+pub(crate) fn decrypt_passphrase(data: &[u8], passphrase: &str) -> Result<Vec<u8>, DecodeError> {
+    if data.len() < KDF_HEADER_SIZE {
+        return Err(DecodeError::Malformed)
+    }
+    let cost = |i: usize| u32::from_be_bytes([data[4*i], data[4*i+1], data[4*i+2], data[4*i+3]]);
+    let kdf = KdfParams { m_cost: cost(0), t_cost: cost(1), p_cost: cost(2) };
+    let key = derive_key(passphrase, &data[12..KDF_HEADER_SIZE], &kdf).ok_or(DecodeError::Malformed)?;
+    decrypt(&data[KDF_HEADER_SIZE..], &key)
+}
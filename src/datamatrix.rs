@@ -0,0 +1,285 @@
+// square ecc200 data matrix symbols (ISO/IEC 16022), preferred by many industrial scanners and
+// small enough for tight labels. payloads are stored as a single base 256 field
+use qrcode::types::QrError;
+use crate::{BitMatrix, MqrError, SymbolEncoder};
+use crate::reed_solomon::Field;
+
+// symbol side, data region side, data codewords, check codewords and interleaved blocks of
+// every square size
+const SYMBOLS: [(usize, usize, usize, usize, usize); 24] = [
+    (10, 8, 3, 5, 1), (12, 10, 5, 7, 1), (14, 12, 8, 10, 1), (16, 14, 12, 12, 1),
+    (18, 16, 18, 14, 1), (20, 18, 22, 18, 1), (22, 20, 30, 20, 1), (24, 22, 36, 24, 1),
+    (26, 24, 44, 28, 1), (32, 14, 62, 36, 1), (36, 16, 86, 42, 1), (40, 18, 114, 48, 1),
+    (44, 20, 144, 56, 1), (48, 22, 174, 68, 1), (52, 24, 204, 84, 2), (64, 14, 280, 112, 2),
+    (72, 16, 368, 144, 4), (80, 18, 456, 192, 4), (88, 20, 576, 224, 4), (96, 22, 696, 272, 4),
+    (104, 24, 816, 336, 6), (120, 18, 1050, 408, 6), (132, 20, 1304, 496, 8), (144, 22, 1558, 620, 10)
+];
+
+const LATCH_BASE256: u8 = 231;
+const PAD: u8 = 129;
+
+// every code of a set is drawn at the same size, 144 by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataMatrix {
+    symbol: usize
+}
+
+impl DataMatrix {
+    // None for sizes ecc200 doesn't have as a square, they go from 10 to 144 modules
+    pub fn new(size: usize) -> Option<Self> {
+        SYMBOLS.iter().position(|&(side, ..)| side == size).map(|symbol| DataMatrix { symbol })
+    }
+
+    // modules per side
+    pub fn size(&self) -> usize {
+        SYMBOLS[self.symbol].0
+    }
+}
+
+impl Default for DataMatrix {
+    fn default() -> Self {
+        DataMatrix { symbol: SYMBOLS.len() - 1 }
+    }
+}
+
+impl SymbolEncoder for DataMatrix {
+    // the base 256 latch and its one or two length bytes come off the data codewords
+    fn capacity(&self) -> usize {
+        let data = SYMBOLS[self.symbol].2;
+        if data - 2 <= 249 { data - 2 } else { data - 3 }
+    }
+
+    fn quiet_zone(&self) -> usize {
+        1
+    }
+
+    fn encode(&self, payload: &[u8]) -> Result<BitMatrix, MqrError> {
+        if payload.len() > self.capacity() {
+            return Err(QrError::DataTooLong.into())
+        }
+        let data_words = SYMBOLS[self.symbol].2;
+        let mut words = vec![LATCH_BASE256];
+        let mut field = Vec::with_capacity(2 + payload.len());
+        if payload.len() <= 249 {
+            field.push(payload.len() as u8);
+        } else {
+            field.extend([(payload.len() / 250 + 249) as u8, (payload.len() % 250) as u8]);
+        }
+        field.extend_from_slice(payload);
+        // base 256 bytes are scrambled by their 1 based position in the codewords
+        for byte in field {
+            let position = words.len() + 1;
+            words.push(randomize_255(byte, position));
+        }
+        if words.len() < data_words {
+            words.push(PAD);
+        }
+        while words.len() < data_words {
+            let position = words.len() + 1;
+            words.push(randomize_253(PAD, position));
+        }
+        Ok(self.symbol(&words))
+    }
+}
+
+impl DataMatrix {
+    // the symbol for a full set of data codewords, with their check words
+    fn symbol(&self, words: &[u8]) -> BitMatrix {
+        let (side, region, data_words, check_words, blocks) = SYMBOLS[self.symbol];
+
+        // block i takes every blocks-th codeword starting at i, its check words are interleaved the same way
+        let gf = Field::new(0x12d, 8);
+        let per_block = check_words / blocks;
+        let mut codewords: Vec<u8> = words.to_vec();
+        codewords.resize(data_words + check_words, 0);
+        for block in 0..blocks {
+            let data: Vec<u16> = words.iter().skip(block).step_by(blocks).map(|&w| w as u16).collect();
+            for (j, check) in gf.check_words(&data, per_block).into_iter().enumerate() {
+                codewords[data_words + block + j * blocks] = check as u8;
+            }
+        }
+
+        let regions = side / (region + 2);
+        let mapping = place(&codewords, regions * region);
+        BitMatrix::from_fn(side, |x, y| {
+            let (ix, iy) = (x % (region + 2), y % (region + 2));
+            match (ix, iy) {
+                // solid left and bottom edges, dotted top and right ones
+                (0, _) => true,
+                (_, iy) if iy == region + 1 => true,
+                (_, 0) => ix % 2 == 0,
+                (ix, _) if ix == region + 1 => iy % 2 == 1,
+                _ => mapping[(y / (region + 2) * region + iy - 1) * regions * region + x / (region + 2) * region + ix - 1]
+            }
+        })
+    }
+}
+
+// scrambling of base 256 bytes
+fn randomize_255(value: u8, position: usize) -> u8 {
+    let value = value as usize + (149 * position) % 255 + 1;
+    (if value <= 255 { value } else { value - 256 }) as u8
+}
+
+// scrambling of every pad codeword after the first
+fn randomize_253(value: u8, position: usize) -> u8 {
+    let value = value as usize + (149 * position) % 253 + 1;
+    (if value <= 254 { value } else { value - 254 }) as u8
+}
+
+// the modules of the data regions stitched together, filled in the diagonal "utah" order of
+// ISO/IEC 16022 annex F
+fn place(codewords: &[u8], size: usize) -> Vec<bool> {
+    let mut placement = Placement { size, bits: vec![None; size * size], codewords };
+    let (mut row, mut col, mut word) = (4isize, 0isize, 0);
+    let n = size as isize;
+    loop {
+        if row == n && col == 0 {
+            placement.corner(word, [(n - 1, 0), (n - 1, 1), (n - 1, 2), (0, n - 2), (0, n - 1), (1, n - 1), (2, n - 1), (3, n - 1)]);
+            word += 1;
+        }
+        if row == n - 2 && col == 0 && n % 4 != 0 {
+            placement.corner(word, [(n - 3, 0), (n - 2, 0), (n - 1, 0), (0, n - 4), (0, n - 3), (0, n - 2), (0, n - 1), (1, n - 1)]);
+            word += 1;
+        }
+        if row == n - 2 && col == 0 && n % 8 == 4 {
+            placement.corner(word, [(n - 3, 0), (n - 2, 0), (n - 1, 0), (0, n - 2), (0, n - 1), (1, n - 1), (2, n - 1), (3, n - 1)]);
+            word += 1;
+        }
+        if row == n + 4 && col == 2 && n % 8 == 0 {
+            placement.corner(word, [(n - 1, 0), (n - 1, n - 1), (0, n - 3), (0, n - 2), (0, n - 1), (1, n - 3), (1, n - 2), (1, n - 1)]);
+            word += 1;
+        }
+        // up and to the right
+        loop {
+            if row < n && col >= 0 && !placement.is_set(row, col) {
+                placement.utah(row, col, word);
+                word += 1;
+            }
+            row -= 2;
+            col += 2;
+            if row < 0 || col >= n {
+                break
+            }
+        }
+        row += 1;
+        col += 3;
+        // down and to the left
+        loop {
+            if row >= 0 && col < n && !placement.is_set(row, col) {
+                placement.utah(row, col, word);
+                word += 1;
+            }
+            row += 2;
+            col -= 2;
+            if row >= n || col < 0 {
+                break
+            }
+        }
+        row += 3;
+        col += 1;
+        if row >= n && col >= n {
+            break
+        }
+    }
+    // sizes that leave the bottom right corner empty get a fixed pattern there
+    let empty_corner = placement.bits[size * size - 1].is_none();
+    let mut bits: Vec<bool> = placement.bits.into_iter().map(|bit| bit.unwrap_or(false)).collect();
+    if empty_corner {
+        bits[size * size - 1] = true;
+        bits[size * size - size - 2] = true;
+    }
+    bits
+}
+
+struct Placement<'a> {
+    size: usize,
+    bits: Vec<Option<bool>>,
+    codewords: &'a [u8]
+}
+
+impl Placement<'_> {
+    fn is_set(&self, row: isize, col: isize) -> bool {
+        self.bits[row as usize * self.size + col as usize].is_some()
+    }
+
+    // bit 1 is the most significant bit of the codeword
+    fn module(&mut self, mut row: isize, mut col: isize, word: usize, bit: u32) {
+        let n = self.size as isize;
+        if row < 0 {
+            row += n;
+            col += 4 - ((n + 4) % 8);
+        }
+        if col < 0 {
+            col += n;
+            row += 4 - ((n + 4) % 8);
+        }
+        let dark = self.codewords.get(word).is_some_and(|w| w & (0x80 >> (bit - 1)) != 0);
+        self.bits[row as usize * self.size + col as usize] = Some(dark);
+    }
+
+    fn utah(&mut self, row: isize, col: isize, word: usize) {
+        let positions = [(row - 2, col - 2), (row - 2, col - 1), (row - 1, col - 2), (row - 1, col - 1), (row - 1, col), (row, col - 2), (row, col - 1), (row, col)];
+        for (bit, (r, c)) in positions.into_iter().enumerate() {
+            self.module(r, c, word, bit as u32 + 1);
+        }
+    }
+
+    fn corner(&mut self, word: usize, positions: [(isize, isize); 8]) {
+        for (bit, (r, c)) in positions.into_iter().enumerate() {
+            self.module(r, c, word, bit as u32 + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // annex F leaves nothing but the fixed corner of some sizes unfilled
+    #[test]
+    fn placement_covers_regions() {
+        for &(side, region, data, check, _) in &SYMBOLS {
+            let size = side / (region + 2) * region;
+            let codewords = vec![0xff; data + check];
+            let dark = place(&codewords, size).into_iter().filter(|&dark| dark).count();
+            assert!(dark == size * size || dark == size * size - 2, "{}x{}", side, side);
+        }
+        assert_eq!(randomize_255(0, 1), 150);
+        assert_eq!(randomize_253(PAD, 3), 70);
+    }
+
+    // the worked example of ISO/IEC 16022 annex O, "123456" in a 10x10 symbol
+    #[test]
+    fn iso_check_words() {
+        assert_eq!(Field::new(0x12d, 8).check_words(&[142, 164, 186], 5), [114, 25, 5, 88, 102]);
+    }
+
+    // "AIMAIM" as ascii codewords in a 14x14 symbol, the data region is the expected placement
+    // of zxing's PlacementTest
+    #[test]
+    fn known_symbol() {
+        let expected = [
+            "X.X.X.X.X.X.X.",
+            "X011100001111X",
+            "X001010101000.",
+            "X010001010100X",
+            "X001010100010.",
+            "X000111000100X",
+            "X011000010100.",
+            "X000100001101X",
+            "X011000010000.",
+            "X001100001101X",
+            "X100010010111.",
+            "X011101011010X",
+            "X001011001010.",
+            "XXXXXXXXXXXXXX"
+        ];
+        let symbol = DataMatrix::new(14).unwrap().symbol(&[66, 74, 78, 66, 74, 78, 129, 56]);
+        let rows: Vec<String> = (0..14).map(|y| (0..14).map(|x| if symbol.get(x, y) { 'X' } else { '.' }).collect()).collect();
+        let expected: Vec<String> = expected.iter().map(|row| row.replace('1', "X").replace('0', ".")).collect();
+        assert_eq!(rows, expected);
+        // the pad codewords follow from the data
+        assert_eq!(randomize_253(PAD, 8), 56);
+    }
+}
@@ -0,0 +1,86 @@
+// every code of a set as one binary blob, for transport where per-code base64 in json is too
+// bulky. the layout is the width as a big-endian u16, the number of codes as a big-endian u32,
+// then the modules of all codes row after row, 8 to a byte with the first in the high bit and
+// no padding between codes
+use qrcode::{Color, QrCode};
+use crate::DecodeError;
+#[cfg(feature = "render-text")]
+use crate::{Base64Variant, QrData};
+
+const PREFIX_SIZE: usize = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrDataSet {
+    pub width: usize,
+    // width * width modules per code
+    pub codes: Vec<Vec<Color>>
+}
+
+impl QrDataSet {
+    // None when the codes don't all have the same width, as with a layout mixing versions
+    pub fn from_codes(codes: &[QrCode]) -> Option<Self> {
+        let width = codes.first().map_or(0, QrCode::width);
+        if codes.iter().any(|code| code.width() != width) {
+            return None
+        }
+        Some(QrDataSet { width, codes: codes.iter().map(QrCode::to_colors).collect() })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PREFIX_SIZE + (self.codes.len() * self.width * self.width).div_ceil(8));
+        bytes.extend_from_slice(&(self.width as u16).to_be_bytes());
+        bytes.extend_from_slice(&(self.codes.len() as u32).to_be_bytes());
+        bytes.extend(pack(self.codes.iter().flatten()));
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < PREFIX_SIZE {
+            return Err(DecodeError::Malformed)
+        }
+        let width = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+        let count = u32::from_be_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]) as usize;
+        let modules = width * width;
+        let data = &bytes[PREFIX_SIZE..];
+        if count.checked_mul(modules).map(|total| total.div_ceil(8)) != Some(data.len()) {
+            return Err(DecodeError::Malformed)
+        }
+        let codes = (0..count).map(|i| unpack(data, i * modules, modules)).collect();
+        Ok(QrDataSet { width, codes })
+    }
+
+    // the codes of to_base64, which have to share one width
+    #[cfg(feature = "render-text")]
+    pub fn from_base64(codes: &[QrData]) -> Result<Self, DecodeError> {
+        let codes = codes.iter().map(QrData::to_colors).collect::<Result<Vec<_>, _>>()?;
+        let width = codes.first().map_or(0, |colors| (colors.len() as f64).sqrt() as usize);
+        if codes.iter().any(|colors| colors.len() != width * width) {
+            return Err(DecodeError::Malformed)
+        }
+        Ok(QrDataSet { width, codes })
+    }
+
+    #[cfg(feature = "render-text")]
+    pub fn to_base64(&self) -> Vec<QrData> {
+        self.codes.iter().map(|colors| QrData::Base64 { width: self.width, data: Base64Variant::Standard.encode(pack(colors)) }).collect()
+    }
+}
+
+// modules 8 to a byte, the first in the high bit and the last byte padded with light modules
+pub(crate) fn pack<'a, I: IntoIterator<Item = &'a Color>>(colors: I) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (i, color) in colors.into_iter().enumerate() {
+        if i % 8 == 0 {
+            bytes.push(0);
+        }
+        if *color == Color::Dark {
+            *bytes.last_mut().unwrap() |= 0x80 >> (i % 8);
+        }
+    }
+    bytes
+}
+
+// count modules starting at module offset, the inverse of pack
+fn unpack(bytes: &[u8], offset: usize, count: usize) -> Vec<Color> {
+    (offset..offset + count).map(|i| if bytes[i / 8] & 0x80 >> (i % 8) != 0 { Color::Dark } else { Color::Light }).collect()
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+#[cfg(all(feature = "rqrr", feature = "fs"))]
+use std::path::{Path, PathBuf};
+#[cfg(all(feature = "rqrr", feature = "fs"))]
+use crate::MqrError;
+use crate::{HeaderFormat, Manifest, charset, compression, crypto, digest, manifest, parity, stripe};
+
+pub use mqrcode_core::DecodeError;
+
+// sort payloads by their index byte, strip it, and concatenate the rest
+pub fn reassemble<I, P>(payloads: I) -> Result<Vec<u8>, DecodeError>
+where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+    reassemble_with(payloads, HeaderFormat::Legacy)
+}
+
+// same as reassemble, but for payloads written with the given header format
+pub fn reassemble_with<I, P>(payloads: I, format: HeaderFormat) -> Result<Vec<u8>, DecodeError>
+where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+    let (flags, data) = join(payloads, format)?;
+    unpack(flags, data, Secret::None)
+}
+
+// payloads of several sets mixed together, grouped by the total, flags and checksum in their
+// headers and reassembled one set at a time, in the order each set was first seen. legacy and
+// compact headers hold too little to tell sets apart. a payload whose header can't be read
+// comes out as an error of its own
+pub fn reassemble_sets<I, P>(payloads: I, format: HeaderFormat) -> Vec<Result<Vec<u8>, DecodeError>>
+where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+    // total, flags and checksum
+    type SetKey = (Option<usize>, u8, Option<u32>);
+    let mut sets: Vec<Result<(SetKey, Vec<P>), DecodeError>> = Vec::new();
+    for payload in payloads {
+        let key = match format.read(payload.as_ref()) {
+            Ok((header, _)) => (header.total, header.flags, header.checksum),
+            Err(e) => {
+                sets.push(Err(e));
+                continue
+            }
+        };
+        match sets.iter_mut().find_map(|set| set.as_mut().ok().filter(|(k, _)| *k == key)) {
+            Some((_, set)) => set.push(payload),
+            None => sets.push(Ok((key, vec![payload])))
+        }
+    }
+    sets.into_iter().map(|set| set.and_then(|(_, payloads)| reassemble_with(payloads, format))).collect()
+}
+
+// every set of codes in the images of a directory, see decode_dir
+#[cfg(all(feature = "rqrr", feature = "fs"))]
+#[derive(Debug)]
+pub struct DirDecode {
+    // by reassemble_sets
+    pub sets: Vec<Result<Vec<u8>, DecodeError>>,
+    // files that couldn't be opened as images or held no readable code
+    pub failures: Vec<(PathBuf, MqrError)>
+}
+
+// extensions of the files decode_dir scans, anything else in the directory is skipped
+#[cfg(all(feature = "rqrr", feature = "fs"))]
+const IMAGE_EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "bmp", "gif", "tif", "tiff", "webp"];
+
+// scans every image in dir, in file name order and without descending into subdirectories
+#[cfg(all(feature = "rqrr", feature = "fs"))]
+pub fn decode_dir<P: AsRef<Path>>(dir: P, format: HeaderFormat) -> Result<DirDecode, MqrError> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|path| path.is_file() && path.extension().and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())));
+    paths.sort();
+
+    // images are opened and scanned on all cores with the parallel feature, then merged in order
+    let scanned = map_items(&paths, |path| image::open(path).map(|image| scan(&image.to_luma8())));
+    let mut payloads = Vec::new();
+    let mut failures = Vec::new();
+    for (i, (path, found)) in paths.into_iter().zip(scanned).enumerate() {
+        let found = match found {
+            Ok(found) => found,
+            Err(e) => {
+                failures.push((path, e.into()));
+                continue
+            }
+        };
+        if found.is_empty() {
+            failures.push((path, DecodeError::Unreadable(i).into()));
+        }
+        payloads.extend(found);
+    }
+    Ok(DirDecode { sets: reassemble_sets(payloads, format), failures })
+}
+
+// reassemble_with for payloads encrypted with Encryption::AesGcm
+#[cfg(feature = "aes-gcm")]
+pub fn reassemble_decrypt<I, P>(payloads: I, format: HeaderFormat, key: &[u8; 32]) -> Result<Vec<u8>, DecodeError>
+where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+    let (flags, data) = join(payloads, format)?;
+    unpack(flags, data, Secret::Key(key))
+}
+
+// reassemble_with for payloads encrypted with Encryption::Passphrase
+#[cfg(feature = "passphrase")]
+pub fn reassemble_passphrase<I, P>(payloads: I, format: HeaderFormat, passphrase: &str) -> Result<Vec<u8>, DecodeError>
+where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+    let (flags, data) = join(payloads, format)?;
+    unpack(flags, data, Secret::Passphrase(passphrase))
+}
+
+// reassemble for codes built with a manifest, which is returned along with the data
+pub fn reassemble_manifest<I, P>(payloads: I) -> Result<(Manifest, Vec<u8>), DecodeError>
+where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+    let (manifest, data) = manifest::join(payloads)?;
+    let data = unpack(manifest.flags, data, Secret::None)?;
+    Ok((manifest, data))
+}
+
+// reassemble_manifest for data encrypted with Encryption::AesGcm
+#[cfg(feature = "aes-gcm")]
+pub fn reassemble_manifest_decrypt<I, P>(payloads: I, key: &[u8; 32]) -> Result<(Manifest, Vec<u8>), DecodeError>
+where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+    let (manifest, data) = manifest::join(payloads)?;
+    let data = unpack(manifest.flags, data, Secret::Key(key))?;
+    Ok((manifest, data))
+}
+
+// reassemble_manifest for data encrypted with Encryption::Passphrase
+#[cfg(feature = "passphrase")]
+pub fn reassemble_manifest_passphrase<I, P>(payloads: I, passphrase: &str) -> Result<(Manifest, Vec<u8>), DecodeError>
+where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+    let (manifest, data) = manifest::join(payloads)?;
+    let data = unpack(manifest.flags, data, Secret::Passphrase(passphrase))?;
+    Ok((manifest, data))
+}
+
+// what encrypted data can be opened with, the variants are only built with the encryption features
+#[allow(dead_code)]
+enum Secret<'a> {
+    None,
+    Key(&'a [u8; 32]),
+    Passphrase(&'a str)
+}
+
+// undoes everything flags say was done to the data before chunking
+fn unpack(flags: u8, data: Vec<u8>, secret: Secret) -> Result<Vec<u8>, DecodeError> {
+    let (digest, data) = digest::split(flags, data)?;
+    let data = match (flags & (crypto::FLAG_AES_GCM | crypto::FLAG_PASSPHRASE), secret) {
+        (0, _) => data,
+        #[cfg(feature = "aes-gcm")]
+        (crypto::FLAG_AES_GCM, Secret::Key(key)) => crypto::decrypt(&data, key)?,
+        #[cfg(feature = "passphrase")]
+        (encryption, Secret::Passphrase(passphrase)) if encryption != crypto::FLAG_AES_GCM => crypto::decrypt_passphrase(&data, passphrase)?,
+        // needs another secret, or one at all
+        _ => return Err(DecodeError::Encrypted)
+    };
+    digest::verify(digest, charset::decode(flags, compression::decompress(flags, data)?)?)
+}
+
+// repeated scans of the same code are common and keep the first copy, but another chunk with
+// the same index can't be told apart from the right one
+pub(crate) fn insert_chunk(chunks: &mut BTreeMap<usize, Vec<u8>>, index: usize, data: &[u8]) -> Result<(), DecodeError> {
+    match chunks.get(&index) {
+        Some(first) if first != data => Err(DecodeError::ConflictingChunk { index, first: first.clone(), second: data.to_vec() }),
+        Some(_) => Ok(()),
+        None => {
+            chunks.insert(index, data.to_vec());
+            Ok(())
+        }
+    }
+}
+
+// concatenated chunk data, still as it was chunked, and the header flags
+fn join<I, P>(payloads: I, format: HeaderFormat) -> Result<(u8, Vec<u8>), DecodeError>
+where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+    let mut chunks: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+    let mut meta: Option<(Option<usize>, u8, Option<u32>)> = None;
+    for payload in payloads {
+        let (header, data) = format.read(payload.as_ref())?;
+        match meta {
+            None => meta = Some((header.total, header.flags, header.checksum)),
+            Some(m) if m != (header.total, header.flags, header.checksum) => return Err(DecodeError::InconsistentHeader),
+            _ => {}
+        }
+        insert_chunk(&mut chunks, header.index, data)?;
+    }
+    let (total, flags, checksum) = meta.unwrap_or((None, 0, None));
+
+    // without a stored total, the highest index seen is the best guess
+    let total = total.unwrap_or_else(|| chunks.keys().next_back().map_or(0, |i| i + 1));
+
+    // parity chunks come after the data chunks and can stand in for any missing ones
+    let mut len = None;
+    if flags & parity::FLAG_PARITY != 0 {
+        let mut shards = BTreeMap::new();
+        for (i, shard) in chunks.split_off(&total) {
+            if shard.len() < parity::PARITY_PREFIX_SIZE {
+                return Err(DecodeError::Malformed)
+            }
+            let (prefix, shard) = shard.split_at(parity::PARITY_PREFIX_SIZE);
+            len = Some(u32::from_be_bytes(prefix.try_into().unwrap()) as usize);
+            shards.insert(i - total, shard.to_vec());
+        }
+        // the total comes from a scanned header, parity only spans MAX_SHARDS codes
+        let parity_count = shards.keys().next_back().map_or(0, |j| j + 1);
+        if total + parity_count > parity::MAX_SHARDS {
+            return Err(DecodeError::Malformed)
+        }
+        parity::recover(&mut chunks, &shards, total)?;
+    }
+    if chunks.keys().any(|&i| i >= total) {
+        return Err(DecodeError::InconsistentHeader)
+    }
+
+    let chunks = (0..total).map(|i| chunks.remove(&i).ok_or(DecodeError::MissingChunk(i))).collect::<Result<Vec<_>, _>>()?;
+    // rebuilt chunks are padded
+    let len = len.unwrap_or_else(|| chunks.iter().map(Vec::len).sum());
+    let res = if flags & stripe::FLAG_STRIPED != 0 {
+        stripe::join(&chunks, len)
+    } else {
+        let mut res = chunks.concat();
+        res.truncate(len);
+        res
+    };
+    if checksum.is_some_and(|checksum| crc32fast::hash(&res) != checksum) {
+        return Err(DecodeError::ChecksumMismatch)
+    }
+    Ok((flags, res))
+}
+
+// raw payloads of every qr code found in an image
+#[cfg(feature = "rqrr")]
+pub fn scan(image: &image::GrayImage) -> Vec<Vec<u8>> {
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize, image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32).0[0]
+    );
+    prepared.detect_grids().iter().filter_map(|grid| {
+        let mut payload = Vec::new();
+        grid.decode_to(&mut payload).ok().map(|_| payload)
+    }).collect()
+}
+
+// f for every item in order, spread over all cores with the parallel feature
+#[cfg(feature = "rqrr")]
+pub(crate) fn map_items<T: Sync, U: Send, F: Fn(&T) -> U + Sync + Send>(items: &[T], f: F) -> Vec<U> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        items.par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    items.iter().map(f).collect()
+}
+
+// scan every image and reassemble the payloads found
+#[cfg(feature = "rqrr")]
+pub fn reassemble_images<'a, I>(images: I) -> Result<Vec<u8>, DecodeError>
+where I: IntoIterator<Item = &'a image::GrayImage> {
+    let images: Vec<&image::GrayImage> = images.into_iter().collect();
+    let mut payloads = Vec::new();
+    for (i, found) in map_items(&images, |image| scan(image)).into_iter().enumerate() {
+        if found.is_empty() {
+            return Err(DecodeError::Unreadable(i))
+        }
+        payloads.extend(found);
+    }
+    reassemble(payloads)
+}
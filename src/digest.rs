@@ -0,0 +1,39 @@
+use crate::DecodeError;
+
+// set when the chunked stream starts with a sha-256 digest of the original data, so it
+// ends up in the first chunk
+pub(crate) const FLAG_SHA256: u8 = 0b0100_0000;
+pub(crate) const DIGEST_SIZE: usize = 32;
+
+#[cfg(feature = "sha256")]
+pub(crate) fn sha256(data: &[u8]) -> [u8; DIGEST_SIZE] {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(data).into()
+}
+
+// the stored digest, if the header flags say there is one, and the rest of the stream
+pub(crate) fn split(flags: u8, mut data: Vec<u8>) -> Result<(Option<[u8; DIGEST_SIZE]>, Vec<u8>), DecodeError> {
+    if flags & FLAG_SHA256 == 0 {
+        return Ok((None, data))
+    }
+    if cfg!(not(feature = "sha256")) {
+        return Err(DecodeError::Unsupported)
+    }
+    if data.len() < DIGEST_SIZE {
+        return Err(DecodeError::Malformed)
+    }
+    let rest = data.split_off(DIGEST_SIZE);
+    let mut digest = [0; DIGEST_SIZE];
+    digest.copy_from_slice(&data);
+    Ok((Some(digest), rest))
+}
+
+// checks the fully decoded data against the digest from split
+pub(crate) fn verify(expected: Option<[u8; DIGEST_SIZE]>, data: Vec<u8>) -> Result<Vec<u8>, DecodeError> {
+    match expected {
+        None => Ok(data),
+        #[cfg(feature = "sha256")]
+        Some(digest) if sha256(&data) == digest => Ok(data),
+        Some(_) => Err(DecodeError::DigestMismatch)
+    }
+}
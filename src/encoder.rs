@@ -0,0 +1,129 @@
+use std::io::{self, Read, Write};
+use qrcode::{QrCode, Version, EcLevel, types::QrError};
+use crate::{HeaderFormat, byte_mode_slack, chunk_size, check_byte_mode, segment};
+
+// lazily encodes chunks as they are read from source, so only one chunk is held in memory.
+// the total size isn't known up front, so chunks always use the legacy single byte header
+pub struct MultiQrEncoder<R> {
+    source: R,
+    version: Version,
+    ec: EcLevel,
+    chunk: usize,
+    index: usize,
+    done: bool
+}
+
+impl<R: Read> MultiQrEncoder<R> {
+    // a borrowed slice works as a source too, since &[u8] implements Read
+    pub fn new(source: R, version: Version, ec: EcLevel) -> Result<Self, QrError> {
+        let chunk = legacy_chunk_size(version, ec)?;
+        Ok(MultiQrEncoder { source, version, ec, chunk, index: 0, done: false })
+    }
+
+    // raw payload (header included) of the next chunk, or None once the source is exhausted
+    pub fn next_payload(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.done {
+            return Ok(None)
+        }
+
+        let mut payload = vec![0u8; 1 + self.chunk];
+        let mut filled = 1;
+        while filled < payload.len() {
+            match self.source.read(&mut payload[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e)
+            }
+        }
+        if filled < payload.len() {
+            self.done = true;
+        }
+        if filled == 1 {
+            return Ok(None)
+        }
+        if self.index >= HeaderFormat::Legacy.max_chunks() {
+            self.done = true;
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, QrError::DataTooLong))
+        }
+
+        payload.truncate(filled);
+        payload[0] = self.index as u8;
+        self.index += 1;
+        Ok(Some(payload))
+    }
+}
+
+impl<R: Read> Iterator for MultiQrEncoder<R> {
+    type Item = io::Result<QrCode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_payload() {
+            Ok(Some(payload)) => Some(segment::byte_code(&payload, self.version, self.ec, false)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))),
+            Ok(None) => None,
+            Err(e) => Some(Err(e))
+        }
+    }
+}
+
+// the push side counterpart of MultiQrEncoder: bytes written are buffered and every
+// filled chunk is encoded and handed to sink right away
+pub struct MultiQrWriter<F> {
+    sink: F,
+    version: Version,
+    ec: EcLevel,
+    chunk: usize,
+    buffer: Vec<u8>,
+    index: usize
+}
+
+impl<F: FnMut(QrCode) -> io::Result<()>> MultiQrWriter<F> {
+    pub fn new(version: Version, ec: EcLevel, sink: F) -> Result<Self, QrError> {
+        let chunk = legacy_chunk_size(version, ec)?;
+        Ok(MultiQrWriter { sink, version, ec, chunk, buffer: Vec::with_capacity(chunk), index: 0 })
+    }
+
+    // encodes whatever is left as a final, shorter chunk and gives the sink back
+    pub fn finish(mut self) -> io::Result<F> {
+        if !self.buffer.is_empty() {
+            self.emit()?;
+        }
+        Ok(self.sink)
+    }
+
+    fn emit(&mut self) -> io::Result<()> {
+        if self.index >= HeaderFormat::Legacy.max_chunks() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, QrError::DataTooLong))
+        }
+        let mut payload = Vec::with_capacity(1 + self.buffer.len());
+        payload.push(self.index as u8);
+        payload.append(&mut self.buffer);
+        let code = segment::byte_code(&payload, self.version, self.ec, false)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.index += 1;
+        (self.sink)(code)
+    }
+}
+
+impl<F: FnMut(QrCode) -> io::Result<()>> Write for MultiQrWriter<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.chunk - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..n]);
+        if self.buffer.len() == self.chunk {
+            self.emit()?;
+        }
+        Ok(n)
+    }
+
+    // a partial chunk can't be emitted early without changing the chunk layout, see finish
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn legacy_chunk_size(version: Version, ec: EcLevel) -> Result<usize, QrError> {
+    check_byte_mode(version)?;
+    let format = HeaderFormat::Legacy;
+    chunk_size(version, ec, format.size() + byte_mode_slack(version)).ok_or(QrError::DataTooLong)
+}
@@ -0,0 +1,68 @@
+// encapsulated postscript, for print shops and label printers that want vector artwork.
+// sizes are in points, dark modules are filled rectangles on a white background
+use std::fmt::Write;
+use qrcode::QrCode;
+use crate::naming;
+use crate::pdf::{CAPTION_SIZE, caption_width, dark_runs, escape};
+
+// one code with quiet_zone light modules around it, module points per module
+pub(crate) fn code(code: &QrCode, module: f32, quiet_zone: usize) -> String {
+    let side = side(code, module, quiet_zone);
+    let mut body = String::new();
+    draw_code(&mut body, code, 0.0, side, module, quiet_zone);
+    document(&body, side, side)
+}
+
+// all codes in a grid of cols columns with a caption under each, the tiles sized for the
+// widest code. cols must be at least 1
+pub(crate) fn sheet(codes: &[QrCode], cols: usize, module: f32, caption: Option<&str>, quiet_zone: usize) -> String {
+    let side = codes.iter().map(|code| self::side(code, module, quiet_zone)).fold(0.0, f32::max);
+    let cell_height = side + 2.0 * CAPTION_SIZE;
+    let rows = codes.len().div_ceil(cols);
+    let (width, height) = (cols.min(codes.len()) as f32 * side, rows as f32 * cell_height);
+
+    let mut body = String::new();
+    for (index, code) in codes.iter().enumerate() {
+        let left = (index % cols) as f32 * side;
+        let top = height - (index / cols) as f32 * cell_height;
+        let offset = (side - self::side(code, module, quiet_zone)) / 2.0;
+        draw_code(&mut body, code, left + offset, top - offset, module, quiet_zone);
+
+        let label = match caption {
+            Some(template) => naming::caption(template, index, codes.len()),
+            None => format!("{} of {}", index + 1, codes.len())
+        };
+        let x = left + (side - caption_width(&label)) / 2.0;
+        let y = top - side - 1.5 * CAPTION_SIZE;
+        let _ = writeln!(body, "0 setgray {:.2} {:.2} moveto ({}) show", x, y, escape(&label));
+    }
+    document(&body, width, height)
+}
+
+fn side(code: &QrCode, module: f32, quiet_zone: usize) -> f32 {
+    (code.width() + 2 * quiet_zone) as f32 * module
+}
+
+fn draw_code(body: &mut String, code: &QrCode, left: f32, top: f32, module: f32, quiet_zone: usize) {
+    let side = side(code, module, quiet_zone);
+    let _ = writeln!(body, "1 setgray {:.3} {:.3} {:.3} {:.3} rectfill", left, top - side, side, side);
+    body.push_str("0 setgray\n");
+    for (x, y, len) in dark_runs(code) {
+        let _ = writeln!(body, "{:.3} {:.3} {:.3} {:.3} rectfill",
+            left + (quiet_zone + x) as f32 * module,
+            top - (quiet_zone + y + 1) as f32 * module,
+            len as f32 * module,
+            module);
+    }
+}
+
+fn document(body: &str, width: f32, height: f32) -> String {
+    let mut res = String::from("%!PS-Adobe-3.0 EPSF-3.0\n");
+    let _ = writeln!(res, "%%BoundingBox: 0 0 {} {}", width.ceil() as u32, height.ceil() as u32);
+    let _ = writeln!(res, "%%HiResBoundingBox: 0 0 {:.3} {:.3}", width, height);
+    res.push_str("%%Creator: mqrcode\n%%Pages: 1\n%%EndComments\n");
+    let _ = writeln!(res, "save\n/Helvetica findfont {} scalefont setfont", CAPTION_SIZE);
+    res.push_str(body);
+    res.push_str("restore\nshowpage\n%%EOF\n");
+    res
+}
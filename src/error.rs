@@ -0,0 +1,78 @@
+use std::{error::Error, fmt, io};
+#[cfg(any(feature = "render-image", feature = "rqrr"))]
+use image::ImageError;
+use qrcode::types::QrError;
+use crate::DecodeError;
+
+// crate wide error, for operations that can fail in more than one way
+#[derive(Debug)]
+pub enum MqrError {
+    Qr(QrError),
+    Io(io::Error),
+    Decode(DecodeError),
+    #[cfg(any(feature = "render-image", feature = "rqrr"))]
+    Image(ImageError),
+    // a combination of options that can't work together
+    InvalidOptions(&'static str),
+    // the payload could not be encrypted
+    Encryption,
+    // a ProgressSink asked to stop
+    Cancelled
+}
+
+impl fmt::Display for MqrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MqrError::Qr(e) => write!(f, "qr encoding failed: {}", e),
+            MqrError::Io(e) => write!(f, "i/o error: {}", e),
+            MqrError::Decode(e) => write!(f, "decoding failed: {}", e),
+            #[cfg(any(feature = "render-image", feature = "rqrr"))]
+            MqrError::Image(e) => write!(f, "image error: {}", e),
+            MqrError::InvalidOptions(reason) => write!(f, "invalid options: {}", reason),
+            MqrError::Encryption => write!(f, "encryption failed"),
+            MqrError::Cancelled => write!(f, "cancelled")
+        }
+    }
+}
+
+impl Error for MqrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MqrError::Qr(e) => Some(e),
+            MqrError::Io(e) => Some(e),
+            MqrError::Decode(e) => Some(e),
+            #[cfg(any(feature = "render-image", feature = "rqrr"))]
+            MqrError::Image(e) => Some(e),
+            MqrError::InvalidOptions(_) | MqrError::Encryption | MqrError::Cancelled => None
+        }
+    }
+}
+
+impl From<QrError> for MqrError {
+    fn from(e: QrError) -> Self {
+        MqrError::Qr(e)
+    }
+}
+
+// the streaming encoders carry QrErrors inside io::Errors, unwrap those again
+impl From<io::Error> for MqrError {
+    fn from(e: io::Error) -> Self {
+        match e.get_ref().and_then(|inner| inner.downcast_ref::<QrError>()) {
+            Some(&qr) => MqrError::Qr(qr),
+            None => MqrError::Io(e)
+        }
+    }
+}
+
+impl From<DecodeError> for MqrError {
+    fn from(e: DecodeError) -> Self {
+        MqrError::Decode(e)
+    }
+}
+
+#[cfg(any(feature = "render-image", feature = "rqrr"))]
+impl From<ImageError> for MqrError {
+    fn from(e: ImageError) -> Self {
+        MqrError::Image(e)
+    }
+}
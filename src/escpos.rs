@@ -0,0 +1,99 @@
+// esc/pos commands for thermal receipt and label printers. every code is printed as a
+// GS v 0 raster bit image, scaled by a whole number of dots and centered on the paper
+use qrcode::{Color, QrCode};
+use crate::{MqrError, naming};
+use crate::pdf::QUIET_ZONE;
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+
+// printable width of the paper, the common sizes at 203 dpi
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaperWidth {
+    Mm58,
+    #[default]
+    Mm80,
+    // any other printer, in dots
+    Dots(u16)
+}
+
+impl PaperWidth {
+    pub fn dots(&self) -> usize {
+        match self {
+            PaperWidth::Mm58 => 384,
+            PaperWidth::Mm80 => 576,
+            PaperWidth::Dots(dots) => *dots as usize
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EscPosOptions {
+    pub paper: PaperWidth,
+    // "3 of 12" printed under every code
+    pub captions: bool,
+    // replaces "3 of 12" with a template taking the placeholders of naming::caption
+    pub caption: Option<String>,
+    // blank lines fed after every code
+    pub feed: u8,
+    // a partial cut after every code, for printers with a cutter
+    pub cut: bool,
+    // light modules around every code, 0 for none
+    pub quiet_zone: usize
+}
+
+impl Default for EscPosOptions {
+    fn default() -> Self {
+        EscPosOptions { paper: PaperWidth::default(), captions: true, caption: None, feed: 3, cut: false, quiet_zone: QUIET_ZONE }
+    }
+}
+
+// the commands printing one code, starting with a printer reset
+pub(crate) fn code(code: &QrCode, index: usize, total: usize, options: &EscPosOptions) -> Result<Vec<u8>, MqrError> {
+    // rows span the whole paper so centering is only a matter of where the modules start
+    let row_bytes = options.paper.dots() / 8;
+    let quiet_zone = options.quiet_zone;
+    let side = code.width() + 2 * quiet_zone;
+    let scale = row_bytes * 8 / side;
+    if scale == 0 {
+        return Err(MqrError::InvalidOptions("paper is too narrow for the code"))
+    }
+    let left = (row_bytes * 8 - side * scale) / 2;
+    let height = side * scale;
+
+    let mut res = vec![ESC, b'@', ESC, b'a', 1, GS, b'v', b'0', 0];
+    res.extend((row_bytes as u16).to_le_bytes());
+    res.extend((height as u16).to_le_bytes());
+    let colors = code.to_colors();
+    for y in 0..height {
+        let mut row = vec![0u8; row_bytes];
+        let module_y = (y / scale).checked_sub(quiet_zone).filter(|&my| my < code.width());
+        if let Some(my) = module_y {
+            for mx in 0..code.width() {
+                if colors[my * code.width() + mx] != Color::Dark {
+                    continue
+                }
+                let start = left + (quiet_zone + mx) * scale;
+                for x in start..start + scale {
+                    row[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+        }
+        res.extend(row);
+    }
+
+    if options.captions {
+        let caption = match &options.caption {
+            Some(template) => naming::caption(template, index, total),
+            None => format!("{} of {}", index + 1, total)
+        };
+        // printers only know their own code page, anything else would come out garbled
+        res.extend(caption.chars().map(|c| if c.is_ascii() && !c.is_ascii_control() { c as u8 } else { b'?' }));
+        res.push(b'\n');
+    }
+    res.extend([ESC, b'd', options.feed]);
+    if options.cut {
+        res.extend([GS, b'V', 66, 0]);
+    }
+    Ok(res)
+}
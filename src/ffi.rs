@@ -0,0 +1,187 @@
+// C ABI over encoding and reassembly, declared in include/mqrcode.h. everything the library
+// allocates is handed back through mqr_free or mqr_free_buffer, never the caller's free()
+#![allow(clippy::missing_safety_doc)]
+
+use std::{ptr, slice};
+use qrcode::{Color, EcLevel, Version, types::QrError};
+use crate::{DecodeError, HeaderFormat, IndexWidth, MultiQrCode, WireFormat, decode};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MqrStatus {
+    Ok = 0,
+    // a null pointer, unknown enum value or version out of range
+    InvalidArgument = -1,
+    // the data needs more codes than the header format can count
+    DataTooLong = -2,
+    EncodeFailed = -3,
+    MissingChunk = -4,
+    ChecksumMismatch = -5,
+    DecodeFailed = -6
+}
+
+// values for the ec arguments, passed as plain ints so a bad value is an error instead of undefined
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MqrEcLevel {
+    L = 0,
+    M = 1,
+    Q = 2,
+    H = 3
+}
+
+// values for the header arguments
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MqrHeader {
+    Legacy = 0,
+    Compact = 1,
+    ExtendedU8 = 2,
+    ExtendedU16 = 3,
+    ExtendedU32 = 4,
+    Versioned = 5
+}
+
+#[repr(C)]
+pub struct MqrBuffer {
+    pub data: *mut u8,
+    pub len: usize
+}
+
+#[repr(C)]
+pub struct MqrCode {
+    // the raw bytes stored in the code, header included
+    pub payload: MqrBuffer,
+    // width * width modules row after row, 1 for dark and 0 for light
+    pub modules: MqrBuffer,
+    pub width: usize
+}
+
+#[repr(C)]
+pub struct MqrCodes {
+    pub codes: *mut MqrCode,
+    pub count: usize
+}
+
+// version is 1 to 40 for normal codes and -1 to -4 for Micro QR codes M1 to M4.
+// on success out holds every code in order and must be released with mqr_free
+#[no_mangle]
+pub unsafe extern "C" fn mqr_encode(data: *const u8, len: usize, version: i16, ec: i32, header: i32, out: *mut MqrCodes) -> MqrStatus {
+    if (data.is_null() && len > 0) || out.is_null() {
+        return MqrStatus::InvalidArgument
+    }
+    let (Some(ec), Some(header)) = (ec_level(ec), header_format(header)) else {
+        return MqrStatus::InvalidArgument
+    };
+    let version = match version {
+        1..=40 => Version::Normal(version),
+        -4..=-1 => Version::Micro(-version),
+        _ => return MqrStatus::InvalidArgument
+    };
+    // safety: the caller promises len readable bytes at data
+    let data = if len == 0 { &[][..] } else { slice::from_raw_parts(data, len) };
+
+    let qr = match MultiQrCode::with_header(data, version, ec, header) {
+        Ok(qr) => qr,
+        Err(QrError::DataTooLong) => return MqrStatus::DataTooLong,
+        Err(QrError::InvalidVersion | QrError::UnsupportedCharacterSet) => return MqrStatus::InvalidArgument,
+        Err(_) => return MqrStatus::EncodeFailed
+    };
+    let codes: Vec<MqrCode> = qr.iter().zip(qr.payloads()).map(|(code, payload)| MqrCode {
+        payload: MqrBuffer::new(payload.clone()),
+        modules: MqrBuffer::new(code.to_colors().into_iter().map(|color| (color == Color::Dark) as u8).collect()),
+        width: code.width()
+    }).collect();
+    let count = codes.len();
+    let codes = Box::into_raw(codes.into_boxed_slice()) as *mut MqrCode;
+    // safety: out was checked for null, the caller owns what it points to
+    *out = MqrCodes { codes, count };
+    MqrStatus::Ok
+}
+
+// releases what mqr_encode returned and empties codes, safe to call twice
+#[no_mangle]
+pub unsafe extern "C" fn mqr_free(codes: *mut MqrCodes) {
+    if codes.is_null() || (*codes).codes.is_null() {
+        return
+    }
+    // safety: codes and count were set by mqr_encode from a boxed slice
+    let boxed = Box::from_raw(ptr::slice_from_raw_parts_mut((*codes).codes, (*codes).count));
+    for code in boxed.iter() {
+        code.payload.release();
+        code.modules.release();
+    }
+    *codes = MqrCodes { codes: ptr::null_mut(), count: 0 };
+}
+
+// reassembles count scanned payloads, payloads[i] being lens[i] bytes long, in any order.
+// on success out holds the data and must be released with mqr_free_buffer
+#[no_mangle]
+pub unsafe extern "C" fn mqr_decode(payloads: *const *const u8, lens: *const usize, count: usize, header: i32, out: *mut MqrBuffer) -> MqrStatus {
+    let Some(header) = header_format(header) else {
+        return MqrStatus::InvalidArgument
+    };
+    if (count > 0 && (payloads.is_null() || lens.is_null())) || out.is_null() {
+        return MqrStatus::InvalidArgument
+    }
+    let mut scanned = Vec::with_capacity(count);
+    for i in 0..count {
+        // safety: the caller promises count entries in both arrays
+        let (payload, len) = (*payloads.add(i), *lens.add(i));
+        if payload.is_null() {
+            return MqrStatus::InvalidArgument
+        }
+        scanned.push(slice::from_raw_parts(payload, len));
+    }
+    match decode::reassemble_with(scanned, header) {
+        Ok(data) => {
+            *out = MqrBuffer::new(data);
+            MqrStatus::Ok
+        }
+        Err(DecodeError::MissingChunk(_)) => MqrStatus::MissingChunk,
+        Err(DecodeError::ChecksumMismatch) => MqrStatus::ChecksumMismatch,
+        Err(_) => MqrStatus::DecodeFailed
+    }
+}
+
+// releases what mqr_decode returned and empties buffer, safe to call twice
+#[no_mangle]
+pub unsafe extern "C" fn mqr_free_buffer(buffer: *mut MqrBuffer) {
+    if buffer.is_null() {
+        return
+    }
+    (*buffer).release();
+    *buffer = MqrBuffer { data: ptr::null_mut(), len: 0 };
+}
+
+impl MqrBuffer {
+    fn new(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        MqrBuffer { data: Box::into_raw(bytes.into_boxed_slice()) as *mut u8, len }
+    }
+
+    // safety: data and len must come from new and not have been released yet
+    unsafe fn release(&self) {
+        if !self.data.is_null() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(self.data, self.len)));
+        }
+    }
+}
+
+// indexed by MqrEcLevel
+fn ec_level(ec: i32) -> Option<EcLevel> {
+    usize::try_from(ec).ok().and_then(|i| [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H].get(i).copied())
+}
+
+// indexed by MqrHeader
+fn header_format(header: i32) -> Option<HeaderFormat> {
+    let formats = [
+        HeaderFormat::Legacy,
+        HeaderFormat::Compact,
+        HeaderFormat::Extended(IndexWidth::U8),
+        HeaderFormat::Extended(IndexWidth::U16),
+        HeaderFormat::Extended(IndexWidth::U32),
+        HeaderFormat::Versioned(WireFormat::LATEST)
+    ];
+    usize::try_from(header).ok().and_then(|i| formats.get(i).copied())
+}
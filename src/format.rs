@@ -0,0 +1,83 @@
+use std::{io::{Cursor, Write}, path::Path};
+use image::{ColorType, DynamicImage, GrayImage, RgbaImage};
+use crate::MqrError;
+
+// file format of saved images, see SaveOptions::format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    // quality from 1 to 100, jpeg has no alpha so transparent backgrounds turn black
+    Jpeg { quality: u8 },
+    Bmp,
+    Tiff,
+    #[cfg(feature = "webp")]
+    WebP
+}
+
+impl ImageFormat {
+    // picked by file extension, jpegs get quality 90
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(ImageFormat::Png),
+            "jpg" | "jpeg" => Some(ImageFormat::Jpeg { quality: 90 }),
+            "bmp" => Some(ImageFormat::Bmp),
+            "tif" | "tiff" => Some(ImageFormat::Tiff),
+            #[cfg(feature = "webp")]
+            "webp" => Some(ImageFormat::WebP),
+            _ => None
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg { .. } => "jpg",
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Tiff => "tiff",
+            #[cfg(feature = "webp")]
+            ImageFormat::WebP => "webp"
+        }
+    }
+}
+
+pub(crate) fn write<W: Write>(image: RgbaImage, format: ImageFormat, mut writer: W) -> Result<(), MqrError> {
+    let (width, height) = image.dimensions();
+    match format {
+        ImageFormat::Png => match gray(&image) {
+            Some(gray) => DynamicImage::ImageLuma8(gray).write_to(&mut writer, image::ImageFormat::Png)?,
+            None => DynamicImage::ImageRgba8(image).write_to(&mut writer, image::ImageFormat::Png)?
+        },
+        ImageFormat::Jpeg { quality } => {
+            let rgb = DynamicImage::ImageRgba8(image).to_rgb8();
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, quality.clamp(1, 100))
+                .encode(&rgb, width, height, ColorType::Rgb8)?
+        }
+        ImageFormat::Bmp => DynamicImage::ImageRgba8(image).write_to(&mut writer, image::ImageFormat::Bmp)?,
+        // the tiff encoder needs to seek back, so it goes through a buffer
+        ImageFormat::Tiff => {
+            let mut buffer = Cursor::new(Vec::new());
+            image::codecs::tiff::TiffEncoder::new(&mut buffer).encode(&image, width, height, ColorType::Rgba8)?;
+            writer.write_all(buffer.get_ref())?
+        }
+        // a single frame animation is a valid still webp
+        #[cfg(feature = "webp")]
+        ImageFormat::WebP => {
+            let encoding = |e: webp_animation::Error| MqrError::Io(std::io::Error::other(format!("webp encoding failed: {:?}", e)));
+            let mut encoder = webp_animation::Encoder::new((width, height)).map_err(encoding)?;
+            encoder.add_frame(image.as_raw(), 0).map_err(encoding)?;
+            writer.write_all(&encoder.finalize(100).map_err(encoding)?)?
+        }
+    }
+    Ok(())
+}
+
+// the image as 8-bit grayscale when every pixel is opaque gray, as the default colors render.
+// such pngs are a fraction of the size of rgba ones
+fn gray(image: &RgbaImage) -> Option<GrayImage> {
+    if !image.pixels().all(|p| p[0] == p[1] && p[1] == p[2] && p[3] == u8::MAX) {
+        return None
+    }
+    Some(GrayImage::from_fn(image.width(), image.height(), |x, y| image::Luma([image.get_pixel(x, y)[0]])))
+}
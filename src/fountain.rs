@@ -0,0 +1,287 @@
+use std::collections::BTreeSet;
+use qrcode::{QrCode, Version, EcLevel, types::QrError};
+use crate::{Capacity, DecodeError, byte_mode_slack, segment};
+
+// every packet starts with its sequence number and the original data length, both u32 big endian
+pub const HEADER_SIZE: usize = 4 + 4;
+
+// most source blocks a stream is split into. the decoder sizes its tables from a packet's
+// header, so a corrupt length could otherwise ask for billions of blocks
+pub const MAX_BLOCKS: usize = 1 << 16;
+
+// robust soliton parameters, the usual values from the LT code literature
+const SOLITON_C: f64 = 0.1;
+const SOLITON_DELTA: f64 = 0.5;
+
+// Luby Transform encoder: any sufficiently large set of packets, in any order, rebuilds the data.
+// the first k packets are the plain blocks, so a receiver that misses nothing needs no extra ones
+pub struct FountainEncoder {
+    data: Vec<u8>,
+    len: usize,
+    block: usize,
+    k: usize,
+    cdf: Vec<f64>,
+    seq: u32,
+    version: Version,
+    ec: EcLevel
+}
+
+impl FountainEncoder {
+    pub fn new<D: AsRef<[u8]>>(data: D, version: Version, ec: EcLevel) -> Result<Self, QrError> {
+        if let Version::Micro(_) = version {
+            return Err(QrError::InvalidVersion)
+        }
+
+        let data = data.as_ref();
+        let qr_size_total = Capacity::for_(version, ec);
+        let block = qr_size_total.checked_sub(HEADER_SIZE + byte_mode_slack(version))
+            .filter(|&s| s > 0).ok_or(QrError::DataTooLong)?;
+        if data.len() > u32::MAX as usize {
+            return Err(QrError::DataTooLong)
+        }
+
+        let k = data.len().div_ceil(block).max(1);
+        if k > MAX_BLOCKS {
+            return Err(QrError::DataTooLong)
+        }
+        let mut padded = data.to_vec();
+        padded.resize(k * block, 0);
+        Ok(FountainEncoder { data: padded, len: data.len(), block, k, cdf: soliton_cdf(k), seq: 0, version, ec })
+    }
+
+    // number of source blocks, the least number of packets a receiver needs
+    pub fn blocks(&self) -> usize {
+        self.k
+    }
+
+    // raw payload of the packet with the given sequence number
+    pub fn packet(&self, seq: u32) -> Vec<u8> {
+        let mut res = Vec::with_capacity(HEADER_SIZE + self.block);
+        res.extend_from_slice(&seq.to_be_bytes());
+        res.extend_from_slice(&(self.len as u32).to_be_bytes());
+        let mut block = vec![0u8; self.block];
+        for i in indices(seq, self.k, &self.cdf) {
+            xor(&mut block, &self.data[i*self.block..(i+1)*self.block]);
+        }
+        res.extend(block);
+        res
+    }
+}
+
+// endless stream of coded qr codes, only stops once the sequence number runs out
+impl Iterator for FountainEncoder {
+    type Item = QrCode;
+
+    fn next(&mut self) -> Option<QrCode> {
+        let packet = self.packet(self.seq);
+        self.seq = self.seq.checked_add(1)?;
+        segment::byte_code(&packet, self.version, self.ec, false).ok()
+    }
+}
+
+// collects fountain packets until the data can be rebuilt
+#[derive(Default)]
+pub struct FountainDecoder {
+    params: Option<(usize, usize)>,
+    cdf: Vec<f64>,
+    blocks: Vec<Option<Vec<u8>>>,
+    remaining: usize,
+    pending: Vec<(Vec<usize>, Vec<u8>)>,
+    seen: BTreeSet<u32>
+}
+
+impl FountainDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // feed one scanned packet, returns whether the data is complete
+    pub fn push(&mut self, payload: &[u8]) -> Result<bool, DecodeError> {
+        if payload.len() <= HEADER_SIZE {
+            return Err(DecodeError::EmptyChunk)
+        }
+        let seq = u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+        let len = u32::from_be_bytes([payload[4], payload[5], payload[6], payload[7]]) as usize;
+        let data = &payload[HEADER_SIZE..];
+
+        match self.params {
+            None => {
+                let k = len.div_ceil(data.len()).max(1);
+                if k > MAX_BLOCKS {
+                    return Err(DecodeError::Malformed)
+                }
+                self.params = Some((len, data.len()));
+                self.cdf = soliton_cdf(k);
+                self.blocks = vec![None; k];
+                self.remaining = k;
+            }
+            Some(params) if params != (len, data.len()) => return Err(DecodeError::InconsistentHeader),
+            _ => {}
+        }
+
+        if self.seen.insert(seq) && !self.is_complete() {
+            self.add(indices(seq, self.blocks.len(), &self.cdf), data.to_vec());
+        }
+        Ok(self.is_complete())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.params.is_some() && self.remaining == 0
+    }
+
+    // the rebuilt data, once enough packets were pushed
+    pub fn result(&self) -> Option<Vec<u8>> {
+        if !self.is_complete() {
+            return None
+        }
+        let (len, _) = self.params?;
+        let mut res: Vec<u8> = self.blocks.iter().flatten().flatten().copied().collect();
+        res.truncate(len);
+        Some(res)
+    }
+
+    fn add(&mut self, mut indices: Vec<usize>, mut data: Vec<u8>) {
+        // strip blocks that are already known
+        indices.retain(|&i| match &self.blocks[i] {
+            Some(block) => { xor(&mut data, block); false }
+            None => true
+        });
+        match indices.len() {
+            0 => {}
+            1 => self.resolve(indices[0], data),
+            _ => self.pending.push((indices, data))
+        }
+    }
+
+    // store a decoded block and peel it off every pending packet, which may decode more blocks
+    fn resolve(&mut self, index: usize, data: Vec<u8>) {
+        let mut queue = vec![(index, data)];
+        while let Some((index, data)) = queue.pop() {
+            if self.blocks[index].is_some() {
+                continue
+            }
+            for (indices, pending) in self.pending.iter_mut() {
+                if let Some(pos) = indices.iter().position(|&i| i == index) {
+                    indices.swap_remove(pos);
+                    xor(pending, &data);
+                }
+            }
+            self.blocks[index] = Some(data);
+            self.remaining -= 1;
+
+            let mut i = 0;
+            while i < self.pending.len() {
+                match self.pending[i].0.len() {
+                    0 => { self.pending.swap_remove(i); }
+                    1 => {
+                        let (indices, data) = self.pending.swap_remove(i);
+                        queue.push((indices[0], data));
+                    }
+                    _ => i += 1
+                }
+            }
+        }
+    }
+}
+
+fn xor(target: &mut [u8], other: &[u8]) {
+    for (a, b) in target.iter_mut().zip(other) {
+        *a ^= b;
+    }
+}
+
+// cumulative robust soliton distribution over degrees 1..=k
+fn soliton_cdf(k: usize) -> Vec<f64> {
+    let kf = k as f64;
+    let r = SOLITON_C * (kf / SOLITON_DELTA).ln() * kf.sqrt();
+    let pivot = if r > 0.0 { ((kf / r).floor() as usize).clamp(1, k) } else { k };
+
+    let mut weights: Vec<f64> = (1..=k).map(|d| {
+        let rho = if d == 1 { 1.0 / kf } else { 1.0 / (d * (d - 1)) as f64 };
+        let tau = if r <= 0.0 || d > pivot {
+            0.0
+        } else if d < pivot {
+            r / (d as f64 * kf)
+        } else {
+            r * (r / SOLITON_DELTA).ln() / kf
+        };
+        rho + tau.max(0.0)
+    }).collect();
+
+    let sum: f64 = weights.iter().sum();
+    let mut acc = 0.0;
+    for w in weights.iter_mut() {
+        acc += *w / sum;
+        *w = acc;
+    }
+    weights
+}
+
+// source blocks combined into the packet with the given sequence number
+fn indices(seq: u32, k: usize, cdf: &[f64]) -> Vec<usize> {
+    if (seq as usize) < k {
+        return vec![seq as usize]
+    }
+    let mut rng = SplitMix(seq as u64);
+    let degree = (cdf.partition_point(|&c| c < rng.unit()) + 1).min(k);
+    let mut res = BTreeSet::new();
+    while res.len() < degree {
+        res.insert(rng.below(k));
+    }
+    res.into_iter().collect()
+}
+
+// small deterministic generator, encoder and decoder must agree on it exactly
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_with_losses() {
+        let data: Vec<u8> = (0..2000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let encoder = FountainEncoder::new(&data, Version::Normal(3), EcLevel::M).unwrap();
+        let mut decoder = FountainDecoder::new();
+        // drop every third packet, including some of the plain blocks
+        for seq in (0..10 * encoder.blocks() as u32).filter(|seq| seq % 3 != 1) {
+            if decoder.push(&encoder.packet(seq)).unwrap() {
+                break
+            }
+        }
+        assert_eq!(decoder.result().unwrap(), data);
+    }
+
+    #[test]
+    fn mismatched_packets() {
+        let a = FountainEncoder::new(b"first", Version::Normal(1), EcLevel::L).unwrap();
+        let b = FountainEncoder::new(b"second", Version::Normal(1), EcLevel::L).unwrap();
+        let mut decoder = FountainDecoder::new();
+        decoder.push(&a.packet(5)).unwrap();
+        assert_eq!(decoder.push(&b.packet(6)), Err(DecodeError::InconsistentHeader));
+
+        // a length of 4 GB in one byte blocks
+        let mut forged = vec![0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0];
+        assert_eq!(FountainDecoder::new().push(&forged), Err(DecodeError::Malformed));
+        forged[4..8].copy_from_slice(&(MAX_BLOCKS as u32).to_be_bytes());
+        assert_eq!(FountainDecoder::new().push(&forged), Ok(false));
+    }
+}
@@ -0,0 +1,2 @@
+// the header types are in mqrcode-core, which builds with alloc only
+pub use mqrcode_core::header::*;
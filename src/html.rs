@@ -0,0 +1,65 @@
+use std::fmt::Write;
+
+// layout of the html export
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlOptions {
+    pub title: String,
+    // "3 of 12" under every code
+    pub captions: bool,
+    // Some(fps) shows one code at a time and cycles through them with javascript, clicking pauses
+    pub auto_cycle: Option<u32>
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        HtmlOptions { title: "QR codes".to_string(), captions: true, auto_cycle: None }
+    }
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:1em}\
+.codes{display:grid;grid-template-columns:repeat(auto-fill,minmax(240px,1fr));gap:1em}\
+figure{margin:0;text-align:center}\
+img{width:100%;image-rendering:pixelated}\
+.cycle figure{display:none}.cycle figure.current{display:block;max-width:90vmin;margin:auto}";
+
+// a standalone page with every code embedded as a data uri
+pub(crate) fn render(uris: &[String], options: &HtmlOptions) -> String {
+    let title = escape(&options.title);
+    let mut res = String::new();
+    let _ = write!(res, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+        <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n", title, STYLE, title);
+
+    let class = if options.auto_cycle.is_some() { "codes cycle" } else { "codes" };
+    let _ = writeln!(res, "<div class=\"{}\">", class);
+    for (i, uri) in uris.iter().enumerate() {
+        let caption = format!("{} of {}", i + 1, uris.len());
+        let current = if i == 0 { " class=\"current\"" } else { "" };
+        let _ = write!(res, "<figure{}><img src=\"{}\" alt=\"{}\">", current, uri, caption);
+        if options.captions {
+            let _ = write!(res, "<figcaption>{}</figcaption>", caption);
+        }
+        res.push_str("</figure>\n");
+    }
+    res.push_str("</div>\n");
+
+    if let Some(fps) = options.auto_cycle {
+        let _ = writeln!(res, "<script>\n\
+            const figures = document.querySelectorAll('.cycle figure');\n\
+            let current = 0, paused = false;\n\
+            setInterval(() => {{\n\
+            \x20 if (paused || figures.length < 2) return;\n\
+            \x20 figures[current].classList.remove('current');\n\
+            \x20 current = (current + 1) % figures.length;\n\
+            \x20 figures[current].classList.add('current');\n\
+            }}, {});\n\
+            document.addEventListener('click', () => paused = !paused);\n\
+            </script>", 1000 / fps.max(1));
+    }
+    res.push_str("</body>\n</html>\n");
+    res
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
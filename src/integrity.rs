@@ -0,0 +1,35 @@
+// how the data is checked on top of the crc32 that headers with a checksum carry over the whole
+// set. the choice goes into the header flags, so decoders verify exactly what was encoded
+use crate::EncodeOptions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Integrity {
+    #[default]
+    None,
+    // a crc32 in every code, so a misread chunk is reported by index. 4 bytes per code
+    Crc32,
+    // a sha-256 digest of the original data in the first chunk. 32 bytes once. building with it
+    // fails with InvalidOptions without the sha256 feature
+    Sha256,
+    // both of the above
+    Both
+}
+
+impl Integrity {
+    pub fn chunk_crc(&self) -> bool {
+        matches!(self, Integrity::Crc32 | Integrity::Both)
+    }
+
+    pub fn sha256(&self) -> bool {
+        matches!(self, Integrity::Sha256 | Integrity::Both)
+    }
+}
+
+impl From<&EncodeOptions> for Integrity {
+    fn from(options: &EncodeOptions) -> Self {
+        if options.sha256 {
+            return if options.chunk_crc { Integrity::Both } else { Integrity::Sha256 }
+        }
+        if options.chunk_crc { Integrity::Crc32 } else { Integrity::None }
+    }
+}
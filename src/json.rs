@@ -0,0 +1,63 @@
+// the manifest.json save_with writes next to the images, so a directory of codes can be
+// checked before reassembly. hashes are crc32 in hex, of every payload and of all of them in order
+use std::{fmt::Write, path::Path};
+use qrcode::{EcLevel, QrCode, Version};
+use crate::MultiQrCode;
+
+pub(crate) const FILE_NAME: &str = "manifest.json";
+
+pub(crate) fn manifest(qr: &MultiQrCode, paths: &[impl AsRef<Path>]) -> String {
+    let mut all = crc32fast::Hasher::new();
+    let mut codes = Vec::with_capacity(qr.len());
+    for ((code, payload), path) in qr.iter().zip(qr.payloads()).zip(paths) {
+        all.update(payload);
+        let file = path.as_ref().file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        codes.push(format!(
+            "    {{\"file\": {}, \"version\": \"{}\", \"ec_level\": \"{}\", \"payload_size\": {}, \"crc32\": \"{:08x}\"}}",
+            string(&file), version(code), ec_level(code), payload.len(), crc32fast::hash(payload)
+        ));
+    }
+    let mut res = String::from("{\n");
+    let _ = writeln!(res, "  \"total\": {},", qr.len());
+    // the first code's, later ones only differ when built with a layout
+    if let Some(first) = qr.codes.first() {
+        let _ = writeln!(res, "  \"version\": \"{}\",\n  \"ec_level\": \"{}\",", version(first), ec_level(first));
+    }
+    let _ = writeln!(res, "  \"crc32\": \"{:08x}\",", all.finalize());
+    let _ = write!(res, "  \"codes\": [\n{}\n  ]\n}}\n", codes.join(",\n"));
+    res
+}
+
+// micro versions are written as "M2"
+fn version(code: &QrCode) -> String {
+    match code.version() {
+        Version::Normal(n) => n.to_string(),
+        Version::Micro(n) => format!("M{}", n)
+    }
+}
+
+fn ec_level(code: &QrCode) -> &'static str {
+    match code.error_correction_level() {
+        EcLevel::L => "L",
+        EcLevel::M => "M",
+        EcLevel::Q => "Q",
+        EcLevel::H => "H"
+    }
+}
+
+fn string(text: &str) -> String {
+    let mut res = String::with_capacity(text.len() + 2);
+    res.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(res, "\\u{:04x}", c as u32);
+            }
+            c => res.push(c)
+        }
+    }
+    res.push('"');
+    res
+}
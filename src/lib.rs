@@ -1,232 +1,3394 @@
-use std::{path::Path};
-use base64::encode;
-use qrcode::{QrCode, Version, EcLevel, types::QrError, Color};
-use image::Luma;
+use std::io::Read;
+#[cfg(feature = "fs")]
+use std::{path::Path, fs::File};
+#[cfg(all(feature = "fs", feature = "render-image"))]
+use std::path::PathBuf;
+#[cfg(feature = "render-text")]
+use std::fmt;
+#[cfg(feature = "render-text")]
+use base64::{Engine, engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig, general_purpose}, alphabet};
+use qrcode::{QrCode, Version, EcLevel, types::{Mode, QrError}};
+#[cfg(feature = "render-text")]
+use qrcode::{Color, render::svg};
+#[cfg(feature = "render-image")]
+use qrcode::render::Renderer;
+#[cfg(feature = "render-image")]
+use image::{GrayImage, RgbaImage, Luma, DynamicImage};
+#[cfg(all(feature = "serde", feature = "render-text"))]
 use serde::{Serialize, Deserialize};
 
+#[cfg(feature = "render-image")]
+pub mod animation;
+#[cfg(feature = "async")]
+pub mod async_io;
+#[cfg(feature = "aztec")]
+pub mod aztec;
+#[cfg(feature = "render-text")]
+mod base45;
+pub mod builder;
+pub mod capacity;
+mod charset;
+pub mod compression;
+pub mod crypto;
+pub mod dataset;
+#[cfg(feature = "datamatrix")]
+pub mod datamatrix;
+pub mod decode;
+mod digest;
+pub mod encoder;
+mod eps;
+pub mod error;
+#[cfg(feature = "escpos")]
+pub mod escpos;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "render-image")]
+pub mod format;
+pub mod fountain;
+pub mod header;
+pub mod integrity;
+pub mod live;
+#[cfg(all(feature = "fs", feature = "render-image"))]
+mod json;
+#[cfg(feature = "render-image")]
+pub mod html;
+pub mod manifest;
+pub mod matrix;
+pub mod naming;
+mod parity;
+pub mod paper;
+pub mod payload;
+pub mod pdf;
+pub mod progress;
+#[cfg(feature = "terminal")]
+mod player;
+pub mod reader;
+#[cfg(any(feature = "datamatrix", feature = "aztec"))]
+mod reed_solomon;
+#[cfg(feature = "render-image")]
+pub mod render;
+#[cfg(feature = "render-text")]
+pub mod rendered;
+mod segment;
+pub mod session;
+#[cfg(feature = "render-image")]
+pub mod sheet;
+pub mod stats;
+#[cfg(feature = "serde")]
+mod store;
+mod stripe;
+pub mod structured_append;
+pub mod symbol;
+#[cfg(feature = "render-text")]
+pub mod text;
+pub mod tiff;
+pub mod transfer;
+pub mod ur;
+#[cfg(feature = "video")]
+pub mod video;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "render-image")]
+pub use animation::AnimationFormat;
+#[cfg(feature = "async")]
+pub use async_io::CodeStream;
+#[cfg(feature = "aztec")]
+pub use aztec::Aztec;
+pub use builder::MultiQrCodeBuilder;
+pub use capacity::Capacity;
+pub use compression::Compression;
+pub use crypto::Encryption;
+pub use dataset::QrDataSet;
+#[cfg(feature = "datamatrix")]
+pub use datamatrix::DataMatrix;
+pub use decode::DecodeError;
+pub use encoder::{MultiQrEncoder, MultiQrWriter};
+pub use error::MqrError;
+#[cfg(feature = "escpos")]
+pub use escpos::{EscPosOptions, PaperWidth};
+#[cfg(feature = "render-image")]
+pub use format::ImageFormat;
+pub use reader::MultiQrReader;
+pub use session::DecodeSession;
+pub use stats::Stats;
+pub use symbol::{QrEncoder, SymbolEncoder, SymbolSet};
+pub use manifest::Manifest;
+pub use matrix::BitMatrix;
+pub use progress::{Progress, ProgressSink};
+#[cfg(feature = "render-image")]
+pub use render::{RenderOptions, SaveOptions, Overwrite, Logo, ModuleShape, Neighbours, Square, Dot, Rounded};
+pub use header::{ChunkHeader, HeaderFormat, IndexWidth, WireFormat};
+pub use integrity::Integrity;
+#[cfg(feature = "render-text")]
+pub use rendered::RenderedMultiQrCode;
+#[cfg(feature = "rqrr")]
+pub use live::FrameSource;
+pub use live::LiveDecoder;
+#[cfg(feature = "render-image")]
+pub use html::HtmlOptions;
+pub use paper::BackupOptions;
+pub use payload::Payload;
+pub use pdf::{PageOptions, PageSize};
+#[cfg(feature = "render-text")]
+pub use text::{TextStyle, TextRenderOptions};
+pub use tiff::TiffOptions;
+pub use transfer::{Interleave, Receiver, Transmitter};
+use segment::Segments;
+
 pub const DEFAULT_QR_VERSION: Version = Version::Normal(40);
 pub const DEFAULT_EC_LEVEL: EcLevel = EcLevel::L;
 
-#[derive(Serialize, Deserialize)]
+#[cfg(feature = "render-text")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum QrData {
     Base64 {
         width: usize,
         data: String
     },
-    String (String)
+    // the same packed modules in base45, for pipelines that store the text in alphanumeric mode
+    Base45 {
+        width: usize,
+        data: String
+    },
+    // lowercase hex of the packed modules, two digits per byte, easy to audit by eye
+    Hex {
+        width: usize,
+        data: String
+    },
+    // the packed modules as they are, for binary transports with no text overhead
+    Raw {
+        width: usize,
+        data: Vec<u8>
+    },
+    String (String),
+    Svg (String)
+}
+
+// an exported code along with where it belongs in its set, so serialized codes can be put back
+// in order and checked for gaps. serialized with serde as a struct holding data under its
+// variant name, e.g. {"index":0,"total":3,"version":{"Normal":40},"ec":"L","data":{"Base64":{..}}}
+#[cfg(feature = "render-text")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IndexedQrData {
+    pub index: usize,
+    pub total: usize,
+    #[cfg_attr(feature = "serde", serde(with = "store::version"))]
+    pub version: Version,
+    #[cfg_attr(feature = "serde", serde(with = "store::ec_level"))]
+    pub ec: EcLevel,
+    pub data: QrData
+}
+
+// the flavour of base64 in QrData::Base64. the url safe alphabet uses - and _ instead of + and /,
+// so the data goes into query strings and paths without escaping
+#[cfg(feature = "render-text")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Base64Variant {
+    #[default]
+    Standard,
+    StandardNoPad,
+    UrlSafe,
+    UrlSafeNoPad
+}
+
+#[cfg(feature = "render-text")]
+impl Base64Variant {
+    pub(crate) fn encode<T: AsRef<[u8]>>(self, bytes: T) -> String {
+        let engine = match self {
+            Base64Variant::Standard => &general_purpose::STANDARD,
+            Base64Variant::StandardNoPad => &general_purpose::STANDARD_NO_PAD,
+            Base64Variant::UrlSafe => &general_purpose::URL_SAFE,
+            Base64Variant::UrlSafeNoPad => &general_purpose::URL_SAFE_NO_PAD
+        };
+        engine.encode(bytes)
+    }
+
+    // any variant, told apart by the alphabet and with or without padding
+    pub(crate) fn decode<T: AsRef<[u8]>>(data: T) -> Option<Vec<u8>> {
+        const LENIENT: GeneralPurposeConfig = GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+        const STANDARD: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, LENIENT);
+        const URL_SAFE: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, LENIENT);
+        let data = data.as_ref();
+        let engine = if data.iter().any(|&b| b == b'-' || b == b'_') { &URL_SAFE } else { &STANDARD };
+        engine.decode(data).ok()
+    }
 }
 
+#[cfg(feature = "render-text")]
 impl QrData {
     pub fn base64_from(code: &QrCode) -> Self {
-        let width = code.width();
-        let mut bytes = vec![0u8; ((width * width) as f32 / 8.0).ceil() as usize];
-        for (i, color) in code.to_colors().iter().enumerate() {
-            let byte_index = i / 8;
-            let shift = 7 - i%8;
-            if let Color::Dark = color {
-                bytes[byte_index] |= 1u8 << shift;
-            }
+        Self::base64_with(code, Base64Variant::Standard)
+    }
+
+    pub fn base64_with(code: &QrCode, variant: Base64Variant) -> Self {
+        QrData::Base64 { width: code.width(), data: variant.encode(pack_modules(code)) }
+    }
+
+    pub fn base45_from(code: &QrCode) -> Self {
+        QrData::Base45 { width: code.width(), data: base45::encode(&pack_modules(code)) }
+    }
+
+    pub fn hex_from(code: &QrCode) -> Self {
+        QrData::Hex { width: code.width(), data: pack_modules(code).iter().map(|byte| format!("{:02x}", byte)).collect() }
+    }
+
+    pub fn raw_from(code: &QrCode) -> Self {
+        QrData::Raw { width: code.width(), data: pack_modules(code) }
+    }
+
+    // modules row after row, the inverse of base64_with for any variant and the other _from
+    // constructors. String and Svg data hold no matrix
+    pub fn to_colors(&self) -> Result<Vec<Color>, DecodeError> {
+        let (width, bytes) = match self {
+            QrData::Base64 { width, data } => (*width, Base64Variant::decode(data)),
+            QrData::Base45 { width, data } => (*width, base45::decode(data)),
+            QrData::Hex { width, data } => (*width, decode_hex(data)),
+            QrData::Raw { width, data } => (*width, Some(data.clone())),
+            _ => return Err(DecodeError::Malformed)
+        };
+        let bytes = bytes.ok_or(DecodeError::Malformed)?;
+        let modules = width * width;
+        if bytes.len() != modules.div_ceil(8) {
+            return Err(DecodeError::Malformed)
         }
-        QrData::Base64 { width, data: encode(bytes) }
+        Ok((0..modules).map(|i| if bytes[i / 8] & 0x80 >> (i % 8) != 0 { Color::Dark } else { Color::Light }).collect())
+    }
+
+    // the same image save writes for the original code, widths below 21 are micro codes with a smaller quiet zone
+    #[cfg(feature = "render-image")]
+    pub fn to_qr_image(&self) -> Result<GrayImage, DecodeError> {
+        let colors = self.to_colors()?;
+        let width = (colors.len() as f64).sqrt() as usize;
+        let quiet_zone = if width < 21 { 2 } else { 4 };
+        Ok(Renderer::<Luma<u8>>::new(&colors, width, quiet_zone).build())
+    }
+}
+
+// modules row after row, 8 to a byte with the first one in the high bit
+#[cfg(feature = "render-text")]
+fn pack_modules(code: &QrCode) -> Vec<u8> {
+    code.to_colors().chunks(8).map(|modules| {
+        modules.iter().fold(0u8, |byte, color| byte << 1 | (*color == Color::Dark) as u8) << (8 - modules.len())
+    }).collect()
+}
+
+// upper or lower case digits, two per byte
+#[cfg(feature = "render-text")]
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let pairs = text.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None
     }
+    pairs.map(|pair| std::str::from_utf8(pair).ok().and_then(|pair| u8::from_str_radix(pair, 16).ok())).collect()
 }
 
 pub struct MultiQrCode {
-    pub codes: Vec<QrCode>
+    pub codes: Vec<QrCode>,
+    payloads: Vec<Vec<u8>>,
+    // how each payload went into its code
+    segments: Vec<Segments>
+}
+
+// everything about how data is turned into chunks, beyond version and ec level
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EncodeOptions {
+    pub header: HeaderFormat,
+    pub compression: Compression,
+    pub encryption: Encryption,
+    // raise the ec level of every code whose chunk still fits at a higher one, the version never changes
+    pub boost_ec: bool,
+    // size chunks by their actual encoded length with numeric and alphanumeric segments where
+    // they're shorter, so digit heavy or uppercase data needs fewer codes. slack is not used
+    pub optimize_segments: bool,
+    // start every code with an ECI header declaring utf-8, for scanners that would guess
+    // another charset for multi-byte text. not available in Micro QR codes
+    pub utf8_eci: bool,
+    // the data is utf-8 text, stored as shift jis so japanese goes into kanji segments at
+    // 13 bits a character instead of 24. needs a header with flags and implies optimize_segments.
+    // fails with InvalidOptions without the kanji feature
+    pub kanji: bool,
+    // a crc32 in every code, so a misread chunk is reported by index instead of corrupting
+    // the reassembled data. costs 4 bytes per code and needs a header with flags
+    pub chunk_crc: bool,
+    // a sha-256 digest of the data in the first chunk, checked when reassembling. needs a header
+    // with flags, and isn't used with encryption, which already authenticates the data. fails
+    // with InvalidOptions without the sha256 feature
+    pub sha256: bool,
+    // this many extra reed-solomon parity codes, so the data survives any of the codes getting
+    // lost as long as no more than parity are missing. needs the extended header, and doesn't
+    // work with optimize_segments or kanji since chunks must be evenly sized
+    pub parity: usize,
+    // spread the data over the codes byte by byte instead of in contiguous runs, so a damaged
+    // code costs every nth byte rather than a whole stretch. meant for use with parity, needs
+    // the extended or versioned header and doesn't work with optimize_segments or kanji
+    pub striped: bool,
+    // exactly this many data bytes in every code but the last, e.g. to line chunks up with an
+    // external protocol. more than the version holds after the header fails with DataTooLong,
+    // and it doesn't work with optimize_segments or kanji, which size chunks themselves
+    pub chunk_size: Option<usize>,
+    // an extra first code describing the set (count, size, checksum, flags, filename), see
+    // Manifest. the other codes only keep an index byte, so the header must stay Legacy
+    pub manifest: bool,
+    // the file name put into the manifest
+    pub filename: Option<String>,
+    // chunks sized for EcLevel::H whatever the ec level, so every code can take a logo (see
+    // RenderOptions::logo) without growing past the version
+    pub logo: bool
 }
 
 impl MultiQrCode {
     pub fn new<D: AsRef<[u8]>>(data: D, version: Version, ec: EcLevel) -> Result<Self, QrError> {
-        Self::with_slack(data, version, ec, QR_VERSION_SLACK[version.to_index()])
+        Self::with_slack(data, version, ec, byte_mode_slack(version))
     }
 
     pub fn with_slack<D: AsRef<[u8]>>(data: D, version: Version, ec: EcLevel, slack: usize) -> Result<Self, QrError> {
-        let mut res: Vec<QrCode> = Vec::new();
+        Self::encode(data.as_ref(), version, ec, slack, &EncodeOptions::default(), 0, None)
+    }
+
+    pub fn with_header<D: AsRef<[u8]>>(data: D, version: Version, ec: EcLevel, format: HeaderFormat) -> Result<Self, QrError> {
+        Self::encode(data.as_ref(), version, ec, byte_mode_slack(version), &EncodeOptions { header: format, ..Default::default() }, 0, None)
+    }
+
+    pub fn with_options<D: AsRef<[u8]>>(data: D, version: Version, ec: EcLevel, options: &EncodeOptions) -> Result<Self, MqrError> {
+        Self::builder().version(version).ec_level(ec).options(options.clone()).build(data)
+    }
+
+    // chainable alternative to the constructors above, see MultiQrCodeBuilder
+    pub fn builder() -> MultiQrCodeBuilder {
+        MultiQrCodeBuilder::new()
+    }
+
+    // data must already be compressed and encrypted as flags say. a manifest gets its total
+    // and crc32 filled in and goes in front of the chunks
+    fn encode(data: &[u8], version: Version, ec: EcLevel, slack: usize, options: &EncodeOptions, flags: u8, manifest: Option<Manifest>) -> Result<Self, QrError> {
+        let encoder = QrEncoder::with_options(version, ec, slack, options);
+        let (payloads, segments) = qr_payloads(data, &encoder, options, flags, manifest)?;
+        Self::build_codes(payloads, segments, |_, payload, segments| encoder.code(payload, segments))
+    }
+
+    // encode with the encoder already set up, telling progress about every code and stopping when it cancels
+    pub(crate) fn encode_with_progress(data: &[u8], encoder: &QrEncoder, options: &EncodeOptions, flags: u8, manifest: Option<Manifest>, progress: Option<&dyn ProgressSink>) -> Result<Self, MqrError> {
+        let (payloads, segments) = qr_payloads(data, encoder, options, flags, manifest)?;
+        let tracker = progress::Tracker::encoding(progress, payloads.len());
+        Self::build_codes(payloads, segments, |_, payload, segments| {
+            tracker.check()?;
+            let code = encoder.code(payload, segments)?;
+            tracker.step()?;
+            Ok(code)
+        })
+    }
+
+    // chunk i is encoded with layout[i], chunks past the end of layout reuse its last entry.
+    // e.g. [(Version::Normal(2), EcLevel::H), (Version::Normal(40), EcLevel::L)] gives one small
+    // robust code up front followed by large dense ones
+    pub fn with_layout<D: AsRef<[u8]>>(data: D, layout: &[(Version, EcLevel)], format: HeaderFormat) -> Result<Self, QrError> {
         let data = data.as_ref();
+        let &last = layout.last().ok_or(QrError::InvalidVersion)?;
+        let spec = |i: usize| layout.get(i).copied().unwrap_or(last);
 
-        // fail if version is Micro (unsupported)
-        if let Version::Micro(_) = version {
-            return Err(QrError::InvalidVersion)
+        // chunk boundaries first, the total goes into every header
+        let mut chunks = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            let (version, ec) = spec(chunks.len());
+            check_byte_mode(version)?;
+            let size = chunk_size(version, ec, format.size() + byte_mode_slack(version)).ok_or(QrError::DataTooLong)?;
+            let (chunk, tail) = rest.split_at(size.min(rest.len()));
+            chunks.push(chunk);
+            rest = tail;
         }
+        let payloads = with_headers(data, &chunks, chunks.len(), format, 0)?;
+        Self::build_codes(payloads, Segments::Bytes { eci: false }, |i, payload, segments| {
+            let (version, ec) = spec(i);
+            segments.code(payload, version, ec)
+        })
+    }
+
+    // splits only between characters, so the data of every code is valid utf-8 on its own.
+    // chunks come out up to 3 bytes shorter than with new, and are stored as plain byte segments
+    pub fn from_str_chunked(text: &str, version: Version, ec: EcLevel) -> Result<Self, QrError> {
+        check_byte_mode(version)?;
+        let size = chunk_size(version, ec, 1 + byte_mode_slack(version)).ok_or(QrError::DataTooLong)?;
+        let mut chunks = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let mut end = size.min(rest.len());
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            // a chunk too small for even one character
+            if end == 0 {
+                return Err(QrError::DataTooLong)
+            }
+            let (chunk, tail) = rest.split_at(end);
+            chunks.push(chunk.as_bytes());
+            rest = tail;
+        }
+        let payloads = with_headers(text.as_bytes(), &chunks, chunks.len(), HeaderFormat::Legacy, 0)?;
+        Self::build_codes(payloads, Segments::Bytes { eci: false }, |_, payload, segments| segments.code(payload, version, ec))
+    }
+
+    fn from_payloads(payloads: Vec<Vec<u8>>, version: Version, ec: EcLevel, boost_ec: bool) -> Result<Self, QrError> {
+        let code = |payload: &[u8], ec| segment::byte_code(payload, version, ec, false);
+        Self::build_codes(payloads, Segments::Bytes { eci: false }, |_, payload, _| if boost_ec { boosted_code(payload, ec, code) } else { code(payload, ec) })
+    }
+
+    // one code per payload, all laid out as segments says, with the parallel feature the codes are built on all cores
+    fn build_codes<F, E>(payloads: Vec<Vec<u8>>, segments: Segments, build: F) -> Result<Self, E>
+    where F: Fn(usize, &[u8], Segments) -> Result<QrCode, E> + Sync + Send, E: Send {
+        let segments = vec![segments; payloads.len()];
+        Self::build_each(payloads, segments, build)
+    }
+
+    // build_codes with the segments of every code given separately
+    fn build_each<F, E>(payloads: Vec<Vec<u8>>, segments: Vec<Segments>, build: F) -> Result<Self, E>
+    where F: Fn(usize, &[u8], Segments) -> Result<QrCode, E> + Sync + Send, E: Send {
+        #[cfg(feature = "parallel")]
+        let codes = {
+            use rayon::prelude::*;
+            payloads.par_iter().enumerate().map(|(i, payload)| build(i, payload, segments[i])).collect::<Result<Vec<_>, _>>()?
+        };
+        #[cfg(not(feature = "parallel"))]
+        let codes = payloads.iter().enumerate().map(|(i, payload)| build(i, payload, segments[i])).collect::<Result<Vec<_>, _>>()?;
+        Ok(MultiQrCode { codes, payloads, segments })
+    }
+
+    // standard structured append (ISO/IEC 18004) symbols instead of the crate's own header,
+    // readable by off-the-shelf scanners but limited to 16 symbols
+    pub fn structured_append<D: AsRef<[u8]>>(data: D, version: Version, ec: EcLevel) -> Result<Vec<structured_append::Symbol>, QrError> {
+        structured_append::encode(data.as_ref(), version, ec)
+    }
+
+    // the first count packets of a fountain coded stream, see fountain::FountainEncoder for an endless one
+    pub fn fountain<D: AsRef<[u8]>>(data: D, version: Version, ec: EcLevel, count: usize) -> Result<Self, QrError> {
+        let encoder = fountain::FountainEncoder::new(data, version, ec)?;
+        let packets = (0..count.min(u32::MAX as usize)).map(|seq| encoder.packet(seq as u32)).collect();
+        Self::from_payloads(packets, version, ec, false)
+    }
+
+    // BC-UR "ur:bytes" parts, scannable by UR aware wallets, decoded again with ur::decode
+    pub fn ur<D: AsRef<[u8]>>(data: D, version: Version, ec: EcLevel) -> Result<Self, QrError> {
+        let parts = ur::encode(data.as_ref(), version, ec)?.into_iter().map(String::into_bytes).collect();
+        // parts are sized for alphanumeric mode, which qrcode picks for them
+        Self::build_codes(parts, Segments::Auto, |_, part, segments| segments.code(part, version, ec))
+    }
+
+    // smallest version that still needs no more codes than version 40 would,
+    // which is a single code whenever the data fits in one
+    pub fn auto<D: AsRef<[u8]>>(data: D, ec: EcLevel) -> Result<Self, QrError> {
+        let data = data.as_ref();
+        let best = chunk_count(data.len(), Version::Normal(40), ec);
+        let version = (1..=40).find(|&v| chunk_count(data.len(), Version::Normal(v), ec) == best).unwrap_or(40);
+        Self::new(data, Version::Normal(version), ec)
+    }
 
-        // calculate sizes
-        let qr_size_total = QR_DATA_LENGTHS[version.to_index()][ec as usize];
-        let qr_size_data = qr_size_total - (1 + slack);
+    // smallest version that splits the data into at most max_codes codes
+    pub fn with_max_codes<D: AsRef<[u8]>>(data: D, max_codes: usize, ec: EcLevel) -> Result<Self, QrError> {
+        let data = data.as_ref();
+        let version = (1..=40)
+            .find(|&v| chunk_count(data.len(), Version::Normal(v), ec).is_some_and(|count| count <= max_codes))
+            .ok_or(QrError::DataTooLong)?;
+        Self::new(data, Version::Normal(version), ec)
+    }
 
-        // create new qr codes for indexed data, add to res
-        let mut qr_data: Vec<u8> = vec!(0; 1+qr_size_data);
-        for (i, part) in data.chunks(qr_size_data).into_iter().enumerate() {
-            qr_data[0] = i as u8;
-            qr_data[1..1+part.len()].clone_from_slice(part);
+    // encodes straight from a reader, one chunk sized read at a time
+    pub fn from_reader<R: Read>(reader: R, version: Version, ec: EcLevel) -> Result<Self, MqrError> {
+        let mut encoder = MultiQrEncoder::new(reader, version, ec)?;
+        let mut payloads = Vec::new();
+        while let Some(payload) = encoder.next_payload()? {
+            payloads.push(payload);
+        }
+        Ok(Self::from_payloads(payloads, version, ec, false)?)
+    }
 
-            res.push(QrCode::with_version(&qr_data[0..1+part.len()], version, ec)?);
+    // with the mmap feature, the file is memory mapped instead of read
+    #[cfg(feature = "fs")]
+    pub fn from_file<P: AsRef<Path>>(path: P, version: Version, ec: EcLevel) -> Result<Self, MqrError> {
+        let file = File::open(path)?;
+        #[cfg(feature = "mmap")]
+        if file.metadata()?.len() > 0 {
+            // safety: the mapping only lives for this call, the file must not be truncated meanwhile
+            let map = unsafe { memmap2::Mmap::map(&file)? };
+            return Self::from_reader(&map[..], version, ec);
         }
+        Self::from_reader(file, version, ec)
+    }
 
-        Ok(MultiQrCode {
-            codes: res
-        })
+    // from_file on tokio's blocking pool, so large files don't stall the executor
+    #[cfg(feature = "async")]
+    pub async fn from_file_async<P: AsRef<Path>>(path: P, version: Version, ec: EcLevel) -> Result<Self, MqrError> {
+        let path = path.as_ref().to_path_buf();
+        async_io::blocking(move || Self::from_file(path, version, ec)).await
     }
 
     pub fn default<D: AsRef<[u8]>>(data: D) -> Result<Self, QrError> {
         Self::new(data, DEFAULT_QR_VERSION, DEFAULT_EC_LEVEL)
     }
 
+    // raw bytes (header included) stored in each code, in the same order as codes
+    pub fn payloads(&self) -> &[Vec<u8>] {
+        &self.payloads
+    }
+
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, QrCode> {
+        self.codes.iter()
+    }
+
+    // chunk sizes, overhead and how full the codes are, for sets made with the legacy header
+    pub fn stats(&self) -> Stats {
+        self.stats_with(HeaderFormat::Legacy)
+    }
+
+    // stats for a set made with the given header
+    pub fn stats_with(&self, header: HeaderFormat) -> Stats {
+        Stats::new(self, header)
+    }
+
+    // adds data to the end of a set made with the legacy header, in new codes of the given
+    // version and ec level. the existing codes are kept as they are, a short last chunk included
+    pub fn append<D: AsRef<[u8]>>(&mut self, data: D, version: Version, ec: EcLevel) -> Result<(), MqrError> {
+        self.append_with(data, version, ec, HeaderFormat::Legacy)
+    }
+
+    // append for a set made with the given header. formats with a total or a checksum have
+    // every header rewritten, so the existing codes are re-encoded with the same version, ec
+    // level and segments. sets with flags (compression, encryption, chunk crcs or parity) or a
+    // manifest can't be extended
+    pub fn append_with<D: AsRef<[u8]>>(&mut self, data: D, version: Version, ec: EcLevel, format: HeaderFormat) -> Result<(), MqrError> {
+        check_byte_mode(version)?;
+        let size = chunk_size(version, ec, format.size() + byte_mode_slack(version)).ok_or(QrError::DataTooLong)?;
+        let chunks = data.as_ref().chunks(size).map(|chunk| (chunk.to_vec(), version, ec, Segments::Bytes { eci: false })).collect();
+        self.extend_chunks(chunks, format)
+    }
+
+    // the chunks of other after the chunks of self, both made with the legacy header. other's
+    // codes keep their version, ec level and segments, only their indices change
+    pub fn merge(&mut self, other: &MultiQrCode) -> Result<(), MqrError> {
+        self.merge_with(other, HeaderFormat::Legacy)
+    }
+
+    // merge for sets made with the given header, see append_with
+    pub fn merge_with(&mut self, other: &MultiQrCode, format: HeaderFormat) -> Result<(), MqrError> {
+        let chunks = other.chunks(format)?;
+        self.extend_chunks(chunks, format)
+    }
+
+    // chunk data of every code with the version, ec level and segments it was encoded with
+    fn chunks(&self, format: HeaderFormat) -> Result<Vec<Chunk>, MqrError> {
+        // the index bytes of a manifest set would read as legacy headers
+        if self.payloads.first().is_some_and(|payload| Manifest::read(payload).is_ok()) {
+            return Err(MqrError::InvalidOptions("sets with a manifest can't be appended to or merged"))
+        }
+        self.codes.iter().zip(&self.payloads).zip(&self.segments).map(|((code, payload), &segments)| {
+            let (header, data) = format.read(payload)?;
+            if header.flags != 0 {
+                return Err(MqrError::InvalidOptions("sets with flags can't be appended to or merged"))
+            }
+            Ok((data.to_vec(), code.version(), code.error_correction_level(), segments))
+        }).collect()
+    }
+
+    // rewrites the headers for the existing chunks followed by new ones. codes whose payload
+    // comes out the same are reused
+    fn extend_chunks(&mut self, new: Vec<Chunk>, format: HeaderFormat) -> Result<(), MqrError> {
+        if new.is_empty() {
+            return Ok(())
+        }
+        let mut chunks = self.chunks(format)?;
+        chunks.extend(new);
+        let parts: Vec<&[u8]> = chunks.iter().map(|(chunk, ..)| chunk.as_slice()).collect();
+        let payloads = with_headers(&parts.concat(), &parts, parts.len(), format, 0)?;
+        let segments = chunks.iter().map(|&(_, _, _, segments)| segments).collect();
+        *self = Self::build_each(payloads, segments, |i, payload, segments| match self.payloads.get(i) {
+            Some(kept) if kept[..] == *payload => Ok(self.codes[i].clone()),
+            _ => segments.code(payload, chunks[i].1, chunks[i].2)
+        })?;
+        Ok(())
+    }
+
+    // inverse of new: takes scanned payloads in any order and returns the original data
+    pub fn reassemble<I, P>(payloads: I) -> Result<Vec<u8>, DecodeError>
+    where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+        decode::reassemble(payloads)
+    }
+
+    // reassemble for payloads written by with_header
+    pub fn reassemble_with<I, P>(payloads: I, format: HeaderFormat) -> Result<Vec<u8>, DecodeError>
+    where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+        decode::reassemble_with(payloads, format)
+    }
+
+    // reassemble for payloads written with Encryption::AesGcm
+    #[cfg(feature = "aes-gcm")]
+    pub fn reassemble_decrypt<I, P>(payloads: I, format: HeaderFormat, key: &[u8; 32]) -> Result<Vec<u8>, DecodeError>
+    where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+        decode::reassemble_decrypt(payloads, format, key)
+    }
+
+    // reassemble for payloads written with Encryption::Passphrase
+    #[cfg(feature = "passphrase")]
+    pub fn reassemble_passphrase<I, P>(payloads: I, format: HeaderFormat, passphrase: &str) -> Result<Vec<u8>, DecodeError>
+    where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+        decode::reassemble_passphrase(payloads, format, passphrase)
+    }
+
+    // reassemble for codes built with a manifest, see Manifest
+    pub fn reassemble_manifest<I, P>(payloads: I) -> Result<(Manifest, Vec<u8>), DecodeError>
+    where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+        decode::reassemble_manifest(payloads)
+    }
+
+    // reassemble for a set built from a Payload, giving back the file's name, type and time with its bytes
+    pub fn reassemble_payload<I, P>(payloads: I, format: HeaderFormat) -> Result<Payload, DecodeError>
+    where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+        Payload::from_bytes(&decode::reassemble_with(payloads, format)?)
+    }
+
+    // reassemble_payload for payloads written with Encryption::AesGcm
+    #[cfg(feature = "aes-gcm")]
+    pub fn reassemble_payload_decrypt<I, P>(payloads: I, format: HeaderFormat, key: &[u8; 32]) -> Result<Payload, DecodeError>
+    where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+        Payload::from_bytes(&decode::reassemble_decrypt(payloads, format, key)?)
+    }
+
+    // reassemble_payload for payloads written with Encryption::Passphrase
+    #[cfg(feature = "passphrase")]
+    pub fn reassemble_payload_passphrase<I, P>(payloads: I, format: HeaderFormat, passphrase: &str) -> Result<Payload, DecodeError>
+    where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+        Payload::from_bytes(&decode::reassemble_passphrase(payloads, format, passphrase)?)
+    }
+
+    // reassemble_payload for codes built with a manifest, which is returned along with the payload
+    pub fn reassemble_payload_manifest<I, P>(payloads: I) -> Result<(Manifest, Payload), DecodeError>
+    where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+        let (manifest, data) = decode::reassemble_manifest(payloads)?;
+        Ok((manifest, Payload::from_bytes(&data)?))
+    }
+
+    // scans every image in a directory and reassembles what it finds, see decode::decode_dir
+    #[cfg(all(feature = "rqrr", feature = "fs"))]
+    pub fn decode_dir<P: AsRef<Path>>(dir: P) -> Result<decode::DirDecode, MqrError> {
+        decode::decode_dir(dir, HeaderFormat::Legacy)
+    }
+
+    #[cfg(all(feature = "rqrr", feature = "fs"))]
+    pub fn decode_dir_with<P: AsRef<Path>>(dir: P, format: HeaderFormat) -> Result<decode::DirDecode, MqrError> {
+        decode::decode_dir(dir, format)
+    }
+
+    // scans a video or animated gif frame by frame, see video::decode_video
+    #[cfg(feature = "video")]
+    pub fn decode_video<P: AsRef<Path>>(path: P, format: HeaderFormat) -> Result<Vec<u8>, MqrError> {
+        video::decode_video(path, format)
+    }
+
+    // caches what the text and image exports render, for calling more than one of them
+    #[cfg(feature = "render-text")]
+    pub fn rendered(&self) -> RenderedMultiQrCode<'_> {
+        RenderedMultiQrCode::new(self)
+    }
+
+    // data from one of the exports, e.g. to_base64, paired up with the index, version and ec
+    // level of its code. data has to come from this set, extra entries are dropped
+    #[cfg(feature = "render-text")]
+    pub fn indexed(&self, data: Vec<QrData>) -> Vec<IndexedQrData> {
+        let total = self.codes.len();
+        self.codes.iter().zip(data).enumerate().map(|(index, (code, data))| IndexedQrData {
+            index,
+            total,
+            version: code.version(),
+            ec: code.error_correction_level(),
+            data
+        }).collect()
+    }
+
+    #[cfg(feature = "render-text")]
     pub fn to_strings(&self) -> Vec<QrData> {
-        self.codes.iter().map(|code| QrData::String(code.render().light_color(' ').dark_color('#').build().to_string())).collect()
+        self.to_strings_styled(TextStyle::Full)
+    }
+
+    #[cfg(feature = "render-text")]
+    pub fn to_strings_styled(&self, style: TextStyle) -> Vec<QrData> {
+        self.map_codes(|code| QrData::String(text::render(code, style)))
+    }
+
+    // one string per module like to_strings, with custom characters
+    #[cfg(feature = "render-text")]
+    pub fn to_strings_with(&self, options: &TextRenderOptions) -> Vec<QrData> {
+        self.map_codes(|code| QrData::String(text::render_with(code, options)))
     }
 
+    #[cfg(feature = "render-text")]
     pub fn to_base64(&self) -> Vec<QrData> {
-        self.codes.iter().map(|code| QrData::base64_from(code)).collect()
+        self.map_codes(QrData::base64_from)
     }
 
-    pub fn save(&self, path: &str) {
-        let path = Path::new(path);
-        for (i, code) in self.codes.iter().enumerate() {
-            code.render::<Luma<u8>>().build().save(path.with_extension(format!("{}.png", i))).unwrap();
+    #[cfg(feature = "render-text")]
+    pub fn to_base64_with(&self, variant: Base64Variant) -> Vec<QrData> {
+        self.map_codes(|code| QrData::base64_with(code, variant))
+    }
+
+    // the text of every code back to back with no separator, what to_string gave before Display
+    #[cfg(feature = "render-text")]
+    pub fn to_concatenated_string(&self) -> String {
+        self.map_codes(|code| text::render(code, TextStyle::Full)).concat()
+    }
+
+    // base45 text of every code, smaller than base64 once stored in alphanumeric mode
+    #[cfg(feature = "render-text")]
+    pub fn to_base45(&self) -> Vec<QrData> {
+        self.map_codes(QrData::base45_from)
+    }
+
+    #[cfg(feature = "render-text")]
+    pub fn to_hex(&self) -> Vec<QrData> {
+        self.map_codes(QrData::hex_from)
+    }
+
+    #[cfg(feature = "render-text")]
+    pub fn to_raw(&self) -> Vec<QrData> {
+        self.map_codes(QrData::raw_from)
+    }
+
+    // the modules of every code, for toolkits and renderers that draw codes themselves
+    pub fn to_matrices(&self) -> Vec<BitMatrix> {
+        self.codes.iter().map(BitMatrix::from_code).collect()
+    }
+
+    // all codes in one compact binary blob, None when their widths differ
+    pub fn to_data_set(&self) -> Option<QrDataSet> {
+        QrDataSet::from_codes(&self.codes)
+    }
+
+    // shows the codes one after another in the terminal, space pauses, arrow keys step and q quits
+    #[cfg(feature = "terminal")]
+    pub fn play_in_terminal(&self, fps: u32) -> Result<(), MqrError> {
+        if fps == 0 {
+            return Err(MqrError::InvalidOptions("fps must be at least 1"))
         }
+        let frames: Vec<String> = self.to_strings().into_iter().filter_map(|data| match data {
+            QrData::String(s) => Some(s),
+            _ => None
+        }).collect();
+        Ok(player::play(&frames, fps)?)
+    }
+
+    // scalable svg documents, one per code
+    #[cfg(feature = "render-text")]
+    pub fn to_svgs(&self) -> Vec<QrData> {
+        self.map_codes(|code| QrData::Svg(code.render::<svg::Color>().build()))
     }
-} impl ToString for MultiQrCode {
-    fn to_string(&self) -> String {
-        let strings = self.to_strings();
-        let mut string = String::new();
-        for qr_data in strings {
-            if let QrData::String(x) = qr_data { string.push_str(&x) }
+
+    // black on white svgs like to_svgs with a light border of quiet_zone modules, 0 for none.
+    // to_svgs_with takes the colors and scale as well
+    #[cfg(feature = "render-text")]
+    pub fn to_svgs_with_quiet_zone(&self, quiet_zone: usize) -> Vec<QrData> {
+        self.map_codes(|code| QrData::Svg(text::svg(code, quiet_zone, 8, "fill=\"#fff\"", "fill=\"#000\"")))
+    }
+
+    // svgs drawn with the colors, quiet zone and module size of options
+    #[cfg(feature = "render-image")]
+    pub fn to_svgs_with(&self, options: &SaveOptions) -> Vec<QrData> {
+        self.map_codes(|code| QrData::Svg(render::render_svg(code, options)))
+    }
+
+    // every code as a png next to path, e.g. backup.0.png for backup.png. save_with and the
+    // default SaveOptions, so the same overwrite and directory checks apply
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    pub fn save(&self, path: &str) -> Result<(), MqrError> {
+        let options = SaveOptions { format: Some(ImageFormat::Png), ..Default::default() };
+        self.save_with(Path::new(path).with_extension("png"), &options)?;
+        Ok(())
+    }
+
+    // save on tokio's blocking pool, the codes are copied for the task
+    #[cfg(all(feature = "async", feature = "render-image"))]
+    pub async fn save_async(&self, path: &str) -> Result<(), MqrError> {
+        let (qr, path) = (self.to_owned_set(), path.to_string());
+        async_io::blocking(move || qr.save(&path)).await
+    }
+
+    #[cfg(all(feature = "async", feature = "render-image"))]
+    pub async fn save_with_async<P: AsRef<Path>>(&self, path: P, options: &SaveOptions) -> Result<Vec<PathBuf>, MqrError> {
+        let (qr, path, options) = (self.to_owned_set(), path.as_ref().to_path_buf(), options.clone());
+        async_io::blocking(move || qr.save_with(path, &options)).await
+    }
+
+    #[cfg(all(feature = "async", feature = "render-image"))]
+    fn to_owned_set(&self) -> MultiQrCode {
+        MultiQrCode { codes: self.codes.clone(), payloads: self.payloads.clone(), segments: self.segments.clone() }
+    }
+
+    // rendered like save does, for post-processing or display without re-rendering
+    #[cfg(feature = "render-image")]
+    pub fn to_images(&self) -> Vec<GrayImage> {
+        self.map_codes(code_image)
+    }
+
+    // rendered with custom scale, colors or shapes, see SaveOptions
+    #[cfg(feature = "render-image")]
+    pub fn to_images_with(&self, options: &SaveOptions) -> Result<Vec<RgbaImage>, MqrError> {
+        self.render_all(options)
+    }
+
+    // indices of the codes that don't scan back to their payload once rendered like save does.
+    // catches versions and ec levels that are valid but too dense to read, before printing them.
+    // rqrr doesn't read micro qr codes, sets with any fail with InvalidOptions
+    #[cfg(all(feature = "rqrr", feature = "render-image"))]
+    pub fn verify(&self) -> Result<Vec<usize>, MqrError> {
+        self.check_scannable()?;
+        Ok(self.unreadable(&self.to_images()))
+    }
+
+    // verify for codes rendered with custom scale, colors, shapes or quiet zone
+    #[cfg(all(feature = "rqrr", feature = "render-image"))]
+    pub fn verify_with(&self, options: &SaveOptions) -> Result<Vec<usize>, MqrError> {
+        self.check_scannable()?;
+        let images: Vec<GrayImage> = self.render_all(options)?.into_iter().map(|image| DynamicImage::ImageRgba8(image).to_luma8()).collect();
+        Ok(self.unreadable(&images))
+    }
+
+    #[cfg(all(feature = "rqrr", feature = "render-image"))]
+    fn check_scannable(&self) -> Result<(), MqrError> {
+        if self.codes.iter().any(|code| matches!(code.version(), Version::Micro(_))) {
+            return Err(MqrError::InvalidOptions("micro qr codes can't be verified, rqrr doesn't read them"))
         }
-        string
+        Ok(())
     }
-}
 
-// simple addition to Version to support easy conversion to index number on tables
-trait ToIndex { 
-    fn to_index(&self) -> usize; 
-} impl ToIndex for Version {
-    fn to_index(&self) -> usize {
-        (match self {
-            Version::Normal(x) => x-1,
-            Version::Micro(x) => x+39
-        }) as usize
+    #[cfg(all(feature = "rqrr", feature = "render-image"))]
+    fn unreadable(&self, images: &[GrayImage]) -> Vec<usize> {
+        decode::map_items(images, decode::scan).into_iter().enumerate()
+            .filter(|(i, scanned)| scanned[..] != self.payloads[*i..=*i])
+            .map(|(i, _)| i)
+            .collect()
     }
-} 
 
-#[cfg(test)]
-mod tests {
-    use more_asserts::assert_le;
+    // encoded png files in memory, in the same order as codes
+    #[cfg(feature = "render-image")]
+    pub fn to_png_bytes(&self) -> Result<Vec<Vec<u8>>, MqrError> {
+        self.map_indexed(|i| {
+            let mut bytes = Vec::new();
+            self.write_png(i, &mut bytes)?;
+            Ok(bytes)
+        }).into_iter().collect()
+    }
 
-    use super::*;
+    // the png of a single code, rendered like save does
+    #[cfg(feature = "render-image")]
+    pub fn write_png<W: std::io::Write>(&self, index: usize, mut writer: W) -> Result<(), MqrError> {
+        let code = self.codes.get(index).ok_or(MqrError::InvalidOptions("code index out of range"))?;
+        let image = DynamicImage::ImageLuma8(code_image(code));
+        Ok(image.write_to(&mut writer, image::ImageFormat::Png)?)
+    }
 
-    const LIPSUM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Integer tristique turpis eros, vel hendrerit purus laoreet vitae. Praesent consectetur non mauris quis fermentum. Vivamus vulputate mi sed felis mollis dapibus. Interdum et malesuada fames ac ante ipsum primis in faucibus. Quisque vel tortor vitae ex blandit sodales in id diam. Morbi urna quam, lobortis id tristique a, imperdiet ac neque. Vivamus metus enim, posuere sed imperdiet in, tempor in purus. Nunc molestie, eros at dignissim commodo, ex eros ultrices augue, nec tristique turpis neque at ipsum. Phasellus sed malesuada justo. Sed lacus tellus, sodales quis pellentesque vitae, sagittis id augue. Quisque sit amet suscipit enim, in luctus lorem. Curabitur condimentum augue ac ornare mattis. Donec tempor pretium maximus.
+    // data:image/png;base64 uris, ready for html img tags or json responses
+    #[cfg(feature = "render-image")]
+    pub fn to_data_uris(&self) -> Result<Vec<String>, MqrError> {
+        Ok(self.to_png_bytes()?.iter().map(|png| format!("data:image/png;base64,{}", Base64Variant::Standard.encode(png))).collect())
+    }
 
-    Cras tempus lacinia sagittis. Vestibulum suscipit nisi sit amet ipsum hendrerit, sit amet iaculis mauris auctor. Vestibulum dictum felis nec risus egestas vulputate. Donec sit amet mauris a mauris venenatis suscipit. Vivamus elementum faucibus ornare. Interdum et malesuada fames ac ante ipsum primis in faucibus. Suspendisse ornare tellus id nisl condimentum condimentum. Suspendisse placerat non nisi sit amet tempor. Curabitur ante lacus, tempor in vulputate eget, vestibulum ut arcu. Sed quam massa, mattis a efficitur cursus, vehicula a purus. Aenean leo justo, malesuada et massa condimentum, pellentesque fermentum lectus.
-    
-    Aliquam rhoncus augue vitae metus congue hendrerit. Nulla tempus turpis urna, sed feugiat nisi vestibulum vitae. Suspendisse at bibendum urna. Phasellus malesuada urna eu imperdiet semper. Morbi rhoncus felis ac ex vestibulum, quis semper eros suscipit. Donec in dignissim eros. In ultrices fermentum purus vitae tempus. Morbi vel ipsum at est laoreet commodo. Morbi iaculis, enim sed iaculis vestibulum, diam arcu aliquet diam, nec eleifend nisl dolor in nunc. Sed dictum elit eget hendrerit feugiat. Donec feugiat lectus at est dignissim, ut ullamcorper dui euismod. Nunc commodo, quam ac efficitur rhoncus, dolor arcu volutpat felis, sed auctor leo neque eget enim. Donec lobortis at orci eu consectetur.
-    
-    Cras convallis neque vitae nisl ullamcorper, a dictum eros ultricies. Etiam purus arcu, faucibus egestas mollis ac, pulvinar ut dolor. Suspendisse potenti. Vivamus id elementum est. Donec vel ornare nulla. Sed laoreet maximus sodales. Nam ullamcorper molestie ipsum quis ultrices. Phasellus aliquam nisi vel iaculis convallis. Quisque volutpat libero nec ipsum rutrum, vel fermentum augue eleifend. Proin vitae libero eu lectus convallis sodales. Etiam posuere nulla sit amet elit viverra placerat. Praesent quis risus in eros fermentum tincidunt. Sed ipsum elit, tempor et nisi sed, bibendum efficitur est. Nullam ultricies arcu mi, at maximus dui mollis nec. Fusce mauris nisl, porttitor ut lacus at, malesuada varius arcu. Nulla facilisi.
-    
-    Phasellus consequat dictum eros, sit amet lobortis orci volutpat in. Orci varius natoque penatibus et magnis dis parturient montes, nascetur ridiculus mus. Nullam auctor bibendum venenatis. Cras est libero, feugiat sit amet massa id, semper lacinia velit. Phasellus justo augue, consequat ut dictum ut, gravida nec neque. Duis iaculis nisi vitae augue pellentesque ornare. Curabitur ullamcorper a urna ut lobortis. Praesent fermentum mattis facilisis. In hac habitasse platea dictumst. Vivamus nec lorem ullamcorper, fringilla tellus sit amet, facilisis nibh. Suspendisse id metus elementum, dignissim risus ac, blandit risus. Cras non ex at augue varius rhoncus. Pellentesque at ex nibh. Cras porttitor lobortis imperdiet. Vestibulum quis euismod turpis.";
+    // a single html page embedding every code, see HtmlOptions
+    #[cfg(feature = "render-image")]
+    pub fn to_html(&self, options: &HtmlOptions) -> Result<String, MqrError> {
+        Ok(html::render(&self.to_data_uris()?, options))
+    }
 
-    #[test]
-    #[ignore]
-    fn confirm_minimum_slack() {
-        for version in 1_usize..41_usize {
-            let mut slack: usize = 0;
-            loop {
-                match MultiQrCode::with_slack(LIPSUM, Version::Normal(version as i16), DEFAULT_EC_LEVEL, slack) {
-                    Ok(_) => {
-                        println!("Minimum slack for version {} is {}", version, slack);
-                        assert_le!(slack, QR_VERSION_SLACK[version-1], "Invalid slack for version {}: {} > {}", version, slack, QR_VERSION_SLACK[version-1]);
-                        break;
-                    }
-                    _ => {
-                        slack += 1;
-                        continue;
-                    }
-                }
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    pub fn save_html<P: AsRef<Path>>(&self, path: P, options: &HtmlOptions) -> Result<(), MqrError> {
+        Ok(std::fs::write(path, self.to_html(options)?)?)
+    }
+
+    // a single code in the format of options, png unless set
+    #[cfg(feature = "render-image")]
+    pub fn write_image<W: std::io::Write>(&self, index: usize, writer: W, options: &SaveOptions) -> Result<(), MqrError> {
+        if index >= self.codes.len() {
+            return Err(MqrError::InvalidOptions("code index out of range"))
+        }
+        format::write(self.render_code(index, options)?, options.format.unwrap_or_default(), writer)
+    }
+
+    // every code encoded like write_image and handed to f one at a time, so they can go straight
+    // to storage, a response or a printer without the filesystem or all of them in memory.
+    // an error from f stops the loop and is returned
+    #[cfg(feature = "render-image")]
+    pub fn for_each_rendered<E, F>(&self, options: &SaveOptions, mut f: F) -> Result<(), E>
+    where E: From<MqrError>, F: FnMut(usize, &[u8]) -> Result<(), E> {
+        let tracker = progress::Tracker::rendering(options.progress.as_deref(), self.codes.len());
+        let mut bytes = Vec::new();
+        for index in 0..self.codes.len() {
+            tracker.check()?;
+            bytes.clear();
+            self.write_image(index, &mut bytes, options)?;
+            tracker.step()?;
+            f(index, &bytes)?;
+        }
+        Ok(())
+    }
+
+    // for_each_rendered for the svgs of to_svgs_with
+    #[cfg(feature = "render-image")]
+    pub fn for_each_svg<E, F>(&self, options: &SaveOptions, mut f: F) -> Result<(), E>
+    where E: From<MqrError>, F: FnMut(usize, &str) -> Result<(), E> {
+        for (index, code) in self.codes.iter().enumerate() {
+            f(index, &render::render_svg(code, options))?;
+        }
+        Ok(())
+    }
+
+    // for_each_rendered for the text of to_strings_with
+    #[cfg(feature = "render-text")]
+    pub fn for_each_text<E, F>(&self, options: &TextRenderOptions, mut f: F) -> Result<(), E>
+    where E: From<MqrError>, F: FnMut(usize, &str) -> Result<(), E> {
+        for (index, code) in self.codes.iter().enumerate() {
+            f(index, &text::render_with(code, options))?;
+        }
+        Ok(())
+    }
+
+    // save with a custom scale, colors or logo, see SaveOptions
+    // path gives the directory, {stem} and {ext} of the file name template. returns the files
+    // written, which leaves out the ones kept by Overwrite::Skip
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    pub fn save_with<P: AsRef<Path>>(&self, path: P, options: &SaveOptions) -> Result<Vec<PathBuf>, MqrError> {
+        let path = path.as_ref();
+        let format = options.format.or_else(|| ImageFormat::from_path(path)).unwrap_or_default();
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("qr");
+        let ext = path.extension().and_then(|s| s.to_str()).unwrap_or(format.extension());
+        let dir = path.parent().unwrap_or(Path::new(""));
+        if options.create_dirs {
+            std::fs::create_dir_all(dir)?;
+        } else if !dir.as_os_str().is_empty() && !dir.is_dir() {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("directory {} does not exist", dir.display())).into())
+        }
+        let paths = self.file_names(path.parent(), stem, ext, &options.file_name)?;
+        let manifest = options.manifest_json.then(|| dir.join(json::FILE_NAME));
+        if options.overwrite == Overwrite::Error {
+            if let Some(existing) = paths.iter().chain(&manifest).find(|path| path.exists()) {
+                return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, format!("{} already exists", existing.display())).into())
             }
         }
+        let skip = |path: &Path| options.overwrite == Overwrite::Skip && path.exists();
+
+        let tracker = progress::Tracker::rendering(options.progress.as_deref(), self.codes.len());
+        let written = self.map_indexed(|i| {
+            tracker.check()?;
+            let path = &paths[i];
+            let written = if skip(path) {
+                None
+            } else {
+                let file = std::io::BufWriter::new(options.overwrite.create(path)?);
+                format::write(self.render_code(i, options)?, format, file)?;
+                Some(path.clone())
+            };
+            tracker.step()?;
+            Ok(written)
+        }).into_iter().collect::<Result<Vec<_>, MqrError>>()?;
+        let mut written: Vec<PathBuf> = written.into_iter().flatten().collect();
+        if let Some(manifest) = manifest.filter(|path| !skip(path)) {
+            std::io::Write::write_all(&mut options.overwrite.create(&manifest)?, json::manifest(self, &paths).as_bytes())?;
+            written.push(manifest);
+        }
+        Ok(written)
     }
 
-    #[test]
-    #[ignore]
-    fn compare_string_vs_base64() {
-        let qr = MultiQrCode::default("Hello world!").unwrap();
-        let string = serde_json::to_string(&qr.to_strings()).unwrap();
-        let base64 = serde_json::to_string(&qr.to_base64()).unwrap();
-        println!("String:\n{}\nBase64:\n{}",string,base64);
-        assert_le!(base64.len(), string.len());
+    // saves every code into dir, creating it if needed, with {stem} set to "qr"
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    pub fn save_to_dir<P: AsRef<Path>>(&self, dir: P, options: &SaveOptions) -> Result<Vec<PathBuf>, MqrError> {
+        self.save_with(dir.as_ref().join("qr.png"), &SaveOptions { create_dirs: true, ..options.clone() })
     }
 
-    #[test]
-    fn print_hello() {
-        let qr = MultiQrCode::new("Hello world!", Version::Normal(10), EcLevel::L).unwrap();
-        println!("{}", qr.to_string());
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    fn file_names(&self, dir: Option<&Path>, stem: &str, ext: &str, template: &str) -> Result<Vec<PathBuf>, MqrError> {
+        let total = self.codes.len();
+        (0..total).map(|i| {
+            let name = naming::file_name(template, stem, ext, i, total)?;
+            Ok(dir.map_or_else(|| PathBuf::from(&name), |dir| dir.join(&name)))
+        }).collect()
     }
 
-    #[test]
-    fn save_hello() {
-        let qr = MultiQrCode::default("Hello world!").unwrap();
-        qr.save("./test-hw.png");
+    // a logo hides modules, so the payload is re-encoded at EcLevel::H in the same version. that
+    // fails with DataTooLong for full chunks unless the set was made with EncodeOptions::logo
+    #[cfg(feature = "render-image")]
+    fn render_code(&self, index: usize, options: &SaveOptions) -> Result<image::RgbaImage, QrError> {
+        let code = &self.codes[index];
+        let image = if options.render.logo.is_some() && code.error_correction_level() != EcLevel::H {
+            let boosted = self.segments[index].code(&self.payloads[index], code.version(), EcLevel::H)?;
+            render::render(&boosted, options)
+        } else {
+            render::render(code, options)
+        };
+        Ok(match &options.caption {
+            Some(template) => {
+                let (foreground, background) = options.render.colors();
+                sheet::caption(&image, &naming::caption(template, index, self.codes.len()), foreground, background)
+            }
+            None => image
+        })
     }
 
-    #[test]
-    fn print_lipsum() {
-        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
-        println!("{}", qr.to_string());
+    // all codes tiled onto printable pages, see PageOptions
+    pub fn to_pdf(&self, options: &PageOptions) -> Result<Vec<u8>, MqrError> {
+        if options.per_row == 0 {
+            return Err(MqrError::InvalidOptions("per_row must be at least 1"))
+        }
+        Ok(pdf::render(&self.codes, options))
     }
 
-    #[test]
-    fn save_lipsum() {
-        let qr = MultiQrCode::default(LIPSUM).unwrap();
-        qr.save("./test-lipsum.png");
-    }
-}
-
-// This table is from <ISO/IEC 18004:2006 §6.4.10, Table 7> but converted into bytes and with no Micro version.
-pub const QR_DATA_LENGTHS: [[usize; 4]; 40] = [
-    [19, 16, 13, 9],
-    [34, 28, 22, 16],
-    [55, 44, 34, 26],
-    [80, 64, 48, 36],
-    [108, 86, 62, 46],
-    [136, 108, 76, 60],
-    [156, 124, 88, 66],
-    [194, 154, 110, 86],
-    [232, 182, 132, 100],
-    [274, 216, 154, 122],
-    [324, 254, 180, 140],
-    [370, 290, 206, 158],
-    [428, 334, 244, 180],
-    [461, 365, 261, 197],
-    [523, 415, 295, 223],
-    [589, 453, 325, 253],
-    [647, 507, 367, 283],
-    [721, 563, 397, 313],
-    [795, 627, 445, 341],
-    [861, 669, 485, 385],
-    [932, 714, 512, 406],
-    [1006, 782, 568, 442],
-    [1094, 860, 614, 464],
-    [1174, 914, 664, 514],
-    [1276, 1000, 718, 538],
-    [1370, 1062, 754, 596],
-    [1468, 1128, 808, 628],
-    [1531, 1193, 871, 661],
-    [1631, 1267, 911, 701],
-    [1735, 1373, 985, 745],
-    [1843, 1455, 1033, 793],
-    [1955, 1541, 1115, 845],
-    [2071, 1631, 1171, 901],
-    [2191, 1725, 1231, 961],
-    [2306, 1812, 1286, 986],
-    [2434, 1914, 1354, 1054],
-    [2566, 1992, 1426, 1096],
-    [2702, 2102, 1502, 1142],
-    [2812, 2216, 1582, 1222],
-    [2956, 2334, 1666, 1276]
-];
+    #[cfg(feature = "fs")]
+    pub fn save_pdf<P: AsRef<Path>>(&self, path: P, options: &PageOptions) -> Result<(), MqrError> {
+        Ok(std::fs::write(path, self.to_pdf(options)?)?)
+    }
 
-pub const QR_VERSION_SLACK: [usize; 40] = [
+    // encapsulated postscript, one document per code with module points per module
+    pub fn to_eps(&self, module: f32) -> Result<Vec<String>, MqrError> {
+        self.to_eps_with(module, pdf::QUIET_ZONE)
+    }
+
+    // to_eps with a light border of quiet_zone modules around every code, 0 for none
+    pub fn to_eps_with(&self, module: f32, quiet_zone: usize) -> Result<Vec<String>, MqrError> {
+        if !(module.is_finite() && module > 0.0) {
+            return Err(MqrError::InvalidOptions("module must be a positive size"))
+        }
+        Ok(self.codes.iter().map(|code| eps::code(code, module, quiet_zone)).collect())
+    }
+
+    // all codes in one encapsulated postscript grid, captioned like to_pdf. caption is a
+    // naming::caption template, None for "3 of 12"
+    pub fn to_eps_sheet(&self, cols: usize, module: f32, caption: Option<&str>) -> Result<String, MqrError> {
+        self.to_eps_sheet_with(cols, module, caption, pdf::QUIET_ZONE)
+    }
+
+    // to_eps_sheet with a light border of quiet_zone modules around every code, 0 for none
+    pub fn to_eps_sheet_with(&self, cols: usize, module: f32, caption: Option<&str>, quiet_zone: usize) -> Result<String, MqrError> {
+        if cols == 0 {
+            return Err(MqrError::InvalidOptions("cols must be at least 1"))
+        }
+        if !(module.is_finite() && module > 0.0) {
+            return Err(MqrError::InvalidOptions("module must be a positive size"))
+        }
+        Ok(eps::sheet(&self.codes, cols, module, caption, quiet_zone))
+    }
+
+    // esc/pos commands printing every code on a thermal printer, one command buffer per code
+    #[cfg(feature = "escpos")]
+    pub fn to_escpos(&self, options: &EscPosOptions) -> Result<Vec<Vec<u8>>, MqrError> {
+        (0..self.codes.len()).map(|i| escpos::code(&self.codes[i], i, self.codes.len(), options)).collect()
+    }
+
+    // the whole set in one go, e.g. to a printer device or a raw socket
+    #[cfg(feature = "escpos")]
+    pub fn write_escpos<W: std::io::Write>(&self, mut writer: W, options: &EscPosOptions) -> Result<(), MqrError> {
+        for commands in self.to_escpos(options)? {
+            writer.write_all(&commands)?;
+        }
+        Ok(writer.flush()?)
+    }
+
+    // one multi-page tiff with a bilevel page per code, see TiffOptions
+    pub fn to_multipage_tiff(&self, options: &TiffOptions) -> Result<Vec<u8>, MqrError> {
+        if options.dpi == 0 || options.module_px == 0 {
+            return Err(MqrError::InvalidOptions("dpi and module_px must be at least 1"))
+        }
+        // a tiff needs at least one page
+        if self.is_empty() {
+            return Err(MqrError::InvalidOptions("an empty set has no pages"))
+        }
+        tiff::render(&self.codes, options)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn save_multipage_tiff<P: AsRef<Path>>(&self, path: P, options: &TiffOptions) -> Result<(), MqrError> {
+        Ok(std::fs::write(path, self.to_multipage_tiff(options)?)?)
+    }
+
+    // a printable backup: a cover page with the title, date, a hash of the data and how to
+    // restore it, then the codes as in to_pdf. options.header must match the set's header
+    pub fn to_paper_backup(&self, options: &BackupOptions) -> Result<Vec<u8>, MqrError> {
+        paper::render(self, options)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn save_paper_backup<P: AsRef<Path>>(&self, path: P, options: &BackupOptions) -> Result<(), MqrError> {
+        Ok(std::fs::write(path, self.to_paper_backup(options)?)?)
+    }
+
+    // all codes tiled into one labelled grid image, spacing is in pixels
+    #[cfg(feature = "render-image")]
+    pub fn to_sheet(&self, cols: usize, spacing: u32) -> Result<RgbaImage, MqrError> {
+        self.to_sheet_with(cols, spacing, &SaveOptions::default())
+    }
+
+    #[cfg(feature = "render-image")]
+    pub fn to_sheet_with(&self, cols: usize, spacing: u32, options: &SaveOptions) -> Result<RgbaImage, MqrError> {
+        if cols == 0 {
+            return Err(MqrError::InvalidOptions("cols must be at least 1"))
+        }
+        // the sheet draws the caption itself, in the space it leaves under every tile
+        let tiles = self.render_all(&SaveOptions { caption: None, ..options.clone() })?;
+        let template = options.caption.as_deref().unwrap_or("{number} of {total}");
+        let labels: Vec<String> = (0..tiles.len()).map(|i| naming::caption(template, i, tiles.len())).collect();
+        let (foreground, background) = options.render.colors();
+        Ok(sheet::render(&tiles, &labels, cols, spacing, foreground, background))
+    }
+
+    #[cfg(feature = "render-image")]
+    fn render_all(&self, options: &SaveOptions) -> Result<Vec<RgbaImage>, MqrError> {
+        let tracker = progress::Tracker::rendering(options.progress.as_deref(), self.codes.len());
+        self.map_indexed(|i| {
+            tracker.check()?;
+            let image = self.render_code(i, options)?;
+            tracker.step()?;
+            Ok(image)
+        }).into_iter().collect()
+    }
+
+    #[cfg(feature = "render-text")]
+    fn map_codes<T: Send, F: Fn(&QrCode) -> T + Sync + Send>(&self, f: F) -> Vec<T> {
+        self.map_indexed(|i| f(&self.codes[i]))
+    }
+
+    // f for every code index in order, spread over all cores with the parallel feature
+    #[cfg(feature = "render-text")]
+    fn map_indexed<T: Send, F: Fn(usize) -> T + Sync + Send>(&self, f: F) -> Vec<T> {
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            (0..self.codes.len()).into_par_iter().map(f).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        (0..self.codes.len()).map(f).collect()
+    }
+
+    // animation cycling through the codes at fps frames per second
+    #[cfg(feature = "render-image")]
+    pub fn write_animation<W: std::io::Write>(&self, writer: W, format: AnimationFormat, fps: u32, looping: bool) -> Result<(), MqrError> {
+        self.write_animation_with(writer, format, fps, looping, &SaveOptions::default())
+    }
+
+    #[cfg(feature = "render-image")]
+    pub fn write_animation_with<W: std::io::Write>(&self, writer: W, format: AnimationFormat, fps: u32, looping: bool, options: &SaveOptions) -> Result<(), MqrError> {
+        if fps == 0 {
+            return Err(MqrError::InvalidOptions("fps must be at least 1"))
+        }
+        animation::write(writer, format, self.render_all(options)?, fps, looping)
+    }
+
+    // the animation format follows the file extension, see AnimationFormat::from_path
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    pub fn save_animation<P: AsRef<Path>>(&self, path: P, fps: u32, looping: bool) -> Result<(), MqrError> {
+        let format = AnimationFormat::from_path(&path).ok_or(MqrError::InvalidOptions("unknown animation file extension"))?;
+        self.save_animation_as(path, format, fps, looping)
+    }
+
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    pub fn save_animation_as<P: AsRef<Path>>(&self, path: P, format: AnimationFormat, fps: u32, looping: bool) -> Result<(), MqrError> {
+        let mut file = std::io::BufWriter::new(File::create(path)?);
+        self.write_animation(&mut file, format, fps, looping)?;
+        Ok(std::io::Write::flush(&mut file)?)
+    }
+
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    pub fn save_gif<P: AsRef<Path>>(&self, path: P, fps: u32, looping: bool) -> Result<(), MqrError> {
+        self.save_animation_as(path, AnimationFormat::Gif, fps, looping)
+    }
+
+    // the image format follows the file extension, usually png
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    pub fn save_sheet<P: AsRef<Path>>(&self, path: P, cols: usize, spacing: u32) -> Result<(), MqrError> {
+        Ok(self.to_sheet(cols, spacing)?.save(path)?)
+    }
+
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    pub fn save_sheet_with<P: AsRef<Path>>(&self, path: P, cols: usize, spacing: u32, options: &SaveOptions) -> Result<(), MqrError> {
+        Ok(self.to_sheet_with(cols, spacing, options)?.save(path)?)
+    }
+}
+
+impl std::ops::Index<usize> for MultiQrCode {
+    type Output = QrCode;
+
+    fn index(&self, index: usize) -> &QrCode {
+        &self.codes[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a MultiQrCode {
+    type Item = &'a QrCode;
+    type IntoIter = std::slice::Iter<'a, QrCode>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.codes.iter()
+    }
+}
+
+// every code as text under a "Code i of N" caption, codes separated by a blank line
+#[cfg(feature = "render-text")]
+impl fmt::Display for MultiQrCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, code) in self.codes.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n\n")?;
+            }
+            write!(f, "Code {} of {}\n{}", i + 1, self.codes.len(), text::render(code, TextStyle::Full))?;
+        }
+        Ok(())
+    }
+}
+
+// the grayscale image save has always written
+#[cfg(feature = "render-image")]
+fn code_image(code: &QrCode) -> GrayImage {
+    code.render::<Luma<u8>>().build()
+}
+
+// data bytes per code as MultiQrCode::new splits it, 0 when the version can't hold any
+pub fn chunk_capacity(version: Version, ec: EcLevel) -> usize {
+    if check_byte_mode(version).is_err() {
+        return 0
+    }
+    chunk_size(version, ec, HeaderFormat::Legacy.size() + byte_mode_slack(version)).unwrap_or(0)
+}
+
+// codes MultiQrCode::new would produce for len bytes without encoding anything,
+// usize::MAX when the version can't hold any data
+pub fn estimate_chunks(len: usize, version: Version, ec: EcLevel) -> usize {
+    match chunk_capacity(version, ec) {
+        0 => usize::MAX,
+        capacity => len.div_ceil(capacity)
+    }
+}
+
+// bytes the byte segment holding a chunk takes on top of the chunk: the mode indicator and the
+// length field, rounded up to whole bytes. chunks are always stored as a single byte segment, the
+// mixed segments qrcode would pick can take more, e.g. for text with digits in large versions
+pub fn byte_mode_slack(version: Version) -> usize {
+    (version.mode_bits_count() + Mode::Byte.length_bits_count(version)).div_ceil(8)
+}
+
+// bytes of data left in each code once the header and slack are taken out
+fn chunk_size(version: Version, ec: EcLevel, overhead: usize) -> Option<usize> {
+    Capacity::for_(version, ec).checked_sub(overhead).filter(|&s| s > 0)
+}
+
+// with_headers from mqrcode-core, with the error the encoders return
+fn with_headers(data: &[u8], chunks: &[&[u8]], total: usize, format: HeaderFormat, flags: u8) -> Result<Vec<Vec<u8>>, QrError> {
+    mqrcode_core::chunk::with_headers(data, chunks, total, format, flags).ok_or(QrError::DataTooLong)
+}
+
+// a chunk's data and how its code was encoded, see MultiQrCode::append_with
+type Chunk = (Vec<u8>, Version, EcLevel, Segments);
+
+// the payloads of MultiQrCode's own codes and how they go into them: chunks sized by their
+// encoded length with optimized segments or kanji, by what encoder holds otherwise
+fn qr_payloads(data: &[u8], encoder: &QrEncoder, options: &EncodeOptions, flags: u8, manifest: Option<Manifest>) -> Result<(Vec<Vec<u8>>, Segments), QrError> {
+    check_byte_mode(encoder.version)?;
+    let eci = encoder.utf8_eci;
+    if !options.optimize_segments && !charset::is_shift_jis(flags) {
+        return Ok((encode_payloads(data, encoder, options, flags, manifest)?, Segments::Bytes { eci }))
+    }
+    // everything in front of the data is kept as a byte segment
+    let prefix = chunk_prefix(options.header, flags);
+    let chunks = segment::split(data, encoder.version, encoder.ec, prefix, eci)?;
+    Ok((head_chunks(data, &chunks, options, flags, manifest)?, Segments::Optimized { prefix, eci }))
+}
+
+// the payload of every symbol for data already compressed and encrypted as flags say: evenly
+// sized chunks of what encoder holds after the header, see head_chunks for the rest
+pub(crate) fn encode_payloads<E: SymbolEncoder + ?Sized>(data: &[u8], encoder: &E, options: &EncodeOptions, flags: u8, manifest: Option<Manifest>) -> Result<Vec<Vec<u8>>, QrError> {
+    // parity payloads carry the stream length on top of a full chunk
+    let parity_size = if options.parity > 0 { parity::PARITY_PREFIX_SIZE } else { 0 };
+    let capacity = encoder.capacity().checked_sub(chunk_prefix(options.header, flags) + parity_size)
+        .filter(|&size| size > 0).ok_or(QrError::DataTooLong)?;
+    let size = match options.chunk_size {
+        Some(size) if size == 0 || size > capacity => return Err(QrError::DataTooLong),
+        Some(size) => size,
+        None => capacity
+    };
+    let striped;
+    let chunks = if options.striped {
+        striped = stripe::split(data, size);
+        striped.iter().map(Vec::as_slice).collect()
+    } else {
+        data.chunks(size).collect::<Vec<_>>()
+    };
+    head_chunks(data, &chunks, options, flags, manifest)
+}
+
+// bytes in front of every chunk's data, the chunk crc included
+fn chunk_prefix(format: HeaderFormat, flags: u8) -> usize {
+    format.size() + if flags & header::FLAG_CHUNK_CRC != 0 { header::CHUNK_CRC_SIZE } else { 0 }
+}
+
+// the chunks with their headers followed by options.parity parity codes, or behind a manifest
+// code that gets its total and crc32 filled in
+fn head_chunks(data: &[u8], chunks: &[&[u8]], options: &EncodeOptions, flags: u8, manifest: Option<Manifest>) -> Result<Vec<Vec<u8>>, QrError> {
+    // only parity is limited to MAX_SHARDS codes and a u32 length, plain sets go up to max_chunks
+    if options.parity > 0 && (chunks.len() + options.parity > parity::MAX_SHARDS || u32::try_from(data.len()).is_err()) {
+        return Err(QrError::DataTooLong)
+    }
+    let shards: Vec<Vec<u8>> = parity::encode(chunks, options.parity).into_iter()
+        .map(|shard| [&(data.len() as u32).to_be_bytes()[..], &shard].concat())
+        .collect();
+    let parts: Vec<&[u8]> = chunks.iter().copied().chain(shards.iter().map(Vec::as_slice)).collect();
+    match manifest {
+        Some(manifest) => manifest::with_manifest(&Manifest { total: chunks.len(), crc32: crc32fast::hash(data), ..manifest }, chunks),
+        None => with_headers(data, &parts, chunks.len(), options.header, flags)
+    }
+}
+
+// the highest ec level from ec up that still fits the payload in version, like QrCode::new
+// does for versions. a short last chunk gets the most robust code for free
+fn boosted_code<F: Fn(&[u8], EcLevel) -> Result<QrCode, QrError>>(payload: &[u8], ec: EcLevel, code: F) -> Result<QrCode, QrError> {
+    [EcLevel::H, EcLevel::Q, EcLevel::M].into_iter()
+        .filter(|&level| level > ec)
+        .find_map(|level| code(payload, level).ok())
+        .map_or_else(|| code(payload, ec), Ok)
+}
+
+// chunks are stored in byte mode, which M1 and M2 don't have
+fn check_byte_mode(version: Version) -> Result<(), QrError> {
+    match version {
+        Version::Micro(1 | 2) => Err(QrError::UnsupportedCharacterSet),
+        _ => Ok(())
+    }
+}
+
+// codes needed for len bytes with the legacy header and default slack
+fn chunk_count(len: usize, version: Version, ec: EcLevel) -> Option<usize> {
+    chunk_size(version, ec, 1 + byte_mode_slack(version)).map(|size| len.div_ceil(size))
+}
+
+// the normal versions only, Micro QR capacities are in Capacity::for_
+#[deprecated(note = "use Capacity::for_, which checks the version")]
+pub const QR_DATA_LENGTHS: [[usize; 4]; 40] = {
+    let mut lengths = [[0; 4]; 40];
+    let mut i = 0;
+    while i < 40 {
+        lengths[i] = capacity::DATA_LENGTHS[i];
+        i += 1;
+    }
+    lengths
+};
+
+#[deprecated(note = "use byte_mode_slack, which is computed from the version")]
+pub const QR_VERSION_SLACK: [usize; 40] = [
     // 2 slack for version 1-9
     2, 2, 2, 2, 2, 2, 2, 2, 2,
     // 3 slack for version 10-40 
     3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3
-];
\ No newline at end of file
+];
+
+#[cfg(test)]
+mod tests {
+    use more_asserts::{assert_le, assert_lt, assert_gt, assert_ge};
+
+    use super::*;
+
+    const LIPSUM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Integer tristique turpis eros, vel hendrerit purus laoreet vitae. Praesent consectetur non mauris quis fermentum. Vivamus vulputate mi sed felis mollis dapibus. Interdum et malesuada fames ac ante ipsum primis in faucibus. Quisque vel tortor vitae ex blandit sodales in id diam. Morbi urna quam, lobortis id tristique a, imperdiet ac neque. Vivamus metus enim, posuere sed imperdiet in, tempor in purus. Nunc molestie, eros at dignissim commodo, ex eros ultrices augue, nec tristique turpis neque at ipsum. Phasellus sed malesuada justo. Sed lacus tellus, sodales quis pellentesque vitae, sagittis id augue. Quisque sit amet suscipit enim, in luctus lorem. Curabitur condimentum augue ac ornare mattis. Donec tempor pretium maximus.
+
+    Cras tempus lacinia sagittis. Vestibulum suscipit nisi sit amet ipsum hendrerit, sit amet iaculis mauris auctor. Vestibulum dictum felis nec risus egestas vulputate. Donec sit amet mauris a mauris venenatis suscipit. Vivamus elementum faucibus ornare. Interdum et malesuada fames ac ante ipsum primis in faucibus. Suspendisse ornare tellus id nisl condimentum condimentum. Suspendisse placerat non nisi sit amet tempor. Curabitur ante lacus, tempor in vulputate eget, vestibulum ut arcu. Sed quam massa, mattis a efficitur cursus, vehicula a purus. Aenean leo justo, malesuada et massa condimentum, pellentesque fermentum lectus.
+    
+    Aliquam rhoncus augue vitae metus congue hendrerit. Nulla tempus turpis urna, sed feugiat nisi vestibulum vitae. Suspendisse at bibendum urna. Phasellus malesuada urna eu imperdiet semper. Morbi rhoncus felis ac ex vestibulum, quis semper eros suscipit. Donec in dignissim eros. In ultrices fermentum purus vitae tempus. Morbi vel ipsum at est laoreet commodo. Morbi iaculis, enim sed iaculis vestibulum, diam arcu aliquet diam, nec eleifend nisl dolor in nunc. Sed dictum elit eget hendrerit feugiat. Donec feugiat lectus at est dignissim, ut ullamcorper dui euismod. Nunc commodo, quam ac efficitur rhoncus, dolor arcu volutpat felis, sed auctor leo neque eget enim. Donec lobortis at orci eu consectetur.
+    
+    Cras convallis neque vitae nisl ullamcorper, a dictum eros ultricies. Etiam purus arcu, faucibus egestas mollis ac, pulvinar ut dolor. Suspendisse potenti. Vivamus id elementum est. Donec vel ornare nulla. Sed laoreet maximus sodales. Nam ullamcorper molestie ipsum quis ultrices. Phasellus aliquam nisi vel iaculis convallis. Quisque volutpat libero nec ipsum rutrum, vel fermentum augue eleifend. Proin vitae libero eu lectus convallis sodales. Etiam posuere nulla sit amet elit viverra placerat. Praesent quis risus in eros fermentum tincidunt. Sed ipsum elit, tempor et nisi sed, bibendum efficitur est. Nullam ultricies arcu mi, at maximus dui mollis nec. Fusce mauris nisl, porttitor ut lacus at, malesuada varius arcu. Nulla facilisi.
+    
+    Phasellus consequat dictum eros, sit amet lobortis orci volutpat in. Orci varius natoque penatibus et magnis dis parturient montes, nascetur ridiculus mus. Nullam auctor bibendum venenatis. Cras est libero, feugiat sit amet massa id, semper lacinia velit. Phasellus justo augue, consequat ut dictum ut, gravida nec neque. Duis iaculis nisi vitae augue pellentesque ornare. Curabitur ullamcorper a urna ut lobortis. Praesent fermentum mattis facilisis. In hac habitasse platea dictumst. Vivamus nec lorem ullamcorper, fringilla tellus sit amet, facilisis nibh. Suspendisse id metus elementum, dignissim risus ac, blandit risus. Cras non ex at augue varius rhoncus. Pellentesque at ex nibh. Cras porttitor lobortis imperdiet. Vestibulum quis euismod turpis.";
+
+    #[test]
+    #[ignore]
+    fn confirm_minimum_slack() {
+        for version in 1_usize..41_usize {
+            let mut slack: usize = 0;
+            loop {
+                match MultiQrCode::with_slack(LIPSUM, Version::Normal(version as i16), DEFAULT_EC_LEVEL, slack) {
+                    Ok(_) => {
+                        println!("Minimum slack for version {} is {}", version, slack);
+                        assert_le!(slack, byte_mode_slack(Version::Normal(version as i16)), "Invalid slack for version {}: {} > {}", version, slack, byte_mode_slack(Version::Normal(version as i16)));
+                        break;
+                    }
+                    _ => {
+                        slack += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn computed_slack() {
+        assert_eq!(byte_mode_slack(Version::Normal(9)), 2);
+        assert_eq!(byte_mode_slack(Version::Normal(40)), 3);
+        assert_eq!(byte_mode_slack(Version::Micro(3)), 1);
+        // the deprecated tables keep their 40 normal versions
+        #[allow(deprecated)]
+        let (lengths, slack) = (QR_DATA_LENGTHS, QR_VERSION_SLACK);
+        assert_eq!((lengths.len(), slack.len()), (40, 40));
+        assert_eq!(lengths[39][EcLevel::L as usize], Capacity::for_(Version::Normal(40), EcLevel::L));
+        // qrcode's own mixed segments for this text don't fit version 40 with 3 bytes of slack
+        let text = "Invoice 12345 ABCDEF paid in full. ".repeat(200);
+        let qr = MultiQrCode::new(&text, Version::Normal(40), EcLevel::L).unwrap();
+        assert_eq!(MultiQrCode::reassemble(qr.payloads()).unwrap(), text.as_bytes());
+        for version in [Version::Normal(1), Version::Normal(27), Version::Micro(3), Version::Micro(4)] {
+            for ec in [EcLevel::L, EcLevel::M] {
+                let qr = MultiQrCode::new(vec![0xff; 2 * chunk_capacity(version, ec) + 1], version, ec).unwrap();
+                // full chunks use up the symbol
+                assert_eq!(qr.payloads()[0].len() + byte_mode_slack(version), Capacity::for_(version, ec));
+            }
+        }
+    }
+
+    #[test]
+    fn bit_matrices() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::L).unwrap();
+        let matrices = qr.to_matrices();
+        assert_eq!(matrices.len(), qr.len());
+        let (code, matrix) = (&qr.codes[0], &matrices[0]);
+        assert_eq!(matrix.width(), 37);
+        assert_eq!(matrix.to_colors(), code.to_colors());
+        let rows = matrix.to_vec();
+        assert_eq!(rows.len(), 37);
+        assert!(rows.iter().all(|row| row.len() == 37));
+        // the finder pattern's corner is dark, the separator next to it light
+        assert!(rows[0][0] && !rows[7][7]);
+        assert!(!matrix.get(37, 0));
+        assert_eq!(matrix.as_bytes().len(), (37 * 37usize).div_ceil(8));
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn indexed_exports() {
+        let qr = MultiQrCode::with_layout(LIPSUM, &[(Version::Normal(2), EcLevel::H), (Version::Normal(10), EcLevel::L)], HeaderFormat::Legacy).unwrap();
+        let indexed = qr.indexed(qr.to_base64());
+        assert_eq!(indexed.len(), qr.len());
+        assert!(indexed.iter().enumerate().all(|(i, entry)| entry.index == i && entry.total == qr.len()));
+        assert_eq!((indexed[0].version, indexed[0].ec), (Version::Normal(2), EcLevel::H));
+        assert_eq!((indexed[1].version, indexed[1].ec), (Version::Normal(10), EcLevel::L));
+        assert!(matches!(&indexed[1].data, QrData::Base64 { width: 57, .. }));
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&indexed).unwrap();
+            assert!(json.starts_with(r#"[{"index":0,"total":"#));
+            assert!(json.contains(r#""version":{"Normal":2},"ec":"H","data":{"Base64":"#));
+            let reloaded: Vec<IndexedQrData> = serde_json::from_str(&json).unwrap();
+            for (a, b) in reloaded.iter().zip(&indexed) {
+                assert_eq!((a.index, a.total, a.version, a.ec), (b.index, b.total, b.version, b.ec));
+                assert!(matches!((&a.data, &b.data), (QrData::Base64 { width: x, data: p }, QrData::Base64 { width: y, data: q }) if x == y && p == q));
+            }
+        }
+    }
+
+    #[cfg(all(feature = "render-text", feature = "serde"))]
+    #[test]
+    #[ignore]
+    fn compare_string_vs_base64() {
+        let qr = MultiQrCode::default("Hello world!").unwrap();
+        let string = serde_json::to_string(&qr.to_strings()).unwrap();
+        let base64 = serde_json::to_string(&qr.to_base64()).unwrap();
+        println!("String:\n{}\nBase64:\n{}",string,base64);
+        assert_le!(base64.len(), string.len());
+    }
+
+    // the original module by module packing, kept to check the output format never changes
+    #[cfg(feature = "render-text")]
+    fn base64_per_module(code: &QrCode) -> (usize, String) {
+        let width = code.width();
+        let mut bytes = vec![0u8; ((width * width) as f32 / 8.0).ceil() as usize];
+        for (i, color) in code.to_colors().iter().enumerate() {
+            if let Color::Dark = color {
+                bytes[i / 8] |= 1u8 << (7 - i % 8);
+            }
+        }
+        (width, Base64Variant::Standard.encode(bytes))
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn base64_packing() {
+        for version in [1, 2, 7, 40] {
+            let qr = MultiQrCode::new(LIPSUM, Version::Normal(version), EcLevel::L).unwrap();
+            for code in qr.codes.iter() {
+                match QrData::base64_from(code) {
+                    QrData::Base64 { width, data } => assert_eq!((width, data), base64_per_module(code)),
+                    _ => panic!("expected base64 data")
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn base64_roundtrip() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::M).unwrap();
+        for (code, data) in qr.codes.iter().zip(qr.to_base64()) {
+            assert_eq!(data.to_colors().unwrap(), code.to_colors());
+            assert_eq!(data.to_qr_image().unwrap(), code_image(code));
+        }
+        assert!(QrData::String("#".to_string()).to_colors().is_err());
+        assert!(QrData::Base64 { width: 21, data: Base64Variant::Standard.encode([0u8; 4]) }.to_colors().is_err());
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn base45_roundtrip() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::M).unwrap();
+        for (code, data) in qr.codes.iter().zip(qr.to_base45()) {
+            match &data {
+                QrData::Base45 { width, data } => {
+                    assert_eq!(*width, code.width());
+                    assert!(data.bytes().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase() || b" $%*+-./:".contains(&c)));
+                }
+                _ => panic!("expected base45 data")
+            }
+            assert_eq!(data.to_colors().unwrap(), code.to_colors());
+        }
+        assert!(QrData::Base45 { width: 21, data: "not base45".to_string() }.to_colors().is_err());
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn hex_and_raw() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::M).unwrap();
+        for ((code, hex), raw) in qr.codes.iter().zip(qr.to_hex()).zip(qr.to_raw()) {
+            let (QrData::Hex { data: text, .. }, QrData::Raw { data: bytes, .. }) = (&hex, &raw) else {
+                panic!("expected hex and raw data")
+            };
+            assert_eq!(text.len(), bytes.len() * 2);
+            assert_eq!(&decode_hex(text).unwrap(), bytes);
+            assert_eq!(hex.to_colors().unwrap(), code.to_colors());
+            assert_eq!(raw.to_colors().unwrap(), code.to_colors());
+            let upper = QrData::Hex { width: code.width(), data: text.to_uppercase() };
+            assert_eq!(upper.to_colors().unwrap(), code.to_colors());
+        }
+        assert!(QrData::Hex { width: 21, data: "0g".repeat(56) }.to_colors().is_err());
+        assert!(QrData::Hex { width: 21, data: "0".repeat(111) }.to_colors().is_err());
+        assert!(QrData::Raw { width: 21, data: vec![0; 4] }.to_colors().is_err());
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn base64_variants() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::M).unwrap();
+        let data = |variant| match qr.to_base64_with(variant).remove(0) {
+            QrData::Base64 { data, .. } => data,
+            _ => unreachable!()
+        };
+        let standard = data(Base64Variant::Standard);
+        let url_safe = data(Base64Variant::UrlSafeNoPad);
+        assert!(standard.contains(['+', '/']) && standard.ends_with('='));
+        assert!(!url_safe.contains(['+', '/', '=']) && url_safe.contains(['-', '_']));
+        assert_eq!(url_safe, standard.trim_end_matches('=').replace('+', "-").replace('/', "_"));
+        assert_eq!(data(Base64Variant::StandardNoPad), standard.trim_end_matches('='));
+        assert_eq!(data(Base64Variant::UrlSafe), standard.replace('+', "-").replace('/', "_"));
+
+        for variant in [Base64Variant::Standard, Base64Variant::StandardNoPad, Base64Variant::UrlSafe, Base64Variant::UrlSafeNoPad] {
+            assert_eq!(qr.to_base64_with(variant)[0].to_colors().unwrap(), qr.codes[0].to_colors());
+        }
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    #[ignore]
+    fn bench_base64_packing() {
+        use std::time::Instant;
+        let code = QrCode::with_version(&LIPSUM.as_bytes()[..2900], Version::Normal(40), EcLevel::L).unwrap();
+        let rounds = 200;
+
+        let start = Instant::now();
+        for _ in 0..rounds {
+            std::hint::black_box(base64_per_module(&code));
+        }
+        let per_module = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..rounds {
+            std::hint::black_box(QrData::base64_from(&code));
+        }
+        let packed = start.elapsed();
+
+        println!("Version 40 x{}: per module {:?}, packed {:?}", rounds, per_module, packed);
+        assert_lt!(packed, per_module);
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn print_hello() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(10), EcLevel::L).unwrap();
+        println!("{}", qr);
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn display_captions() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let strings: Vec<String> = qr.to_strings().into_iter().map(|data| match data {
+            QrData::String(s) => s,
+            _ => unreachable!()
+        }).collect();
+        let shown = qr.to_string();
+        let blocks: Vec<&str> = shown.split("\n\n").collect();
+        assert_eq!(blocks.len(), qr.len());
+        for (i, (block, string)) in blocks.iter().zip(&strings).enumerate() {
+            assert_eq!(*block, format!("Code {} of {}\n{}", i + 1, qr.len(), string));
+        }
+        assert_eq!(qr.to_concatenated_string(), strings.concat());
+    }
+
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    #[test]
+    fn save_hello() {
+        let qr = MultiQrCode::default("Hello world!").unwrap();
+        let path = std::env::temp_dir().join("mqrcode-test-hw.png");
+        qr.save(path.to_str().unwrap()).unwrap();
+        // opaque black and white codes are written as grayscale
+        assert_eq!(image::open(path.with_extension("0.png")).unwrap().color(), image::ColorType::L8);
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn print_lipsum() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        println!("{}", qr);
+    }
+
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    #[test]
+    fn save_lipsum() {
+        let qr = MultiQrCode::default(LIPSUM).unwrap();
+        qr.save(std::env::temp_dir().join("mqrcode-test-lipsum.png").to_str().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn reassemble_lipsum() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let mut payloads = qr.payloads().to_vec();
+        payloads.reverse();
+        assert_eq!(MultiQrCode::reassemble(&payloads).unwrap(), LIPSUM.as_bytes());
+    }
+
+    #[test]
+    fn reassemble_missing_chunk() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let payloads: Vec<&Vec<u8>> = qr.payloads().iter().filter(|p| p[0] != 1).collect();
+        assert_eq!(MultiQrCode::reassemble(payloads), Err(DecodeError::MissingChunk(1)));
+    }
+
+    #[test]
+    fn reassemble_extended_header() {
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let qr = MultiQrCode::with_header(LIPSUM, Version::Normal(10), EcLevel::L, format).unwrap();
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), format).unwrap(), LIPSUM.as_bytes());
+
+        // unlike the legacy header, a missing last chunk is noticed
+        let last = qr.payloads().len() - 1;
+        assert_eq!(MultiQrCode::reassemble_with(&qr.payloads()[..last], format), Err(DecodeError::MissingChunk(last)));
+
+        let mut corrupted = qr.payloads().to_vec();
+        corrupted[0][format.size()] ^= 1;
+        assert_eq!(MultiQrCode::reassemble_with(corrupted, format), Err(DecodeError::ChecksumMismatch));
+    }
+
+    #[test]
+    fn versioned_header() {
+        let format = HeaderFormat::Versioned(WireFormat::LATEST);
+        let qr = MultiQrCode::builder().version(Version::Normal(10)).header(format).chunk_crc(true).build(LIPSUM).unwrap();
+        assert!(qr.payloads().iter().all(|payload| payload[0] == 0xb1));
+        assert_eq!(WireFormat::of(&qr.payloads()[0]), Ok(WireFormat::V1));
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), format).unwrap(), LIPSUM.as_bytes());
+
+        // a wire format from a newer build is reported rather than misread
+        let mut newer = qr.payloads().to_vec();
+        newer[0][0] = 0xb2;
+        assert_eq!(MultiQrCode::reassemble_with(&newer, format), Err(DecodeError::Unsupported));
+
+        // codes from before the version marker are read with the header they were made with,
+        // reading them as versioned is refused rather than guessed at
+        let plain = MultiQrCode::with_header(LIPSUM, Version::Normal(10), EcLevel::L, HeaderFormat::Extended(IndexWidth::U16)).unwrap();
+        assert_eq!(MultiQrCode::reassemble_with(plain.payloads(), HeaderFormat::Extended(IndexWidth::U16)).unwrap(), LIPSUM.as_bytes());
+        assert_eq!(MultiQrCode::reassemble_with(plain.payloads(), format), Err(DecodeError::Malformed));
+        let legacy = MultiQrCode::with_header(LIPSUM, Version::Normal(5), EcLevel::L, HeaderFormat::Legacy).unwrap();
+        assert_gt!(legacy.len(), 1);
+        assert_eq!(MultiQrCode::reassemble_with(legacy.payloads(), HeaderFormat::Legacy).unwrap(), LIPSUM.as_bytes());
+        assert_eq!(MultiQrCode::reassemble(legacy.payloads()).unwrap(), LIPSUM.as_bytes());
+        assert_eq!(MultiQrCode::reassemble_with(legacy.payloads(), format), Err(DecodeError::Malformed));
+    }
+
+    #[test]
+    fn auto_version() {
+        let qr = MultiQrCode::auto("Hello world!", EcLevel::L).unwrap();
+        assert_eq!(qr.codes.len(), 1);
+        assert_eq!(qr.codes[0].version(), Version::Normal(1));
+
+        let qr = MultiQrCode::auto(LIPSUM, EcLevel::L).unwrap();
+        assert_eq!(qr.codes.len(), MultiQrCode::default(LIPSUM).unwrap().codes.len());
+        assert_lt!(qr.codes[0].version().width(), DEFAULT_QR_VERSION.width());
+    }
+
+    #[test]
+    fn max_codes() {
+        let qr = MultiQrCode::with_max_codes(LIPSUM, 6, EcLevel::M).unwrap();
+        assert_le!(qr.codes.len(), 6);
+        if let Version::Normal(v) = qr.codes[0].version() {
+            assert_gt!(MultiQrCode::new(LIPSUM, Version::Normal(v - 1), EcLevel::M).unwrap().codes.len(), 6);
+        }
+
+        assert!(matches!(MultiQrCode::with_max_codes(LIPSUM, 1, EcLevel::H), Err(QrError::DataTooLong)));
+    }
+
+    #[test]
+    fn encoder_matches_new() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let mut encoder = MultiQrEncoder::new(LIPSUM.as_bytes(), Version::Normal(10), EcLevel::L).unwrap();
+        for payload in qr.payloads() {
+            assert_eq!(&encoder.next_payload().unwrap().unwrap(), payload);
+        }
+        assert!(encoder.next_payload().unwrap().is_none());
+
+        let codes: Vec<QrCode> = MultiQrEncoder::new(LIPSUM.as_bytes(), Version::Normal(10), EcLevel::L).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(codes.len(), qr.codes.len());
+    }
+
+    #[test]
+    fn writer_matches_new() {
+        let mut codes = Vec::new();
+        let mut writer = MultiQrWriter::new(Version::Normal(10), EcLevel::L, |code| { codes.push(code); Ok(()) }).unwrap();
+        for line in LIPSUM.as_bytes().chunks(100) {
+            std::io::Write::write_all(&mut writer, line).unwrap();
+        }
+        assert!(writer.finish().is_ok());
+
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        assert_eq!(codes.iter().map(|c| c.to_colors()).collect::<Vec<_>>(), qr.codes.iter().map(|c| c.to_colors()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reader_streams_in_order() {
+        use std::io::{Read, ErrorKind};
+
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let qr = MultiQrCode::with_header(LIPSUM, Version::Normal(10), EcLevel::L, format).unwrap();
+        let mut reader = MultiQrReader::with_header(format);
+        let mut res = Vec::new();
+
+        // nothing can be read until the first chunk shows up
+        for payload in qr.payloads()[1..].iter().rev() {
+            reader.push(payload).unwrap();
+        }
+        assert_eq!(reader.read_to_end(&mut res).unwrap_err().kind(), ErrorKind::WouldBlock);
+        reader.push(&qr.payloads()[0]).unwrap();
+        reader.read_to_end(&mut res).unwrap();
+        assert_eq!(res, LIPSUM.as_bytes());
+
+        // the legacy header has no total, so the end is only known after close
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let mut reader = MultiQrReader::new();
+        let mut res = Vec::new();
+        for payload in qr.payloads() {
+            reader.push(payload).unwrap();
+        }
+        assert_eq!(reader.read_to_end(&mut res).unwrap_err().kind(), ErrorKind::WouldBlock);
+        reader.close();
+        reader.read_to_end(&mut res).unwrap();
+        assert_eq!(res, LIPSUM.as_bytes());
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn from_file_lipsum() {
+        let path = std::env::temp_dir().join("mqrcode-from-file.txt");
+        std::fs::write(&path, LIPSUM).unwrap();
+        let qr = MultiQrCode::from_file(&path, Version::Normal(10), EcLevel::L).unwrap();
+        assert_eq!(qr.payloads(), MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap().payloads());
+
+        assert!(matches!(MultiQrCode::from_file(path.with_extension("missing"), Version::Normal(10), EcLevel::L), Err(MqrError::Io(_))));
+        assert!(matches!(MultiQrCode::from_reader(&[0u8; 6 * 257][..], Version::Normal(1), EcLevel::H), Err(MqrError::Qr(QrError::DataTooLong))));
+    }
+
+    #[test]
+    fn ur_lipsum() {
+        let qr = MultiQrCode::ur(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let parts: Vec<&str> = qr.payloads().iter().map(|p| std::str::from_utf8(p).unwrap()).collect();
+        assert_eq!(ur::decode(parts).unwrap(), LIPSUM.as_bytes());
+    }
+
+    #[test]
+    fn compression_needs_flags() {
+        let options = EncodeOptions { header: HeaderFormat::Extended(IndexWidth::U8), ..Default::default() };
+        let qr = MultiQrCode::with_options(LIPSUM, Version::Normal(10), EcLevel::L, &options).unwrap();
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), options.header).unwrap(), LIPSUM.as_bytes());
+
+        // pretend the data was compressed with zstd, which this build may not have
+        let mut payloads = qr.payloads().to_vec();
+        for payload in payloads.iter_mut() {
+            payload[2] = 0b10;
+        }
+        #[cfg(feature = "zstd")]
+        let expected = DecodeError::Decompression;
+        #[cfg(not(feature = "zstd"))]
+        let expected = DecodeError::Unsupported;
+        assert_eq!(MultiQrCode::reassemble_with(&payloads, options.header), Err(expected));
+
+        // encrypted data can't be reassembled without a key
+        for payload in payloads.iter_mut() {
+            payload[2] = 0b100;
+        }
+        assert_eq!(MultiQrCode::reassemble_with(&payloads, options.header), Err(DecodeError::Encrypted));
+    }
+
+    #[cfg(feature = "aes-gcm")]
+    #[test]
+    fn aes_gcm_lipsum() {
+        let header = HeaderFormat::Extended(IndexWidth::U8);
+        let options = EncodeOptions { header, encryption: Encryption::AesGcm { key: [7; 32] }, ..Default::default() };
+        let qr = MultiQrCode::with_options(LIPSUM, Version::Normal(10), EcLevel::L, &options).unwrap();
+        assert_eq!(MultiQrCode::reassemble_decrypt(qr.payloads(), header, &[7; 32]).unwrap(), LIPSUM.as_bytes());
+        assert_eq!(MultiQrCode::reassemble_decrypt(qr.payloads(), header, &[8; 32]), Err(DecodeError::Decryption));
+    }
+
+    #[cfg(feature = "render-text")]
+    #[cfg(feature = "passphrase")]
+    #[test]
+    fn passphrase_lipsum() {
+        let header = HeaderFormat::Extended(IndexWidth::U8);
+        // cheap parameters to keep the test fast
+        let kdf = crypto::KdfParams { m_cost: 64, t_cost: 1, p_cost: 1 };
+        let encryption = Encryption::Passphrase { passphrase: "correct horse".to_string(), kdf };
+        let options = EncodeOptions { header, encryption, ..Default::default() };
+        let qr = MultiQrCode::with_options(LIPSUM, Version::Normal(10), EcLevel::L, &options).unwrap();
+        assert_eq!(MultiQrCode::reassemble_passphrase(qr.payloads(), header, "correct horse").unwrap(), LIPSUM.as_bytes());
+        assert_eq!(MultiQrCode::reassemble_passphrase(qr.payloads(), header, "battery staple"), Err(DecodeError::Decryption));
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), header), Err(DecodeError::Encrypted));
+
+        // a forged memory cost of 4 TiB is refused before hashing
+        let mut forged = vec![0xff, 0xff, 0xff, 0xff, 0, 0, 0, 1, 0, 0, 0, 1];
+        forged.resize(crypto::KDF_HEADER_SIZE + crypto::NONCE_SIZE + 32, 0);
+        assert_eq!(crypto::decrypt_passphrase(&forged, "correct horse"), Err(DecodeError::Malformed));
+        let kdf = crypto::KdfParams { t_cost: crypto::MAX_KDF_PARAMS.t_cost + 1, ..kdf };
+        let options = EncodeOptions { encryption: Encryption::Passphrase { passphrase: "correct horse".to_string(), kdf }, ..options };
+        assert!(matches!(MultiQrCode::with_options(LIPSUM, Version::Normal(10), EcLevel::L, &options), Err(MqrError::Encryption)));
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn deflate_lipsum() {
+        let header = HeaderFormat::Extended(IndexWidth::U8);
+        let options = EncodeOptions { header, compression: Compression::Deflate, ..Default::default() };
+        let qr = MultiQrCode::with_options(LIPSUM, Version::Normal(10), EcLevel::L, &options).unwrap();
+        assert_lt!(qr.codes.len(), MultiQrCode::with_header(LIPSUM, Version::Normal(10), EcLevel::L, header).unwrap().codes.len());
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), header).unwrap(), LIPSUM.as_bytes());
+
+        let options = EncodeOptions { header: HeaderFormat::Legacy, compression: Compression::Deflate, ..Default::default() };
+        assert!(matches!(MultiQrCode::with_options(LIPSUM, Version::Normal(10), EcLevel::L, &options), Err(MqrError::InvalidOptions(_))));
+
+        // a bomb of zeros is stopped at the limit instead of inflated in full
+        use std::io::Write;
+        let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(&vec![0; compression::MAX_DECOMPRESSED_SIZE + 1]).unwrap();
+        let bomb = encoder.finish().unwrap();
+        assert_eq!(compression::decompress(Compression::Deflate.flag(), bomb), Err(DecodeError::Malformed));
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn half_block_lines() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(10), EcLevel::L).unwrap();
+        let full = match &qr.to_strings()[0] { QrData::String(s) => s.clone(), _ => unreachable!() };
+        let compact = match &qr.to_strings_styled(TextStyle::HalfBlock)[0] { QrData::String(s) => s.clone(), _ => unreachable!() };
+        assert_eq!(compact.lines().count(), full.lines().count().div_ceil(2));
+        assert_eq!(compact.lines().next().unwrap().chars().count(), full.lines().next().unwrap().chars().count());
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn text_render_options() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(1), EcLevel::L).unwrap();
+        let string = |data: &QrData| match data { QrData::String(s) => s.clone(), _ => unreachable!() };
+        assert_eq!(string(&qr.to_strings_with(&TextRenderOptions::default())[0]), string(&qr.to_strings()[0]));
+
+        let options = TextRenderOptions { dark: "██".to_string(), light: "  ".to_string(), invert: true, quiet_zone: 1 };
+        let blocks = string(&qr.to_strings_with(&options)[0]);
+        assert_eq!(blocks.lines().count(), 21 + 2);
+        // the inverted quiet zone is solid
+        assert_eq!(blocks.lines().next().unwrap(), "██".repeat(23));
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn braille_cells() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(40), EcLevel::L).unwrap();
+        let braille = match &qr.to_strings_styled(TextStyle::Braille)[0] { QrData::String(s) => s.clone(), _ => unreachable!() };
+        // 177 modules plus 8 of quiet zone
+        assert_eq!(braille.lines().count(), 185usize.div_ceil(4));
+        assert!(braille.lines().all(|line| line.chars().count() == 185usize.div_ceil(2)));
+        // the quiet zone has no dots
+        assert!(braille.lines().next().unwrap().chars().all(|c| c == '\u{2800}'));
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn ansi_backgrounds() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(1), EcLevel::L).unwrap();
+        let ansi = match &qr.to_strings_styled(TextStyle::Ansi { invert: false })[0] { QrData::String(s) => s.clone(), _ => unreachable!() };
+        let inverted = match &qr.to_strings_styled(TextStyle::Ansi { invert: true })[0] { QrData::String(s) => s.clone(), _ => unreachable!() };
+        assert_eq!(ansi.lines().count(), 21 + 8);
+        assert!(ansi.lines().all(|line| line.ends_with("\x1b[0m")));
+        assert!(ansi.starts_with("\x1b[107m"));
+        assert!(inverted.starts_with("\x1b[40m"));
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn svg_per_code() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let svgs = qr.to_svgs();
+        assert_eq!(svgs.len(), qr.codes.len());
+        assert!(svgs.iter().all(|svg| matches!(svg, QrData::Svg(s) if s.starts_with("<?xml") && s.ends_with("</svg>"))));
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn render_colors() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(1), EcLevel::L).unwrap();
+        let options = RenderOptions::transparent(image::Rgba([0, 0, 128, 255]));
+        let image = qr.render_code(0, &SaveOptions { render: options, ..Default::default() }).unwrap();
+        // the corner is quiet zone, the finder pattern starts 4 modules in
+        assert_eq!(image.get_pixel(0, 0), &image::Rgba([0, 0, 0, 0]));
+        let module = image.width() / (21 + 8);
+        assert_eq!(image.get_pixel(4 * module, 4 * module), &image::Rgba([0, 0, 128, 255]));
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn logo_boosts_ec() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(1), EcLevel::L).unwrap();
+        let logo = Logo::new(image::RgbaImage::from_pixel(10, 10, image::Rgba([255, 0, 0, 255])));
+        let options = SaveOptions { render: RenderOptions { logo: Some(logo), ..Default::default() }, ..Default::default() };
+        // 12 bytes don't fit version 1 at H, and the version never grows
+        assert_eq!(qr.render_code(0, &options), Err(QrError::DataTooLong));
+
+        let short = MultiQrCode::new("Hello", Version::Normal(1), EcLevel::L).unwrap();
+        let image = short.render_code(0, &options).unwrap();
+        assert_eq!(image.width(), (21 + 8) * 8);
+        let center = image.width() / 2;
+        assert_eq!(image.get_pixel(center, center), &image::Rgba([255, 0, 0, 255]));
+
+        // sized for H up front, a full set keeps version 40 with a logo on every code
+        let data: Vec<u8> = (0..=255).cycle().take(5000).collect();
+        let qr = MultiQrCodeBuilder::new().logo(true).build(&data).unwrap();
+        assert!(qr.codes.iter().all(|code| code.version() == Version::Normal(40) && code.error_correction_level() == EcLevel::H));
+        let small = SaveOptions { module_px: 1, ..options };
+        assert_eq!(qr.render_code(0, &small).unwrap().width(), 177 + 8);
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn module_shapes() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(1), EcLevel::L).unwrap();
+        let square = qr.render_code(0, &SaveOptions::default()).unwrap();
+        // the default square path matches qrcode's own renderer
+        assert_eq!(square, qr.codes[0].render::<image::Rgba<u8>>().build());
+
+        let render = RenderOptions { shape: std::sync::Arc::new(render::Dot::default()), ..Default::default() };
+        let dots = qr.render_code(0, &SaveOptions { render, ..Default::default() }).unwrap();
+        assert_eq!(dots.dimensions(), square.dimensions());
+        assert_ne!(dots, square);
+        // finder pattern corners stay square
+        assert_eq!(dots.get_pixel(32, 32), square.get_pixel(32, 32));
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn png_bytes() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let pngs = qr.to_png_bytes().unwrap();
+        assert_eq!(pngs.len(), qr.codes.len());
+        let decoded = image::load_from_memory_with_format(&pngs[0], image::ImageFormat::Png).unwrap().to_luma8();
+        assert_eq!(decoded, qr.codes[0].render::<Luma<u8>>().build());
+        assert!(qr.write_png(qr.codes.len(), Vec::new()).is_err());
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn image_formats() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(1), EcLevel::L).unwrap();
+        assert_eq!(ImageFormat::from_path("a.JPG"), Some(ImageFormat::Jpeg { quality: 90 }));
+        assert_eq!(ImageFormat::from_path("a.txt"), None);
+        for (format, magic) in [(ImageFormat::Bmp, &b"BM"[..]), (ImageFormat::Jpeg { quality: 50 }, &[0xff, 0xd8]), (ImageFormat::Tiff, b"II")] {
+            let mut bytes = Vec::new();
+            qr.write_image(0, &mut bytes, &SaveOptions { format: Some(format), ..Default::default() }).unwrap();
+            assert!(bytes.starts_with(magic), "{:?}", format);
+        }
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn data_uris() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let uris = qr.to_data_uris().unwrap();
+        assert_eq!(uris.len(), qr.codes.len());
+        let png = Base64Variant::decode(uris[0].strip_prefix("data:image/png;base64,").unwrap()).unwrap();
+        assert_eq!(png, qr.to_png_bytes().unwrap()[0]);
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn html_page() {
+        let uris = vec!["data:a".to_string(), "data:b".to_string()];
+        let options = HtmlOptions { title: "<backup>".to_string(), auto_cycle: Some(2), ..Default::default() };
+        let page = html::render(&uris, &options);
+        assert!(page.contains("<title>&lt;backup&gt;</title>"));
+        assert!(page.contains("<img src=\"data:b\" alt=\"2 of 2\">"));
+        assert!(page.contains("<figcaption>1 of 2</figcaption>"));
+        assert!(page.contains("}, 500);"));
+        assert!(!html::render(&uris, &HtmlOptions::default()).contains("<script>"));
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn images_in_memory() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let images = qr.to_images();
+        assert_eq!(images.len(), qr.codes.len());
+        let colored = qr.to_images_with(&SaveOptions::default()).unwrap();
+        assert_eq!(DynamicImage::ImageRgba8(colored[0].clone()).to_luma8(), images[0]);
+    }
+
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    #[test]
+    fn save_bad_path() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(1), EcLevel::L).unwrap();
+        assert!(matches!(qr.save("./missing-dir/nested/test.png"), Err(MqrError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound));
+    }
+
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    #[test]
+    fn file_name_templates() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let total = qr.codes.len();
+        let names = qr.file_names(Some(Path::new("out")), "backup", "png", "{stem}-{index:03}-of-{total}.{ext}").unwrap();
+        assert_eq!(names[0], Path::new("out").join(format!("backup-000-of-{}.png", total)));
+        let names = qr.file_names(None, "backup", "png", naming::DEFAULT_TEMPLATE).unwrap();
+        assert_eq!(names[1], PathBuf::from("backup.1.png"));
+        let names = qr.file_names(None, "b", "png", "{number:2}").unwrap();
+        assert_eq!(names[0], PathBuf::from("01"));
+        assert!(qr.file_names(None, "b", "png", "{page}").is_err());
+        assert!(qr.file_names(None, "b", "png", "{index").is_err());
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn inverted_render() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(1), EcLevel::L).unwrap();
+        let options = SaveOptions { module_px: 2, quiet_zone_modules: 4, render: RenderOptions { invert: true, ..Default::default() }, ..Default::default() };
+        let image = qr.render_code(0, &options).unwrap();
+        let (black, white) = (image::Rgba([0, 0, 0, 255]), image::Rgba([255, 255, 255, 255]));
+        // the quiet zone is dark, the outer ring of the finder pattern light
+        assert_eq!(*image.get_pixel(0, 0), black);
+        assert_eq!(*image.get_pixel(8, 8), white);
+        assert_eq!(*image.get_pixel(10, 10), black);
+        let normal = qr.render_code(0, &SaveOptions { render: RenderOptions::default(), ..options }).unwrap();
+        assert!(image.pixels().zip(normal.pixels()).all(|(a, b)| a != b));
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn quiet_zones() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(1), EcLevel::L).unwrap();
+        let bare = SaveOptions { module_px: 2, quiet_zone_modules: 0, ..Default::default() };
+        assert_eq!(qr.to_images_with(&bare).unwrap()[0].width(), 21 * 2);
+        assert_eq!(qr.to_sheet_with(1, 0, &bare).unwrap().width(), 21 * 2);
+        let svg = match &qr.to_svgs_with(&bare)[0] {
+            QrData::Svg(svg) => svg.clone(),
+            _ => unreachable!()
+        };
+        assert!(svg.contains("width=\"42\" height=\"42\" viewBox=\"0 0 21 21\""));
+        assert!(svg.contains("<path d=\"M0 0h7v1h-7z"));
+        let svg = match &qr.to_svgs_with(&SaveOptions { quiet_zone_modules: 2, ..bare })[0] {
+            QrData::Svg(svg) => svg.clone(),
+            _ => unreachable!()
+        };
+        assert!(svg.contains("viewBox=\"0 0 25 25\""));
+        assert!(svg.contains("<path d=\"M2 2h7v1h-7z"));
+        let svg = match &qr.to_svgs_with_quiet_zone(0)[0] {
+            QrData::Svg(svg) => svg.clone(),
+            _ => unreachable!()
+        };
+        assert!(svg.contains("width=\"168\" height=\"168\" viewBox=\"0 0 21 21\""));
+        assert!(svg.contains("<path d=\"M0 0h7v1h-7z"));
+
+        let text = |quiet_zone| match &qr.to_strings_with(&TextRenderOptions { quiet_zone, ..Default::default() })[0] {
+            QrData::String(text) => text.lines().next().unwrap().len(),
+            _ => unreachable!()
+        };
+        assert_eq!((text(0), text(4)), (21, 29));
+
+        // without a quiet zone the finder pattern starts right at the 36pt margin
+        let pdf = |quiet_zone| String::from_utf8(qr.to_pdf(&PageOptions { quiet_zone, ..Default::default() }).unwrap()).unwrap();
+        assert!(pdf(0).contains("\n36.000 "));
+        assert!(!pdf(4).contains("\n36.000 "));
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn save_options_scale() {
+        let qr = MultiQrCode::new("Hello world!", Version::Normal(1), EcLevel::L).unwrap();
+        let options = SaveOptions { module_px: 3, quiet_zone_modules: 1, ..Default::default() };
+        assert_eq!(qr.render_code(0, &options).unwrap().width(), (21 + 2) * 3);
+        let options = SaveOptions { min_dimensions: Some((500, 500)), ..options };
+        assert_eq!(qr.render_code(0, &options).unwrap().width(), (21 + 2) * 22);
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn sheet_grid() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let tile = qr.render_code(0, &SaveOptions::default()).unwrap();
+        let sheet = qr.to_sheet(3, 10).unwrap();
+        let rows = qr.codes.len().div_ceil(3) as u32;
+        assert_eq!(sheet.width(), 3 * (tile.width() + 10) + 10);
+        assert_gt!(sheet.height(), rows * (tile.height() + 10));
+        assert!(qr.to_sheet(0, 10).is_err());
+
+        // a caption template relabels the sheet instead of adding a second label under each tile
+        let captioned = qr.to_sheet_with(3, 10, &SaveOptions::captioned()).unwrap();
+        assert_eq!(captioned.dimensions(), sheet.dimensions());
+        assert_ne!(captioned, sheet);
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn captioned_images() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let plain = qr.render_code(1, &SaveOptions::default()).unwrap();
+        let captioned = qr.render_code(1, &SaveOptions::captioned()).unwrap();
+        let scale = (plain.width() / 100).max(2);
+        assert_eq!(captioned.dimensions(), (plain.width(), plain.height() + 7 * scale));
+        assert_eq!(*image::imageops::crop_imm(&captioned, 0, 0, plain.width(), plain.height()).to_image(), *plain);
+        let strip = image::imageops::crop_imm(&captioned, 0, plain.height(), plain.width(), 7 * scale).to_image();
+        assert!(strip.pixels().any(|pixel| pixel.0 == [0, 0, 0, 255]));
+
+        let custom = SaveOptions { caption: Some("box 4 - {number}".to_string()), ..Default::default() };
+        assert_ne!(qr.render_code(1, &custom).unwrap(), captioned);
+        assert_eq!(naming::caption("{index}: {number} / {total}", 1, 12), "1: 2 / 12");
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn gif_frames() {
+        use image::AnimationDecoder;
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let mut gif = Vec::new();
+        qr.write_animation(&mut gif, AnimationFormat::Gif, 5, true).unwrap();
+        let frames = image::codecs::gif::GifDecoder::new(&gif[..]).unwrap().into_frames().collect_frames().unwrap();
+        assert_eq!(frames.len(), qr.codes.len());
+        assert_eq!(frames[0].delay().numer_denom_ms(), (200, 1));
+        assert_eq!(AnimationFormat::from_path("codes.GIF"), Some(AnimationFormat::Gif));
+        assert_eq!(AnimationFormat::from_path("codes.txt"), None);
+    }
+
+    #[cfg(feature = "apng")]
+    #[test]
+    fn apng_frames() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let mut apng = Vec::new();
+        qr.write_animation(&mut apng, AnimationFormat::Apng, 5, false).unwrap();
+        let reader = png::Decoder::new(&apng[..]).read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!(control.num_frames as usize, qr.codes.len());
+        assert_eq!(control.num_plays, 1);
+        let frame = reader.info().frame_control.unwrap();
+        assert_eq!((frame.delay_num, frame.delay_den), (1, 5));
+        assert_eq!(AnimationFormat::from_path("codes.apng"), Some(AnimationFormat::Apng));
+    }
+
+    #[cfg(feature = "webp")]
+    #[test]
+    fn webp_frames() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let mut webp = Vec::new();
+        qr.write_animation(&mut webp, AnimationFormat::WebP, 5, false).unwrap();
+        let timestamps: Vec<i32> = webp_animation::Decoder::new(&webp).unwrap().into_iter().map(|frame| frame.timestamp()).collect();
+        assert_eq!(timestamps.len(), qr.codes.len());
+        assert_eq!(timestamps[1] - timestamps[0], 200);
+        // ANIM is the first chunk after VP8X, its loop count follows the background color
+        let anim = webp.windows(4).position(|w| w == b"ANIM").unwrap();
+        assert_eq!(webp[anim + 12..anim + 14], [1, 0]);
+
+        // faster than a millisecond per frame still gives every frame a duration
+        let mut fast = Vec::new();
+        qr.write_animation(&mut fast, AnimationFormat::WebP, 5000, true).unwrap();
+        let timestamps: Vec<i32> = webp_animation::Decoder::new(&fast).unwrap().into_iter().map(|frame| frame.timestamp()).collect();
+        assert_eq!(timestamps.len(), qr.codes.len());
+        let anim = fast.windows(4).position(|w| w == b"ANIM").unwrap();
+        assert_eq!(fast[anim + 12..anim + 14], [0, 0]);
+    }
+
+    #[test]
+    fn pdf_pages() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::L).unwrap();
+        let options = PageOptions { per_row: 3, ..Default::default() };
+        let pdf = String::from_utf8(qr.to_pdf(&options).unwrap()).unwrap();
+        // 3 columns of 174pt codes with captions leave room for 3 rows on A4
+        let pages = qr.codes.len().div_ceil(9);
+        assert!(pdf.starts_with("%PDF-1.4"));
+        assert!(pdf.contains(&format!("/Count {} >>", pages)));
+        assert!(pdf.contains(&format!("({} of {})", qr.codes.len(), qr.codes.len())));
+        assert!(qr.to_pdf(&PageOptions { per_row: 0, ..options }).is_err());
+
+        let labelled = PageOptions { caption: Some("page (part) {number}/{total}".to_string()), ..Default::default() };
+        let pdf = String::from_utf8(qr.to_pdf(&labelled).unwrap()).unwrap();
+        assert!(pdf.contains(&format!("(page \\(part\\) 1/{})", qr.codes.len())));
+    }
+
+    #[test]
+    fn eps_export() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::L).unwrap();
+        let docs = qr.to_eps(2.0).unwrap();
+        assert_eq!(docs.len(), qr.codes.len());
+        // version 5 is 37 modules, plus 4 on every side
+        assert!(docs[0].starts_with("%!PS-Adobe-3.0 EPSF-3.0\n%%BoundingBox: 0 0 90 90\n"));
+        assert!(docs[0].ends_with("%%EOF\n"));
+        assert!(qr.to_eps(0.0).is_err());
+
+        let sheet = qr.to_eps_sheet(3, 1.0, None).unwrap();
+        let rows = qr.codes.len().div_ceil(3);
+        assert!(sheet.contains(&format!("%%BoundingBox: 0 0 135 {}\n", rows * 65)));
+        assert!(sheet.contains(&format!("({} of {}) show", qr.codes.len(), qr.codes.len())));
+        assert!(qr.to_eps_sheet(0, 1.0, None).is_err());
+
+        let bare = qr.to_eps_with(2.0, 0).unwrap();
+        assert!(bare[0].contains("%%BoundingBox: 0 0 74 74\n"));
+        assert!(qr.to_eps_sheet_with(3, 1.0, None, 0).unwrap().contains(&format!("%%BoundingBox: 0 0 111 {}\n", rows * 57)));
+    }
+
+    #[test]
+    #[cfg(feature = "escpos")]
+    fn escpos_raster() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::L).unwrap();
+        let options = EscPosOptions { paper: PaperWidth::Mm58, cut: true, ..Default::default() };
+        let jobs = qr.to_escpos(&options).unwrap();
+        assert_eq!(jobs.len(), qr.codes.len());
+        // 45 modules with the quiet zone fit 8 times into 384 dots
+        assert_eq!(jobs[0][..13], [0x1b, b'@', 0x1b, b'a', 1, 0x1d, b'v', b'0', 0, 48, 0, 104, 1]);
+        let raster = &jobs[0][13..13 + 48 * 360];
+        // the top left finder pattern starts 12 dots in plus 4 modules of quiet zone
+        assert_eq!(raster[32 * 48 + 5], 0b0000_1111);
+        assert!(jobs[0].ends_with(format!("1 of {}\n\x1bd\x03\x1dVB\x00", qr.codes.len()).as_bytes()));
+
+        let mut all = Vec::new();
+        qr.write_escpos(&mut all, &options).unwrap();
+        assert_eq!(all, jobs.concat());
+        assert!(qr.to_escpos(&EscPosOptions { paper: PaperWidth::Dots(40), ..options.clone() }).is_err());
+
+        // without a quiet zone 37 modules fit 10 times, the finder pattern starts 7 dots in
+        let bare = &qr.to_escpos(&EscPosOptions { quiet_zone: 0, ..options }).unwrap()[0];
+        assert_eq!(bare[11..14], [0x72, 1, 0b0000_0001]);
+    }
+
+    #[test]
+    fn encoding_stats() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::L).unwrap();
+        let stats = qr.stats();
+        assert_eq!(stats.chunks, qr.len());
+        assert_eq!(stats.data_bytes, LIPSUM.len());
+        // version 5-L holds 108 bytes, 2 go to the byte segment and 1 to the header
+        assert_eq!(stats.chunk_bytes[0], 106);
+        assert_eq!(stats.overhead_bytes, 3 * qr.len());
+        let last = stats.chunk_bytes[qr.len() - 1];
+        assert_eq!(stats.last_chunk_percent, 100.0 * last as f32 / 106.0);
+        assert_eq!(stats.unused_bytes, 106 - last);
+        // 45 modules of 3 dots at 300 dpi
+        assert!((stats.printed_sides(300, 3)[0] - 11.43).abs() < 0.001);
+        assert!((stats.printed_area(300, 3) - qr.len() as f32 * 11.43 * 11.43).abs() < 0.1);
+
+        let header = HeaderFormat::Extended(IndexWidth::U16);
+        let extended = MultiQrCode::builder().version(Version::Normal(5)).header(header).build(LIPSUM).unwrap().stats_with(header);
+        assert_eq!(extended.data_bytes, LIPSUM.len());
+        assert!(extended.efficiency() < stats.efficiency());
+
+        // parity codes, chunk crcs and the manifest code count as overhead, as does an ECI header
+        let qr = MultiQrCode::builder().version(Version::Normal(5)).header(header).parity(2).chunk_crc(true).utf8_eci(true).build(LIPSUM).unwrap();
+        let stored: usize = qr.payloads().iter().map(Vec::len).sum();
+        let protected = qr.stats_with(header);
+        assert_eq!(protected.data_bytes, LIPSUM.len());
+        // 4 bits of mode indicator, 8 of length and 12 of ECI header
+        assert_eq!(protected.overhead_bytes, stored - LIPSUM.len() + 3 * qr.len());
+        let qr = MultiQrCode::builder().version(Version::Normal(5)).manifest(true).build(LIPSUM).unwrap();
+        let stored: usize = qr.payloads().iter().map(Vec::len).sum();
+        assert_eq!(qr.stats().data_bytes, LIPSUM.len());
+        assert_eq!(qr.stats().overhead_bytes, stored - LIPSUM.len() + 2 * qr.len());
+    }
+
+    #[test]
+    #[cfg(feature = "render-image")]
+    fn render_cache() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::L).unwrap();
+        let rendered = qr.rendered();
+        let text = |data: Vec<QrData>| data.into_iter().map(|data| match data {
+            QrData::String(s) | QrData::Svg(s) => s,
+            QrData::Base64 { data, .. } | QrData::Base45 { data, .. } | QrData::Hex { data, .. } => data,
+            QrData::Raw { data, .. } => Base64Variant::Standard.encode(data)
+        }).collect::<Vec<_>>();
+        assert_eq!(text(rendered.to_strings()), text(qr.to_strings()));
+        assert_eq!(text(rendered.to_base64()), text(qr.to_base64()));
+        assert_eq!(text(rendered.to_base64_with(Base64Variant::UrlSafeNoPad)), text(qr.to_base64_with(Base64Variant::UrlSafeNoPad)));
+        assert_eq!(text(rendered.to_base45()), text(qr.to_base45()));
+        assert_eq!(text(rendered.to_hex()), text(qr.to_hex()));
+        assert_eq!(text(rendered.to_raw()), text(qr.to_raw()));
+        assert_eq!(rendered.images(), qr.to_images());
+        // computed once, every later call hands out the same matrices
+        assert!(std::ptr::eq(rendered.packed(), rendered.packed()));
+        assert!(std::ptr::eq(rendered.images(), rendered.images()));
+    }
+
+    #[test]
+    #[cfg(all(feature = "async", feature = "render-image"))]
+    fn async_api() {
+        use futures_core::Stream;
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::L).unwrap();
+            let mut stream = CodeStream::new(LIPSUM.as_bytes(), Version::Normal(5), EcLevel::L).unwrap();
+            let mut codes = Vec::new();
+            while let Some(code) = std::future::poll_fn(|cx| std::pin::Pin::new(&mut stream).poll_next(cx)).await {
+                codes.push(code.unwrap().to_colors());
+            }
+            assert_eq!(codes, qr.codes.iter().map(QrCode::to_colors).collect::<Vec<_>>());
+
+            let dir = std::env::temp_dir().join("mqrcode-async");
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("lipsum.txt"), LIPSUM).unwrap();
+            let read = MultiQrCode::from_file_async(dir.join("lipsum.txt"), Version::Normal(5), EcLevel::L).await.unwrap();
+            assert_eq!(read.payloads(), qr.payloads());
+            let saved = qr.save_with_async(dir.join("qr.bmp"), &SaveOptions::default()).await.unwrap();
+            assert_eq!(saved.len(), qr.len());
+            assert!(saved.iter().all(|path| path.exists()));
+            std::fs::remove_dir_all(&dir).unwrap();
+        });
+    }
+
+    #[test]
+    fn fixed_chunk_size() {
+        let header = HeaderFormat::Extended(IndexWidth::U16);
+        let qr = MultiQrCode::builder().version(Version::Normal(10)).header(header).chunk_size(100).build(LIPSUM).unwrap();
+        assert_eq!(qr.len(), LIPSUM.len().div_ceil(100));
+        assert!(qr.payloads()[..qr.len() - 1].iter().all(|payload| payload.len() == header.size() + 100));
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), header).unwrap(), LIPSUM.as_bytes());
+
+        // version 10-L holds 274 bytes, 3 go to the byte segment and 9 to the header
+        assert!(MultiQrCode::builder().version(Version::Normal(10)).header(header).chunk_size(262).build(LIPSUM).is_ok());
+        assert!(matches!(MultiQrCode::builder().version(Version::Normal(10)).header(header).chunk_size(263).build(LIPSUM), Err(MqrError::Qr(QrError::DataTooLong))));
+        assert!(matches!(MultiQrCode::builder().chunk_size(0).build(LIPSUM), Err(MqrError::InvalidOptions(_))));
+        assert!(matches!(MultiQrCode::builder().chunk_size(50).optimize_segments(true).build(LIPSUM), Err(MqrError::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn paper_backup() {
+        let header = HeaderFormat::Extended(IndexWidth::U16);
+        let qr = MultiQrCode::builder().version(Version::Normal(5)).header(header).build(LIPSUM).unwrap();
+        let options = BackupOptions { title: "Lipsum".to_string(), date: Some("2024-01-02".to_string()), header, ..Default::default() };
+        let pdf = String::from_utf8(qr.to_paper_backup(&options).unwrap()).unwrap();
+        assert!(pdf.contains("(Lipsum)"));
+        assert!(pdf.contains("2024-01-02"));
+        assert!(pdf.contains("--header extended"));
+        #[cfg(not(feature = "sha256"))]
+        assert!(pdf.contains(&format!("{:08x}", crc32fast::hash(LIPSUM.as_bytes()))));
+        // the cover page comes before the same pages to_pdf writes
+        let count = |pdf: &str| pdf.split("/Count ").nth(1).and_then(|rest| rest.split(' ').next()?.parse::<usize>().ok()).unwrap();
+        assert_eq!(count(&pdf), count(&String::from_utf8(qr.to_pdf(&options.page).unwrap()).unwrap()) + 1);
+        assert!(pdf.contains(&format!("({} of {})", qr.codes.len(), qr.codes.len())));
+        assert!(qr.to_paper_backup(&BackupOptions { page: PageOptions { per_row: 0, ..Default::default() }, ..options.clone() }).is_err());
+        // the cover text with its wrapped lines joined back up
+        let text = |pdf: &str| pdf.split(") Tj").filter_map(|part| part.rsplit_once(" Td (").map(|(_, line)| line)).collect::<Vec<_>>().join(" ");
+        assert!(text(&pdf).contains("Sort the chunks by index from 0"));
+
+        // the restore text follows the flags the set was built with
+        let qr = MultiQrCode::builder().version(Version::Normal(5)).header(header).striped(true).chunk_crc(true).parity(2).build(LIPSUM).unwrap();
+        let pdf = text(&String::from_utf8(qr.to_paper_backup(&options).unwrap()).unwrap());
+        let total = qr.len() - 2;
+        assert!(pdf.contains(&format!("any {} of the {} codes", total, qr.len())));
+        assert!(pdf.contains(&format!("Codes from index {} on are Reed-Solomon parity codes", total)));
+        assert!(pdf.contains("the crc32 of the header and chunk"));
+        assert!(pdf.contains("striped"));
+        assert!(!pdf.contains("Sort the chunks"));
+
+        // the manifest code isn't part of the data
+        let qr = MultiQrCode::builder().version(Version::Normal(5)).manifest(true).build(LIPSUM).unwrap();
+        let pdf = text(&String::from_utf8(qr.to_paper_backup(&BackupOptions { header: HeaderFormat::Legacy, ..options }).unwrap()).unwrap());
+        assert!(pdf.contains("--manifest"));
+        assert!(pdf.contains("Sort the chunks by index from 1"));
+        #[cfg(not(feature = "sha256"))]
+        assert!(pdf.contains(&format!("{:08x}", crc32fast::hash(LIPSUM.as_bytes()))));
+    }
+
+    #[test]
+    fn estimates() {
+        assert_eq!(chunk_capacity(Version::Normal(40), EcLevel::L), 2956 - 1 - 3);
+        assert_eq!(chunk_capacity(Version::Micro(2), EcLevel::L), 0);
+        assert_eq!(estimate_chunks(0, Version::Normal(1), EcLevel::L), 0);
+        assert_eq!(estimate_chunks(100, Version::Micro(4), EcLevel::H), usize::MAX);
+        for (version, ec) in [(Version::Normal(3), EcLevel::H), (Version::Normal(12), EcLevel::Q), (Version::Micro(4), EcLevel::L)] {
+            let qr = MultiQrCode::new(&LIPSUM[..1000], version, ec).unwrap();
+            assert_eq!(estimate_chunks(1000, version, ec), qr.codes.len());
+            assert_eq!(qr.payloads()[0].len(), 1 + chunk_capacity(version, ec));
+        }
+    }
+
+    #[test]
+    fn builder() {
+        let built = MultiQrCode::builder().version(Version::Normal(5)).ec_level(EcLevel::M).build(LIPSUM).unwrap();
+        assert_eq!(built.payloads(), MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::M).unwrap().payloads());
+
+        let built = MultiQrCode::builder().version(Version::Normal(5)).slack(10).build(LIPSUM).unwrap();
+        assert_eq!(built.payloads(), MultiQrCode::with_slack(LIPSUM, Version::Normal(5), DEFAULT_EC_LEVEL, 10).unwrap().payloads());
+
+        let header = HeaderFormat::Extended(IndexWidth::U8);
+        let built = MultiQrCode::builder().version(Version::Normal(8)).header(header).boost_ec(true).build(LIPSUM).unwrap();
+        assert_eq!(built.codes.last().unwrap().error_correction_level(), EcLevel::M);
+        assert_eq!(MultiQrCode::reassemble_with(built.payloads(), header).unwrap(), LIPSUM.as_bytes());
+
+        #[cfg(feature = "aes-gcm")]
+        assert!(matches!(MultiQrCode::builder().encryption(Encryption::AesGcm { key: [1; 32] }).build(LIPSUM), Err(MqrError::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn optimized_segments() {
+        let digits = "0123456789".repeat(300);
+        let options = EncodeOptions { optimize_segments: true, ..Default::default() };
+        for data in [digits.as_str(), "HELLO WORLD $42 ".repeat(100).as_str(), LIPSUM] {
+            let plain = MultiQrCode::new(data, Version::Normal(10), EcLevel::L).unwrap();
+            let optimized = MultiQrCode::with_options(data, Version::Normal(10), EcLevel::L, &options).unwrap();
+            assert_le!(optimized.codes.len(), plain.codes.len());
+            assert_eq!(MultiQrCode::reassemble(optimized.payloads()).unwrap(), data.as_bytes());
+        }
+        // a version 10 code holds 270 bytes, but over 600 digits
+        let optimized = MultiQrCode::with_options(&digits, Version::Normal(10), EcLevel::L, &options).unwrap();
+        assert_eq!(optimized.codes.len(), 5);
+
+        let micro = MultiQrCode::builder().version(Version::Micro(3)).optimize_segments(true).build(&digits).unwrap();
+        assert_eq!(MultiQrCode::reassemble(micro.payloads()).unwrap(), digits.as_bytes());
+    }
+
+    #[test]
+    fn utf8_eci() {
+        let text = "Grüße aus Köln, 日本語のテキスト. ".repeat(40);
+        for (version, optimize_segments) in [(Version::Normal(9), false), (Version::Normal(10), false), (Version::Normal(40), false), (Version::Normal(10), true)] {
+            let options = EncodeOptions { utf8_eci: true, optimize_segments, ..Default::default() };
+            let qr = MultiQrCode::with_options(&text, version, EcLevel::L, &options).unwrap();
+            assert_eq!(MultiQrCode::reassemble(qr.payloads()).unwrap(), text.as_bytes());
+
+            let qr = MultiQrCode::with_options(LIPSUM, version, EcLevel::L, &options).unwrap();
+            let plain = MultiQrCode::with_options(LIPSUM, version, EcLevel::L, &EncodeOptions { optimize_segments, ..Default::default() }).unwrap();
+            assert_ne!(qr.codes[0].to_colors(), plain.codes[0].to_colors());
+        }
+        let options = EncodeOptions { utf8_eci: true, ..Default::default() };
+        assert!(MultiQrCode::with_options(&text, Version::Micro(4), EcLevel::L, &options).is_err());
+    }
+
+    #[test]
+    fn kanji() {
+        let text = "日本語のテキストを漢字モードで保存します。".repeat(20);
+        let header = HeaderFormat::Extended(IndexWidth::U8);
+        #[cfg(not(feature = "kanji"))]
+        assert!(matches!(MultiQrCode::builder().header(header).kanji(true).build(&text), Err(MqrError::InvalidOptions(_))));
+        #[cfg(feature = "kanji")]
+        {
+            let qr = MultiQrCode::builder().version(Version::Normal(10)).header(header).kanji(true).build(&text).unwrap();
+            let plain = MultiQrCode::builder().version(Version::Normal(10)).header(header).build(&text).unwrap();
+            assert_lt!(qr.codes.len(), plain.codes.len());
+            assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), header).unwrap(), text.as_bytes());
+            assert!(MultiQrCode::builder().kanji(true).build(&text).is_err());
+            assert!(MultiQrCode::builder().header(header).kanji(true).build([0xff, 0xfe]).is_err());
+        }
+    }
+
+    #[test]
+    fn str_chunked() {
+        let text = "Grüße aus Köln, 日本語のテキスト, emoji 🦀🦀. ".repeat(30);
+        for version in [Version::Normal(1), Version::Normal(9), Version::Normal(10), Version::Micro(4)] {
+            let qr = MultiQrCode::from_str_chunked(&text, version, EcLevel::L).unwrap();
+            for payload in qr.payloads() {
+                assert!(std::str::from_utf8(&payload[1..]).is_ok());
+                assert_le!(payload.len(), 1 + chunk_capacity(version, EcLevel::L));
+            }
+            assert_eq!(MultiQrCode::reassemble(qr.payloads()).unwrap(), text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn decode_session() {
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let qr = MultiQrCode::with_header(LIPSUM, Version::Normal(10), EcLevel::L, format).unwrap();
+        let total = qr.payloads().len();
+        let mut session = DecodeSession::with_header(format);
+        assert_eq!(session.progress(), None);
+
+        for payload in qr.payloads().iter().rev().skip(1) {
+            assert_eq!(session.push(payload).unwrap(), None);
+            assert_eq!(session.push(payload).unwrap(), None);
+        }
+        assert_eq!(session.total(), Some(total));
+        assert_eq!(session.missing().into_iter().collect::<Vec<_>>(), [total - 1]);
+        assert_eq!(session.received().len(), total - 1);
+        assert_eq!(session.progress(), Some((total - 1) as f32 / total as f32));
+        assert!(matches!(session.finish(), Err(DecodeError::MissingChunk(_))));
+
+        assert_eq!(session.push(&qr.payloads()[total - 1]).unwrap().unwrap(), LIPSUM.as_bytes());
+        assert!(session.is_complete());
+        assert_eq!(session.push(&qr.payloads()[0]).unwrap(), None);
+
+        // the legacy header has no total, gaps are all that can be reported
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let mut session = DecodeSession::new();
+        session.push(&qr.payloads()[0]).unwrap();
+        session.push(&qr.payloads()[3]).unwrap();
+        assert_eq!(session.missing().into_iter().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(session.progress(), None);
+    }
+
+    #[test]
+    fn decode_session_retries() {
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+
+        // an encrypted set is reported complete and left to the caller to decrypt
+        #[cfg(feature = "aes-gcm")]
+        {
+            let qr = MultiQrCode::builder().version(Version::Normal(10)).header(format).encryption(Encryption::AesGcm { key: [7; 32] }).build(LIPSUM).unwrap();
+            let mut session = DecodeSession::with_header(format);
+            for payload in qr.payloads() {
+                assert_eq!(session.push(payload).unwrap(), None);
+            }
+            assert!(session.is_complete() && session.is_encrypted());
+            assert_eq!(MultiQrCode::reassemble_decrypt(session.payloads(), format, &[7; 32]).unwrap(), LIPSUM.as_bytes());
+        }
+
+        // a misread parity code fails the set once it looks complete, a good one pushed later
+        // still completes it
+        let qr = MultiQrCode::builder().version(Version::Normal(10)).header(format).parity(2).build(LIPSUM).unwrap();
+        let total = qr.len() - 2;
+        let mut corrupted = qr.payloads()[total + 1].clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        let mut session = DecodeSession::with_header(format);
+        for payload in &qr.payloads()[1..total] {
+            assert_eq!(session.push(payload).unwrap(), None);
+        }
+        assert!(!session.is_encrypted());
+        assert_eq!(session.push(&corrupted), Err(DecodeError::ChecksumMismatch));
+        assert_eq!(session.push(&qr.payloads()[total]).unwrap().unwrap(), LIPSUM.as_bytes());
+        assert_eq!(session.push(&qr.payloads()[0]).unwrap(), None);
+    }
+
+    #[test]
+    fn transfer() {
+        assert_eq!(Interleave::Sequential.order(5), [0, 1, 2, 3, 4]);
+        assert_eq!(Interleave::Stride(2).order(5), [0, 2, 4, 1, 3]);
+        assert_eq!(Interleave::Stride(9).order(3), [0, 1, 2]);
+        let mut shuffled = Interleave::Shuffled { seed: 7 }.order(20);
+        assert_eq!(shuffled, Interleave::Shuffled { seed: 7 }.order(20));
+        assert_ne!(shuffled, Interleave::Sequential.order(20));
+        shuffled.sort();
+        assert_eq!(shuffled, Interleave::Sequential.order(20));
+
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let qr = MultiQrCode::with_header(LIPSUM, Version::Normal(10), EcLevel::L, format).unwrap();
+        let total = qr.len();
+        let transmitter = Transmitter::new(&qr).fps(30).repeat(Some(2)).interleave(Interleave::Stride(2));
+        assert_eq!(transmitter.frame_duration(), std::time::Duration::from_secs(1) / 30);
+        let schedule: Vec<usize> = transmitter.schedule().collect();
+        assert_eq!(schedule.len(), 2 * total);
+        assert_eq!(schedule[..3], [0, 2, 4]);
+        assert_eq!(schedule[..total], schedule[total..]);
+        assert_eq!(Transmitter::new(&qr).repeat(None).schedule().take(3 * total).count(), 3 * total);
+        let empty = MultiQrCode::new([], Version::Normal(10), EcLevel::L).unwrap();
+        assert_eq!(Transmitter::new(&empty).repeat(None).schedule().count(), 0);
+        assert!(transmitter.frames().all(|(i, code)| code.to_colors() == qr.codes[i].to_colors()));
+
+        let mut shown = 0;
+        Transmitter::new(&qr).fps(1000).repeat(None).run(|_, _| { shown += 1; shown < 5 }).unwrap();
+        assert_eq!(shown, 5);
+        assert!(Transmitter::new(&qr).fps(0).run(|_, _| true).is_err());
+
+        // a camera that misses every third frame of the first pass and sees some junk
+        let mut receiver = Receiver::new(format);
+        let frames: Vec<usize> = transmitter.schedule().enumerate().filter(|(n, _)| n % 3 != 0).map(|(_, i)| i).collect();
+        let mut data = None;
+        for (n, i) in frames.into_iter().enumerate() {
+            if n == 1 {
+                assert_eq!(receiver.push(b"junk").unwrap(), None);
+            }
+            if let Some(done) = receiver.push(&qr.payloads()[i]).unwrap() {
+                data = Some(done.to_vec());
+                break
+            }
+        }
+        assert_eq!(data.unwrap(), LIPSUM.as_bytes());
+        assert!(receiver.is_done());
+        assert_eq!(receiver.rejected(), 1);
+        assert_gt!(receiver.duplicates(), 0);
+        assert_eq!(receiver.into_data().unwrap(), LIPSUM.as_bytes());
+    }
+
+    #[cfg(all(feature = "rqrr", feature = "render-image"))]
+    #[test]
+    fn verify() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        assert!(qr.verify().unwrap().is_empty());
+        let micro = MultiQrCode::new("Hello world!", Version::Micro(4), EcLevel::L).unwrap();
+        assert!(matches!(micro.verify(), Err(MqrError::InvalidOptions(_))));
+        // dark modules drawn in the background color can't be read by anything
+        let options = SaveOptions { render: RenderOptions { dark: image::Rgba([255, 255, 255, 255]), ..Default::default() }, ..Default::default() };
+        assert_eq!(qr.verify_with(&options).unwrap(), (0..qr.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn append_merge() {
+        let (head, tail) = LIPSUM.split_at(700);
+        let mut qr = MultiQrCode::new(head, Version::Normal(10), EcLevel::L).unwrap();
+        let first = qr.codes[0].to_colors();
+        let before = qr.len();
+        qr.append(tail, Version::Normal(5), EcLevel::M).unwrap();
+        assert_gt!(qr.len(), before);
+        assert_eq!(qr.codes[0].to_colors(), first);
+        assert_eq!(qr.codes[before].version(), Version::Normal(5));
+        assert_eq!(MultiQrCode::reassemble(qr.payloads()).unwrap(), LIPSUM.as_bytes());
+        qr.append("", Version::Normal(5), EcLevel::M).unwrap();
+        assert_eq!(MultiQrCode::reassemble(qr.payloads()).unwrap(), LIPSUM.as_bytes());
+
+        // totals and checksums are rewritten with the richer headers
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let mut qr = MultiQrCode::with_header(head, Version::Normal(10), EcLevel::L, format).unwrap();
+        let other = MultiQrCode::with_header(tail, Version::Normal(8), EcLevel::Q, format).unwrap();
+        qr.merge_with(&other, format).unwrap();
+        assert_eq!(qr.len(), qr.payloads().len());
+        assert_eq!(qr.codes.last().unwrap().version(), Version::Normal(8));
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), format).unwrap(), LIPSUM.as_bytes());
+        let mut session = DecodeSession::with_header(format);
+        for payload in qr.payloads() {
+            session.push(payload).unwrap();
+        }
+        assert_eq!(session.total(), Some(qr.len()));
+
+        let mut checked = MultiQrCode::builder().version(Version::Normal(10)).header(format).chunk_crc(true).build(head).unwrap();
+        assert!(checked.append_with(tail, Version::Normal(10), EcLevel::L, format).is_err());
+
+        // optimized and eci codes are encoded again the same way with their new totals
+        let digits: String = (0..3000).map(|i| char::from(b'0' + (i * 7 % 10) as u8)).collect();
+        for options in [EncodeOptions { optimize_segments: true, ..Default::default() }, EncodeOptions { utf8_eci: true, ..Default::default() }] {
+            let options = EncodeOptions { header: format, ..options };
+            let mut qr = MultiQrCode::with_options(&digits, Version::Normal(10), EcLevel::L, &options).unwrap();
+            let (before, segments) = (qr.len(), qr.segments[0]);
+            qr.append_with(tail, Version::Normal(10), EcLevel::L, format).unwrap();
+            assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), format).unwrap(), [digits.as_bytes(), tail.as_bytes()].concat());
+            assert_eq!(qr.segments[..before], vec![segments; before][..]);
+            assert_eq!(qr.segments[before], Segments::Bytes { eci: false });
+        }
+
+        let mut manifest = MultiQrCode::builder().version(Version::Normal(10)).manifest(true).build(head).unwrap();
+        assert!(matches!(manifest.append(tail, Version::Normal(10), EcLevel::L), Err(MqrError::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn multipage_tiff() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let options = TiffOptions { dpi: 600, module_px: 4, ..Default::default() };
+        let tiff = qr.to_multipage_tiff(&options).unwrap();
+        assert_eq!(tiff[..4], *b"II*\0");
+
+        let u16_at = |at: usize| u16::from_le_bytes([tiff[at], tiff[at + 1]]) as usize;
+        let u32_at = |at: usize| u32::from_le_bytes(tiff[at..at + 4].try_into().unwrap()) as usize;
+        let tag = |ifd: usize, tag: usize| (0..u16_at(ifd)).map(|i| ifd + 2 + 12 * i).find(|&entry| u16_at(entry) == tag).map(|entry| entry + 8).unwrap();
+        let mut pages = Vec::new();
+        let mut ifd = u32_at(4);
+        while ifd != 0 {
+            pages.push(ifd);
+            ifd = u32_at(ifd + 2 + 12 * u16_at(ifd));
+        }
+        assert_eq!(pages.len(), qr.len());
+        let side = (qr.codes[0].width() + 8) * 4;
+        assert_eq!(u32_at(tag(pages[0], 256)), side);
+        assert_eq!(u32_at(u32_at(tag(pages[0], 282))), 600);
+        assert_eq!((u16_at(tag(pages[1], 297)), u16_at(tag(pages[1], 297) + 2)), (1, qr.len()));
+
+        assert!(qr.to_multipage_tiff(&TiffOptions { dpi: 0, ..Default::default() }).is_err());
+        let empty = MultiQrCode::new("", Version::Normal(1), EcLevel::L).unwrap();
+        assert!(empty.is_empty());
+        assert!(matches!(empty.to_multipage_tiff(&options), Err(MqrError::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn payload_envelope() {
+        // the modification time keeps its nanoseconds
+        let payload = Payload { modified: Some(std::time::UNIX_EPOCH + std::time::Duration::new(1_700_000_000, 123_456_789)), ..Payload::bytes("lipsum.txt", "text/plain", LIPSUM) };
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let qr = MultiQrCode::builder().version(Version::Normal(10)).header(format).build_payload(&payload).unwrap();
+        assert_eq!(MultiQrCode::reassemble_payload(qr.payloads(), format).unwrap(), payload);
+        let qr = MultiQrCode::builder().version(Version::Normal(10)).manifest(true).build_payload(&payload).unwrap();
+        let (manifest, restored) = MultiQrCode::reassemble_payload_manifest(qr.payloads()).unwrap();
+        assert_eq!((manifest.total, restored), (qr.len() - 1, payload.clone()));
+        #[cfg(feature = "aes-gcm")]
+        {
+            let key = [7; 32];
+            let qr = MultiQrCode::builder().version(Version::Normal(10)).header(format).encryption(Encryption::AesGcm { key }).build_payload(&payload).unwrap();
+            assert_eq!(MultiQrCode::reassemble_payload_decrypt(qr.payloads(), format, &key).unwrap(), payload);
+            assert_eq!(MultiQrCode::reassemble_payload(qr.payloads(), format), Err(DecodeError::Encrypted));
+        }
+
+        let bare = Payload { data: b"data".to_vec(), ..Default::default() };
+        assert_eq!(Payload::from_bytes(&bare.to_bytes()).unwrap(), bare);
+        assert_eq!(Payload::from_bytes(LIPSUM.as_bytes()), Err(DecodeError::Malformed));
+
+        #[cfg(feature = "fs")]
+        {
+            let dir = std::env::temp_dir().join("mqrcode-payload");
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            let escaping = Payload { name: Some("../../escape.txt".into()), ..payload.clone() };
+            let restored = escaping.save_in(&dir).unwrap();
+            assert_eq!(restored, dir.join("escape.txt"));
+            assert_eq!(escaping.save_in(&dir).unwrap_err().kind(), std::io::ErrorKind::AlreadyExists);
+            let read = Payload::file(&restored).unwrap();
+            assert_eq!(read, Payload { name: Some("escape.txt".into()), ..payload });
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn symbol_sets() {
+        // the qr backend chunks exactly like MultiQrCode
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let encoder = QrEncoder::new(Version::Normal(10), EcLevel::L);
+        let set = SymbolSet::new(LIPSUM, &encoder, format).unwrap();
+        let qr = MultiQrCode::with_header(LIPSUM, Version::Normal(10), EcLevel::L, format).unwrap();
+        assert_eq!(set.payloads(), qr.payloads());
+        assert_eq!(set.symbols, qr.to_matrices());
+        #[cfg(feature = "render-image")]
+        assert_eq!(set.to_images(2)[0].width() as usize, (qr.codes[0].width() + 8) * 2);
+        assert!(SymbolSet::new(LIPSUM, &QrEncoder::new(Version::Micro(1), EcLevel::L), format).is_err());
+
+        // every other option goes through the same pipeline, whatever the backend
+        let builder = MultiQrCode::builder().version(Version::Normal(10)).ec_level(EcLevel::L).header(format).chunk_crc(true).parity(2);
+        let set = builder.build_symbols(LIPSUM, &encoder).unwrap();
+        assert_eq!(set.payloads(), builder.build(LIPSUM).unwrap().payloads());
+        assert_eq!(MultiQrCode::reassemble_with(&set.payloads()[2..], format).unwrap(), LIPSUM.as_bytes());
+        assert!(matches!(builder.clone().logo(true).build_symbols(LIPSUM, &encoder), Err(MqrError::InvalidOptions(_))));
+
+        #[cfg(feature = "datamatrix")]
+        {
+            let encoder = DataMatrix::new(32).unwrap();
+            assert!(DataMatrix::new(30).is_none());
+            assert_eq!(encoder.capacity(), 60);
+            let set = SymbolSet::new(LIPSUM, &encoder, format).unwrap();
+            assert_eq!(set.len(), LIPSUM.len().div_ceil(60 - format.size()));
+            assert_eq!(MultiQrCode::reassemble_with(set.payloads(), format).unwrap(), LIPSUM.as_bytes());
+            let manifest = MultiQrCode::builder().manifest(true).build_symbols(LIPSUM, &encoder).unwrap();
+            assert_eq!(MultiQrCode::reassemble_manifest(manifest.payloads()).unwrap().1, LIPSUM.as_bytes());
+            let symbol = &set.symbols[0];
+            assert_eq!(symbol.width(), 32);
+            // solid l on the left and bottom of every data region, dotted edges opposite
+            assert!((0..32).all(|i| symbol.get(0, i) && symbol.get(i, 31) && symbol.get(16, i) && symbol.get(i, 15)));
+            assert!((0..32).all(|i| symbol.get(i, 0) == (i % 2 == 0) && symbol.get(31, i) == (i % 2 == 1)));
+            assert_eq!(DataMatrix::default().size(), 144);
+            assert!(DataMatrix::default().encode(&[0; 1556]).is_err());
+        }
+
+        #[cfg(feature = "aztec")]
+        {
+            let encoder = Aztec::full(6).unwrap();
+            assert!(Aztec::compact(5).is_none());
+            assert_eq!(encoder.size(), 41);
+            let set = SymbolSet::new(LIPSUM, &encoder, format).unwrap();
+            assert_eq!(MultiQrCode::reassemble_with(set.payloads(), format).unwrap(), LIPSUM.as_bytes());
+            let symbol = &set.symbols[0];
+            assert_eq!(symbol.width(), 41);
+            // the bullseye: dark rings at even distances from the center, light ones in between
+            assert!((0..=6).all(|d| symbol.get(20 + d, 20) == (d % 2 == 0) && symbol.get(20, 20 - d) == (d % 2 == 0)));
+            assert_eq!(Aztec::default().size(), 151);
+            assert_eq!(Aztec::compact(1).unwrap().size(), 15);
+            let compact = Aztec::compact(4).unwrap();
+            assert_eq!(compact.encode(&vec![7; compact.capacity()]).unwrap().width(), 27);
+            assert!(compact.encode(&vec![7; compact.capacity() + 1]).is_err());
+        }
+    }
+
+    #[test]
+    fn chunk_crc() {
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let qr = MultiQrCode::builder().version(Version::Normal(10)).header(format).chunk_crc(true).build(LIPSUM).unwrap();
+        let plain = MultiQrCode::builder().version(Version::Normal(10)).header(format).build(LIPSUM).unwrap();
+        assert_eq!(qr.payloads()[0].len(), plain.payloads()[0].len());
+        assert_gt!(qr.codes.len(), plain.codes.len());
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), format).unwrap(), LIPSUM.as_bytes());
+
+        let mut corrupted = qr.payloads().to_vec();
+        let last = corrupted[2].len() - 1;
+        corrupted[2][last] ^= 1;
+        assert_eq!(MultiQrCode::reassemble_with(&corrupted, format), Err(DecodeError::CorruptChunk(2)));
+        assert_eq!(DecodeSession::with_header(format).push(&corrupted[2]), Err(DecodeError::CorruptChunk(2)));
+
+        assert!(MultiQrCode::builder().chunk_crc(true).build(LIPSUM).is_err());
+    }
+
+    #[cfg(feature = "render-image")]
+    #[test]
+    fn rendered_callbacks() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::L).unwrap();
+        let options = SaveOptions { format: Some(ImageFormat::Bmp), ..Default::default() };
+        let mut images = Vec::new();
+        qr.for_each_rendered(&options, |index, bytes| {
+            images.push((index, bytes.to_vec()));
+            Ok::<_, MqrError>(())
+        }).unwrap();
+        assert_eq!(images.len(), qr.len());
+        let mut expected = Vec::new();
+        qr.write_image(1, &mut expected, &options).unwrap();
+        assert_eq!(images[1], (1, expected));
+
+        // the caller's own error type stops the loop
+        #[derive(Debug, PartialEq)]
+        enum Upload {
+            Failed(usize),
+            Render
+        }
+        impl From<MqrError> for Upload {
+            fn from(_: MqrError) -> Self {
+                Upload::Render
+            }
+        }
+        let mut calls = 0;
+        let result = qr.for_each_svg(&SaveOptions::default(), |index, svg| {
+            calls += 1;
+            assert!(svg.ends_with("</svg>"));
+            if index == 1 { Err(Upload::Failed(index)) } else { Ok(()) }
+        });
+        assert_eq!((result, calls), (Err(Upload::Failed(1)), 2));
+
+        let mut lines = Vec::new();
+        qr.for_each_text(&TextRenderOptions::default(), |_, text| {
+            lines.push(text.lines().count());
+            Ok::<_, MqrError>(())
+        }).unwrap();
+        assert_eq!(lines, vec![37 + 8; qr.len()]);
+    }
+
+    #[test]
+    fn integrity_choice() {
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let builder = MultiQrCode::builder().version(Version::Normal(10)).header(format);
+        let crc = builder.clone().integrity(Integrity::Crc32).build(LIPSUM).unwrap();
+        assert_eq!(crc.payloads(), builder.clone().chunk_crc(true).build(LIPSUM).unwrap().payloads());
+        let (header, _) = ChunkHeader::parse(format, &crc.payloads()[0]).unwrap();
+        assert_eq!(header.flags, header::FLAG_CHUNK_CRC);
+        let none = builder.clone().chunk_crc(true).integrity(Integrity::None).build(LIPSUM).unwrap();
+        assert_eq!(ChunkHeader::parse(format, &none.payloads()[0]).unwrap().0.flags, 0);
+        assert_eq!(Integrity::from(&EncodeOptions { chunk_crc: true, ..Default::default() }), Integrity::Crc32);
+
+        assert_eq!(Integrity::from(&EncodeOptions { chunk_crc: true, sha256: true, ..Default::default() }), Integrity::Both);
+        #[cfg(feature = "sha256")]
+        {
+            let both = builder.integrity(Integrity::Both).build(LIPSUM).unwrap();
+            let (header, _) = ChunkHeader::parse(format, &both.payloads()[0]).unwrap();
+            assert_eq!(header.flags, header::FLAG_CHUNK_CRC | digest::FLAG_SHA256);
+            assert_eq!(MultiQrCode::reassemble_with(both.payloads(), format).unwrap(), LIPSUM.as_bytes());
+        }
+        #[cfg(not(feature = "sha256"))]
+        assert!(matches!(builder.integrity(Integrity::Sha256).build(LIPSUM), Err(MqrError::InvalidOptions(_))));
+        assert!(MultiQrCode::builder().integrity(Integrity::Crc32).build(LIPSUM).is_err());
+    }
+
+    #[cfg(feature = "sha256")]
+    #[test]
+    fn sha256_digest() {
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let qr = MultiQrCode::builder().version(Version::Normal(10)).header(format).sha256(true).build(LIPSUM).unwrap();
+        assert_eq!(&qr.payloads()[0][7..39], &digest::sha256(LIPSUM.as_bytes())[..]);
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), format).unwrap(), LIPSUM.as_bytes());
+
+        // a wrong digest with the crc32 fixed up so only the digest check can catch it
+        let mut payloads = qr.payloads().to_vec();
+        payloads[0][7] ^= 1;
+        let stream: Vec<u8> = payloads.iter().flat_map(|payload| payload[7..].to_vec()).collect();
+        for payload in payloads.iter_mut() {
+            payload[3..7].copy_from_slice(&crc32fast::hash(&stream).to_be_bytes());
+        }
+        assert_eq!(MultiQrCode::reassemble_with(&payloads, format), Err(DecodeError::DigestMismatch));
+
+        assert!(MultiQrCode::builder().sha256(true).build(LIPSUM).is_err());
+    }
+
+    #[test]
+    fn parity_codes() {
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let qr = MultiQrCode::builder().version(Version::Normal(10)).header(format).parity(3).chunk_crc(true).build(LIPSUM).unwrap();
+        let plain = MultiQrCode::builder().version(Version::Normal(10)).header(format).chunk_crc(true).build(LIPSUM).unwrap();
+        let total = qr.codes.len() - 3;
+        assert_ge!(total, plain.codes.len());
+
+        // any three codes can go, including the short last data chunk
+        for dropped in [[0, 1, 2], [0, total - 1, total + 2], [total, total + 1, total + 2], [1, 3, total - 1]] {
+            let payloads: Vec<&Vec<u8>> = qr.payloads().iter().enumerate().filter(|(i, _)| !dropped.contains(i)).map(|(_, p)| p).collect();
+            assert_eq!(MultiQrCode::reassemble_with(&payloads, format).unwrap(), LIPSUM.as_bytes());
+        }
+        let payloads: Vec<&Vec<u8>> = qr.payloads().iter().skip(4).collect();
+        assert_eq!(MultiQrCode::reassemble_with(&payloads, format), Err(DecodeError::MissingChunk(0)));
+
+        let mut session = DecodeSession::with_header(format);
+        for payload in qr.payloads().iter().rev().take(total - 1) {
+            assert_eq!(session.push(payload).unwrap(), None);
+        }
+        assert_eq!(session.push(&qr.payloads()[1]).unwrap().unwrap(), LIPSUM.as_bytes());
+
+        assert!(MultiQrCode::builder().parity(1).build(LIPSUM).is_err());
+        assert!(MultiQrCode::builder().header(format).parity(1).optimize_segments(true).build(LIPSUM).is_err());
+
+        // the shard limit only applies with parity, a wider header still takes more chunks
+        let data = vec![7; 30000];
+        let wide = HeaderFormat::Extended(IndexWidth::U16);
+        let qr = MultiQrCode::builder().version(Version::Normal(5)).header(wide).build(&data).unwrap();
+        assert_gt!(qr.len(), parity::MAX_SHARDS);
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), wide).unwrap(), data);
+        assert!(MultiQrCode::builder().version(Version::Normal(5)).header(wide).parity(1).build(&data).is_err());
+
+        // a forged total of 4 billion data chunks with one parity chunk after it
+        let forged = HeaderFormat::Extended(IndexWidth::U32);
+        let total = u32::MAX as usize - 1;
+        let mut payload = Vec::new();
+        forged.write(&mut payload, total, total, parity::FLAG_PARITY, 0);
+        payload.extend_from_slice(&[0, 0, 0, 1, 42]);
+        assert_eq!(MultiQrCode::reassemble_with([&payload], forged), Err(DecodeError::Malformed));
+    }
+
+    #[test]
+    fn striped_chunks() {
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let mut qr = MultiQrCode::builder().version(Version::Normal(10)).header(format).striped(true).build(LIPSUM).unwrap();
+        let total = qr.len();
+        let (_, first) = format.read(&qr.payloads()[0]).unwrap();
+        assert_eq!(first[..3], [LIPSUM.as_bytes()[0], LIPSUM.as_bytes()[total], LIPSUM.as_bytes()[2 * total]]);
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads().iter().rev(), format).unwrap(), LIPSUM.as_bytes());
+        // the header says so, every decoder unstripes from the flag alone
+        assert_eq!(ChunkHeader::parse(format, &qr.payloads()[0]).unwrap().0.flags, stripe::FLAG_STRIPED);
+        let mut live = LiveDecoder::new(format);
+        assert_eq!(qr.payloads().iter().rev().find_map(|payload| live.push(payload).unwrap()).unwrap(), LIPSUM.as_bytes());
+        assert_eq!(decode::reassemble_sets(qr.payloads(), format), vec![Ok(LIPSUM.as_bytes().to_vec())]);
+        assert_eq!(MultiQrReader::with_header(format).push(&qr.payloads()[0]), Err(DecodeError::Unsupported));
+        assert!(qr.append_with(LIPSUM, Version::Normal(10), EcLevel::L, format).is_err());
+
+        // a lost code is rebuilt from parity like any other
+        let qr = MultiQrCode::builder().version(Version::Normal(10)).header(format).striped(true).parity(2).build(LIPSUM).unwrap();
+        let payloads: Vec<&Vec<u8>> = qr.payloads().iter().enumerate().filter(|&(i, _)| i != 1 && i != total - 1).map(|(_, p)| p).collect();
+        assert_eq!(MultiQrCode::reassemble_with(&payloads, format).unwrap(), LIPSUM.as_bytes());
+
+        assert!(MultiQrCode::builder().striped(true).build(LIPSUM).is_err());
+        assert!(MultiQrCode::builder().header(format).striped(true).optimize_segments(true).build(LIPSUM).is_err());
+    }
+
+    #[cfg(feature = "render-text")]
+    #[cfg(feature = "ffi")]
+    #[test]
+    fn ffi_roundtrip() {
+        use ffi::*;
+        let mut codes = MqrCodes { codes: std::ptr::null_mut(), count: 0 };
+        let status = unsafe { mqr_encode(LIPSUM.as_ptr(), LIPSUM.len(), 10, MqrEcLevel::L as i32, MqrHeader::ExtendedU8 as i32, &mut codes) };
+        assert_eq!(status, MqrStatus::Ok);
+        let qr = MultiQrCode::with_header(LIPSUM, Version::Normal(10), EcLevel::L, HeaderFormat::Extended(IndexWidth::U8)).unwrap();
+        assert_eq!(codes.count, qr.codes.len());
+
+        let list = unsafe { std::slice::from_raw_parts(codes.codes, codes.count) };
+        assert_eq!(list[0].width, qr.codes[0].width());
+        assert_eq!(list[0].modules.len, qr.codes[0].width() * qr.codes[0].width());
+        let payloads: Vec<*const u8> = list.iter().map(|code| code.payload.data as *const u8).collect();
+        let lens: Vec<usize> = list.iter().map(|code| code.payload.len).collect();
+        let mut data = MqrBuffer { data: std::ptr::null_mut(), len: 0 };
+        let status = unsafe { mqr_decode(payloads.as_ptr(), lens.as_ptr(), codes.count, MqrHeader::ExtendedU8 as i32, &mut data) };
+        assert_eq!(status, MqrStatus::Ok);
+        assert_eq!(unsafe { std::slice::from_raw_parts(data.data, data.len) }, LIPSUM.as_bytes());
+        let status = unsafe { mqr_decode(payloads.as_ptr(), lens.as_ptr(), codes.count - 1, MqrHeader::ExtendedU8 as i32, &mut data) };
+        assert_eq!(status, MqrStatus::MissingChunk);
+
+        unsafe {
+            mqr_free_buffer(&mut data);
+            mqr_free(&mut codes);
+            mqr_free(&mut codes);
+        }
+        assert!(codes.codes.is_null() && data.data.is_null());
+        assert_eq!(unsafe { mqr_encode(LIPSUM.as_ptr(), LIPSUM.len(), 41, 0, 0, &mut codes) }, MqrStatus::InvalidArgument);
+        assert_eq!(unsafe { mqr_encode(LIPSUM.as_ptr(), LIPSUM.len(), 10, 4, 0, &mut codes) }, MqrStatus::InvalidArgument);
+    }
+
+    #[cfg(feature = "render-text")]
+    #[test]
+    fn manifest_code() {
+        let qr = MultiQrCode::builder().version(Version::Normal(10)).manifest(true).filename("lipsum.txt").build(LIPSUM).unwrap();
+        let plain = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        assert_eq!(qr.codes.len(), plain.codes.len() + 1);
+
+        let manifest = Manifest::read(&qr.payloads()[0]).unwrap();
+        assert_eq!(manifest, Manifest {
+            total: plain.codes.len(),
+            length: LIPSUM.len(),
+            crc32: crc32fast::hash(LIPSUM.as_bytes()),
+            flags: 0,
+            sha256: None,
+            filename: Some("lipsum.txt".to_string())
+        });
+        assert_eq!(manifest.to_string(), format!("expecting {} codes, 3 KB, file lipsum.txt", plain.codes.len()));
+        assert_eq!(Manifest::read(&qr.payloads()[1]), Err(DecodeError::Malformed));
+
+        let mut payloads = qr.payloads().to_vec();
+        payloads.reverse();
+        assert_eq!(MultiQrCode::reassemble_manifest(&payloads).unwrap(), (manifest, LIPSUM.as_bytes().to_vec()));
+        assert_eq!(MultiQrCode::reassemble_manifest(&qr.payloads()[1..]), Err(DecodeError::MissingChunk(0)));
+        assert_eq!(MultiQrCode::reassemble_manifest(&qr.payloads()[..2]), Err(DecodeError::MissingChunk(2)));
+
+        assert!(MultiQrCode::builder().manifest(true).header(HeaderFormat::Extended(IndexWidth::U8)).build(LIPSUM).is_err());
+    }
+
+    #[test]
+    fn data_set_bytes() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(5), EcLevel::M).unwrap();
+        let set = qr.to_data_set().unwrap();
+        let bytes = set.to_bytes();
+        assert_eq!(bytes.len(), 6 + (qr.codes.len() * 37 * 37).div_ceil(8));
+        assert_eq!(QrDataSet::from_bytes(&bytes).unwrap(), set);
+        for (colors, code) in set.codes.iter().zip(&qr.codes) {
+            assert_eq!(colors, &code.to_colors());
+        }
+        assert_eq!(QrDataSet::from_bytes(&bytes[..bytes.len() - 1]), Err(DecodeError::Malformed));
+        assert_eq!(QrDataSet::from_bytes(&[0, 21]), Err(DecodeError::Malformed));
+
+        #[cfg(feature = "render-text")]
+        {
+            let base64 = qr.to_base64();
+            assert_eq!(QrDataSet::from_base64(&base64).unwrap(), set);
+            assert!(set.to_base64().iter().zip(&base64).all(|(a, b)| matches!((a, b),
+                (QrData::Base64 { width: w1, data: d1 }, QrData::Base64 { width: w2, data: d2 }) if w1 == w2 && d1 == d2)));
+        }
+
+        let mixed = MultiQrCode::with_layout(LIPSUM, &[(Version::Normal(2), EcLevel::H), (Version::Normal(10), EcLevel::L)], HeaderFormat::Legacy).unwrap();
+        assert!(mixed.to_data_set().is_none());
+    }
+
+    #[test]
+    fn progress_reports() {
+        use std::sync::{Arc, Mutex};
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let qr = MultiQrCode::builder().version(Version::Normal(10)).progress(move |progress| {
+            sink.lock().unwrap().push(progress);
+            true
+        }).build(LIPSUM).unwrap();
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort_by_key(|progress| match progress {
+            Progress::Encoded { done, .. } | Progress::Rendered { done, .. } | Progress::Scanned { received: done, .. } => *done
+        });
+        let total = qr.len();
+        assert_eq!(seen, (1..=total).map(|done| Progress::Encoded { done, total }).collect::<Vec<_>>());
+
+        let cancelled = MultiQrCode::builder().version(Version::Normal(10)).progress(|progress| !matches!(progress, Progress::Encoded { done: 2, .. })).build(LIPSUM);
+        assert!(matches!(cancelled, Err(MqrError::Cancelled)));
+
+        #[cfg(feature = "render-image")]
+        {
+            use std::sync::atomic::{AtomicUsize, Ordering};
+            let rendered = Arc::new(AtomicUsize::new(0));
+            let counter = rendered.clone();
+            let options = SaveOptions { progress: Some(Arc::new(move |progress| {
+                assert!(matches!(progress, Progress::Rendered { total: 3.., .. }));
+                counter.fetch_add(1, Ordering::Relaxed);
+                true
+            })), ..Default::default() };
+            assert_eq!(qr.to_images_with(&options).unwrap().len(), total);
+            assert_eq!(rendered.load(Ordering::Relaxed), total);
+
+            let stop = SaveOptions { progress: Some(Arc::new(|_| false)), ..Default::default() };
+            assert!(matches!(qr.to_sheet_with(3, 10, &stop), Err(MqrError::Cancelled)));
+        }
+    }
+
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    #[test]
+    fn manifest_json() {
+        let dir = std::env::temp_dir().join("mqrcode-manifest-json");
+        let _ = std::fs::remove_dir_all(&dir);
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::M).unwrap();
+        let options = SaveOptions { manifest_json: true, format: Some(ImageFormat::Bmp), ..Default::default() };
+        qr.save_to_dir(&dir, &options).unwrap();
+
+        let json = std::fs::read_to_string(dir.join("manifest.json")).unwrap();
+        assert!(json.starts_with(&format!("{{\n  \"total\": {},\n  \"version\": \"10\",\n  \"ec_level\": \"M\",", qr.len())));
+        let all = crc32fast::hash(&qr.payloads().concat());
+        assert!(json.contains(&format!("\"crc32\": \"{:08x}\",\n  \"codes\": [", all)));
+        for (i, payload) in qr.payloads().iter().enumerate() {
+            let entry = format!("{{\"file\": \"qr.{}.png\", \"version\": \"10\", \"ec_level\": \"M\", \"payload_size\": {}, \"crc32\": \"{:08x}\"}}",
+                i, payload.len(), crc32fast::hash(payload));
+            assert!(json.contains(&entry), "{}", entry);
+            assert!(dir.join(format!("qr.{}.png", i)).exists());
+        }
+        assert_eq!(json.matches("\"file\"").count(), qr.len());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        qr.save_to_dir(&dir, &SaveOptions { format: Some(ImageFormat::Bmp), ..Default::default() }).unwrap();
+        assert!(!dir.join("manifest.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    #[test]
+    fn safe_save() {
+        let dir = std::env::temp_dir().join("mqrcode-safe-save");
+        let _ = std::fs::remove_dir_all(&dir);
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::M).unwrap();
+        let path = dir.join("backup").join("qr.bmp");
+        let options = SaveOptions { overwrite: Overwrite::Error, ..Default::default() };
+        assert!(matches!(qr.save_with(&path, &options), Err(MqrError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound));
+
+        let options = SaveOptions { create_dirs: true, ..options };
+        let written = qr.save_with(&path, &options).unwrap();
+        assert_eq!(written.len(), qr.len());
+        assert!(written.iter().all(|file| file.starts_with(dir.join("backup")) && file.exists()));
+
+        // nothing is touched when any file is in the way
+        std::fs::remove_file(&written[1]).unwrap();
+        assert!(matches!(qr.save_with(&path, &options), Err(MqrError::Io(e)) if e.kind() == std::io::ErrorKind::AlreadyExists));
+        assert!(!written[1].exists());
+
+        let skip = SaveOptions { overwrite: Overwrite::Skip, ..options };
+        assert_eq!(qr.save_with(&path, &skip).unwrap(), [written[1].clone()]);
+        assert_eq!(qr.save_with(&path, &SaveOptions::default()).unwrap(), written);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(all(feature = "rqrr", feature = "fs", feature = "render-image"))]
+    #[test]
+    fn decode_dir() {
+        let dir = std::env::temp_dir().join("mqrcode-decode-dir");
+        let _ = std::fs::remove_dir_all(&dir);
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        qr.save_to_dir(&dir, &SaveOptions { format: Some(ImageFormat::Bmp), file_name: "{stem}.{index}.bmp".to_string(), ..Default::default() }).unwrap();
+        std::fs::write(dir.join("notes.txt"), "not an image").unwrap();
+        std::fs::write(dir.join("broken.png"), "not a png either").unwrap();
+
+        let decoded = MultiQrCode::decode_dir(&dir).unwrap();
+        assert_eq!(decoded.sets, vec![Ok(LIPSUM.as_bytes().to_vec())]);
+        assert_eq!(decoded.failures.len(), 1);
+        assert_eq!(decoded.failures[0].0, dir.join("broken.png"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(all(feature = "rqrr", feature = "render-image"))]
+    #[test]
+    fn live_decoder() {
+        use std::sync::{Arc, Mutex};
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let mut empty = || Ok(None);
+        assert!(matches!(LiveDecoder::new(format).run(&mut empty), Err(MqrError::Decode(DecodeError::MissingChunk(0)))));
+
+        let qr = MultiQrCode::with_header(LIPSUM, Version::Normal(10), EcLevel::L, format).unwrap();
+        // every frame twice, like a camera lingering on each code
+        let mut frames = qr.to_images().into_iter().flat_map(|image| [image.clone(), image]);
+        let mut source = || Ok(frames.next());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let mut decoder = LiveDecoder::new(format).progress(move |progress| {
+            sink.lock().unwrap().push(progress);
+            true
+        });
+        assert_eq!(decoder.run(&mut source).unwrap(), LIPSUM.as_bytes());
+        let total = qr.len();
+        let expected = (0..total).map(|index| Progress::Scanned { index, received: index + 1, total: Some(total) }).collect::<Vec<_>>();
+        assert_eq!(*seen.lock().unwrap(), expected);
+
+        // a batch scanned at once, in reverse so the set is only complete at the last frame
+        let images: Vec<_> = qr.to_images().into_iter().rev().collect();
+        let mut batched = LiveDecoder::new(format);
+        assert_eq!(batched.feed_all(&images[..1]).unwrap(), None);
+        assert_eq!(batched.feed_all(&images).unwrap().unwrap(), LIPSUM.as_bytes());
+    }
+
+    #[cfg(all(feature = "video", feature = "render-image"))]
+    #[test]
+    fn decode_gif() {
+        let path = std::env::temp_dir().join("mqrcode-decode-video.gif");
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let qr = MultiQrCode::with_header(LIPSUM, Version::Normal(10), EcLevel::L, format).unwrap();
+        qr.save_animation(&path, 2, true).unwrap();
+        assert_eq!(MultiQrCode::decode_video(&path, format).unwrap(), LIPSUM.as_bytes());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn mixed_sets() {
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let first = MultiQrCode::with_header(LIPSUM, Version::Normal(10), EcLevel::L, format).unwrap();
+        let second = MultiQrCode::with_header(&LIPSUM[..1500], Version::Normal(10), EcLevel::L, format).unwrap();
+        let mut payloads: Vec<&[u8]> = Vec::new();
+        for i in 0..first.len().max(second.len()) {
+            payloads.extend(second.payloads().get(i).map(Vec::as_slice));
+            payloads.extend(first.payloads().get(i).map(Vec::as_slice));
+        }
+        payloads.insert(3, &[]);
+        assert_eq!(decode::reassemble_sets(&payloads, format), vec![
+            Ok(LIPSUM.as_bytes()[..1500].to_vec()),
+            Ok(LIPSUM.as_bytes().to_vec()),
+            Err(DecodeError::EmptyChunk)
+        ]);
+        let sets = decode::reassemble_sets(&payloads[1..], format);
+        assert_eq!(sets[0], Ok(LIPSUM.as_bytes().to_vec()));
+        assert_eq!(sets[1], Err(DecodeError::MissingChunk(0)));
+    }
+
+    #[test]
+    fn conflicting_chunks() {
+        let first = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        let second = MultiQrCode::new(&LIPSUM[100..], Version::Normal(10), EcLevel::L).unwrap();
+        let mut payloads = first.payloads().to_vec();
+        payloads.insert(2, payloads[1].clone());
+        assert_eq!(MultiQrCode::reassemble(&payloads).unwrap(), LIPSUM.as_bytes());
+
+        payloads.push(second.payloads()[1].clone());
+        let conflict = DecodeError::ConflictingChunk { index: 1, first: first.payloads()[1][1..].to_vec(), second: second.payloads()[1][1..].to_vec() };
+        assert_eq!(MultiQrCode::reassemble(&payloads), Err(conflict.clone()));
+
+        let mut session = DecodeSession::new();
+        for payload in first.payloads() {
+            session.push(payload).unwrap();
+        }
+        assert_eq!(session.push(&second.payloads()[1]), Err(conflict));
+        assert_eq!(session.finish().unwrap(), LIPSUM.as_bytes());
+    }
+
+    #[test]
+    fn collection_traits() {
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::L).unwrap();
+        assert_eq!(qr.len(), qr.codes.len());
+        assert!(!qr.is_empty());
+        assert_eq!(qr[1].to_colors(), qr.codes[1].to_colors());
+        let mut count = 0;
+        for code in &qr {
+            assert_eq!(code.width(), qr.codes[count].width());
+            count += 1;
+        }
+        assert_eq!(count, qr.len());
+        assert_eq!(qr.iter().count(), qr.len());
+        assert!(MultiQrCode::new([], Version::Normal(10), EcLevel::L).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn stored_codes() {
+        use store::StoredSet;
+        let sets = [
+            MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::M).unwrap(),
+            MultiQrCode::new([0xff; 40], Version::Micro(4), EcLevel::L).unwrap(),
+            MultiQrCode::builder().version(Version::Normal(20)).optimize_segments(true).build(LIPSUM).unwrap(),
+            MultiQrCode::builder().utf8_eci(true).boost_ec(true).build(LIPSUM).unwrap(),
+            MultiQrCode::from_str_chunked("héllo wörld", Version::Normal(1), EcLevel::H).unwrap()
+        ];
+        for qr in sets {
+            let reloaded = MultiQrCode::try_from(StoredSet::from(&qr)).unwrap();
+            assert_eq!(reloaded.payloads(), qr.payloads());
+            for (a, b) in reloaded.codes.iter().zip(&qr.codes) {
+                assert_eq!((a.version(), a.error_correction_level()), (b.version(), b.error_correction_level()));
+                assert_eq!(a.to_colors(), b.to_colors());
+            }
+        }
+
+        let qr = MultiQrCode::new(LIPSUM, Version::Normal(10), EcLevel::M).unwrap();
+        let mut stored = StoredSet::from(&qr);
+        stored.codes[0].modules[0] ^= 1;
+        assert!(MultiQrCode::try_from(stored).is_err());
+
+        // the widest headers with a chunk crc in front of optimized segments
+        let formats = [HeaderFormat::Legacy, HeaderFormat::Compact, HeaderFormat::Versioned(WireFormat::V1),
+            HeaderFormat::Extended(IndexWidth::U8), HeaderFormat::Extended(IndexWidth::U16), HeaderFormat::Extended(IndexWidth::U32)];
+        for format in formats {
+            let chunk_crc = format.has_flags();
+            let qr = MultiQrCode::builder().version(Version::Normal(10)).header(format).chunk_crc(chunk_crc).optimize_segments(true).utf8_eci(true).build(LIPSUM).unwrap();
+            let json = serde_json::to_string(&qr).unwrap();
+            let reloaded: MultiQrCode = serde_json::from_str(&json).unwrap();
+            assert_eq!(reloaded.payloads(), qr.payloads());
+            assert!(reloaded.codes.iter().zip(&qr.codes).all(|(a, b)| a.to_colors() == b.to_colors()));
+            assert_eq!(MultiQrCode::reassemble_with(reloaded.payloads(), format).unwrap(), LIPSUM.as_bytes());
+        }
+    }
+
+    #[test]
+    fn boost_ec() {
+        let data = &LIPSUM.as_bytes()[..850];
+        let options = EncodeOptions { boost_ec: true, ..Default::default() };
+        let qr = MultiQrCode::with_options(data, Version::Normal(10), EcLevel::L, &options).unwrap();
+        let (last, full) = qr.codes.split_last().unwrap();
+        assert!(full.iter().all(|code| code.error_correction_level() == EcLevel::L && code.version() == Version::Normal(10)));
+        assert_eq!(last.error_correction_level(), EcLevel::H);
+        assert_eq!(last.version(), Version::Normal(10));
+        assert_eq!(MultiQrCode::reassemble(qr.payloads()).unwrap(), data);
+
+        let plain = MultiQrCode::new(data, Version::Normal(10), EcLevel::L).unwrap();
+        assert_eq!(plain.codes.last().unwrap().error_correction_level(), EcLevel::L);
+    }
+
+    #[test]
+    fn chunk_layout() {
+        let layout = [(Version::Normal(2), EcLevel::H), (Version::Normal(10), EcLevel::L)];
+        let format = HeaderFormat::Extended(IndexWidth::U8);
+        let qr = MultiQrCode::with_layout(LIPSUM, &layout, format).unwrap();
+        assert_eq!((qr.codes[0].version(), qr.codes[0].error_correction_level()), layout[0]);
+        assert!(qr.codes[1..].iter().all(|code| (code.version(), code.error_correction_level()) == layout[1]));
+        // version 2 at H holds 16 bytes, less 7 for the header and 2 of slack
+        assert_eq!(qr.payloads()[0].len(), 7 + 7);
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), format).unwrap(), LIPSUM.as_bytes());
+        assert!(MultiQrCode::with_layout(LIPSUM, &[], format).is_err());
+    }
+
+    #[test]
+    fn micro_codes() {
+        let data = &LIPSUM.as_bytes()[..100];
+        for (version, ec) in [(Version::Micro(3), EcLevel::L), (Version::Micro(3), EcLevel::M), (Version::Micro(4), EcLevel::Q)] {
+            let qr = MultiQrCode::new(data, version, ec).unwrap();
+            assert!(qr.codes.iter().all(|code| code.version() == version));
+            assert_eq!(MultiQrCode::reassemble(qr.payloads()).unwrap(), data);
+        }
+
+        // 14 bytes of data per M4-L code, so 8 codes fit the 16 the compact header allows
+        let qr = MultiQrCode::with_header(data, Version::Micro(4), EcLevel::L, HeaderFormat::Compact).unwrap();
+        assert_eq!(qr.codes.len(), 8);
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), HeaderFormat::Compact).unwrap(), data);
+        assert!(matches!(MultiQrCode::reassemble_with(&qr.payloads()[1..], HeaderFormat::Compact), Err(DecodeError::MissingChunk(0))));
+        assert!(MultiQrCode::with_header(LIPSUM, Version::Micro(4), EcLevel::L, HeaderFormat::Compact).is_err());
+
+        assert!(matches!(MultiQrCode::new(data, Version::Micro(2), EcLevel::L), Err(QrError::UnsupportedCharacterSet)));
+        assert!(matches!(MultiQrCode::new(data, Version::Micro(4), EcLevel::H), Err(QrError::DataTooLong)));
+
+        // every byte value, so no chunk gets a denser segment than byte mode and the slack of
+        // every micro version and ec level is exercised on full chunks
+        let bytes: Vec<u8> = (0..=255).collect();
+        for (version, ec) in [(3, EcLevel::L), (3, EcLevel::M), (4, EcLevel::L), (4, EcLevel::M), (4, EcLevel::Q)] {
+            let qr = MultiQrCode::new(&bytes, Version::Micro(version), ec).unwrap();
+            assert_eq!(MultiQrCode::reassemble(qr.payloads()).unwrap(), bytes);
+            let qr = MultiQrCode::with_header(&bytes[200..], Version::Micro(version), ec, HeaderFormat::Compact).unwrap();
+            assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), HeaderFormat::Compact).unwrap(), &bytes[200..]);
+        }
+    }
+
+    #[test]
+    fn too_many_chunks() {
+        // version 1 at EC H holds 9 bytes, leaving 6 for data with the legacy header
+        let data = vec![0u8; 6 * 257];
+        assert!(matches!(MultiQrCode::new(&data, Version::Normal(1), EcLevel::H), Err(QrError::DataTooLong)));
+
+        let format = HeaderFormat::Extended(IndexWidth::U16);
+        let qr = MultiQrCode::with_header(&data, Version::Normal(5), EcLevel::H, format).unwrap();
+        assert_eq!(MultiQrCode::reassemble_with(qr.payloads(), format).unwrap(), data);
+    }
+}
+
@@ -0,0 +1,151 @@
+// decoding from a camera or any other stream of frames. the app only grabs frames, scanning,
+// skipping repeats and misreads and knowing when the set is complete is done here. without
+// rqrr the app scans the frames itself and pushes the payloads
+use std::{fmt, sync::Arc};
+#[cfg(feature = "rqrr")]
+use image::GrayImage;
+use crate::{DecodeError, DecodeSession, HeaderFormat, MqrError, Progress, ProgressSink};
+#[cfg(feature = "rqrr")]
+use crate::decode;
+
+// a stream of grayscale frames, next_frame returns None once the stream has ended
+#[cfg(feature = "rqrr")]
+pub trait FrameSource {
+    fn next_frame(&mut self) -> Result<Option<GrayImage>, MqrError>;
+}
+
+#[cfg(feature = "rqrr")]
+impl<F: FnMut() -> Result<Option<GrayImage>, MqrError>> FrameSource for F {
+    fn next_frame(&mut self) -> Result<Option<GrayImage>, MqrError> {
+        self()
+    }
+}
+
+#[derive(Clone)]
+pub struct LiveDecoder {
+    format: HeaderFormat,
+    session: DecodeSession,
+    progress: Option<Arc<dyn ProgressSink>>,
+    duplicates: usize,
+    rejected: usize
+}
+
+impl fmt::Debug for LiveDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LiveDecoder")
+            .field("format", &self.format)
+            .field("session", &self.session)
+            .field("duplicates", &self.duplicates)
+            .field("rejected", &self.rejected)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LiveDecoder {
+    pub fn new(format: HeaderFormat) -> Self {
+        LiveDecoder { format, session: DecodeSession::with_header(format), progress: None, duplicates: 0, rejected: 0 }
+    }
+
+    // gets a Progress::Scanned for every new chunk, returning false stops with MqrError::Cancelled
+    pub fn progress<S: ProgressSink + 'static>(mut self, sink: S) -> Self {
+        self.progress = Some(Arc::new(sink));
+        self
+    }
+
+    // what was received so far, e.g. to show the missing chunks
+    pub fn session(&self) -> &DecodeSession {
+        &self.session
+    }
+
+    // payloads seen again after they were first received
+    pub fn duplicates(&self) -> usize {
+        self.duplicates
+    }
+
+    // payloads that didn't fit the set, like misreads or a code from another set in view
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+
+    // adds one scanned payload, returning the data once the set is complete. repeats and
+    // payloads that aren't part of the set are counted and skipped, only a complete set that
+    // fails to reassemble, e.g. on a checksum mismatch, is an error
+    pub fn push(&mut self, payload: &[u8]) -> Result<Option<Vec<u8>>, MqrError> {
+        let Ok((header, _)) = self.format.read(payload) else {
+            self.rejected += 1;
+            return Ok(None)
+        };
+        let received = self.session.received().len();
+        let was_complete = self.session.is_complete();
+        let done = match self.session.push(payload) {
+            Ok(done) => done,
+            Err(e) if !was_complete && self.session.is_complete() => return Err(e.into()),
+            Err(_) => {
+                self.rejected += 1;
+                return Ok(None)
+            }
+        };
+        // encrypted sets need a key, which LiveDecoder doesn't take
+        if !was_complete && self.session.is_complete() && self.session.is_encrypted() {
+            return Err(DecodeError::Encrypted.into())
+        }
+        let total = self.session.received().len();
+        if total == received {
+            self.duplicates += 1;
+        } else if let Some(sink) = self.progress.as_deref() {
+            if !sink.report(Progress::Scanned { index: header.index, received: total, total: self.session.total() }) {
+                return Err(MqrError::Cancelled)
+            }
+        }
+        Ok(done)
+    }
+
+    // scans one frame, returning the data once the set is complete
+    #[cfg(feature = "rqrr")]
+    pub fn feed(&mut self, frame: &GrayImage) -> Result<Option<Vec<u8>>, MqrError> {
+        self.push_all(decode::scan(frame))
+    }
+
+    // feed for several frames at once, scanned on all cores with the parallel feature. the
+    // payloads are pushed in frame order, so the outcome matches feeding the frames one by one
+    #[cfg(feature = "rqrr")]
+    pub fn feed_all(&mut self, frames: &[GrayImage]) -> Result<Option<Vec<u8>>, MqrError> {
+        for payloads in decode::map_items(frames, decode::scan) {
+            if let Some(data) = self.push_all(payloads)? {
+                return Ok(Some(data))
+            }
+        }
+        Ok(None)
+    }
+
+    #[cfg(feature = "rqrr")]
+    fn push_all(&mut self, payloads: Vec<Vec<u8>>) -> Result<Option<Vec<u8>>, MqrError> {
+        for payload in payloads {
+            if let Some(data) = self.push(&payload)? {
+                return Ok(Some(data))
+            }
+        }
+        Ok(None)
+    }
+
+    // pulls frames until the set is complete or the source runs out. with the legacy header,
+    // which doesn't know the total, the whole stream is read
+    #[cfg(feature = "rqrr")]
+    pub fn run<S: FrameSource + ?Sized>(&mut self, source: &mut S) -> Result<Vec<u8>, MqrError> {
+        while let Some(frame) = source.next_frame()? {
+            if let Some(data) = self.feed(&frame)? {
+                return Ok(data)
+            }
+        }
+        self.finish()
+    }
+
+    // the data from what was received, for when the stream ended before the set was complete
+    pub fn finish(&self) -> Result<Vec<u8>, MqrError> {
+        // no code at all would otherwise reassemble to empty data
+        if self.session.received().is_empty() {
+            return Err(DecodeError::MissingChunk(0).into())
+        }
+        Ok(self.session.finish()?)
+    }
+}
@@ -0,0 +1,199 @@
+use std::{collections::BTreeMap, fmt};
+use qrcode::types::QrError;
+use crate::{DecodeError, HeaderFormat, decode, ur::{cbor_head, cbor_read_head}};
+
+// with a manifest, every code starts with a single index byte like HeaderFormat::Legacy.
+// index 0 is the manifest, a CBOR map describing the whole set, and indices 1..=total hold
+// the data, so a scanner can show what is coming from the first code alone
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Manifest {
+    // data codes besides the manifest
+    pub total: usize,
+    // bytes of the original data
+    pub length: usize,
+    // crc32 of the data as it was chunked, after compression and encryption
+    pub crc32: u32,
+    // the header flags the data would otherwise carry, for compression, encryption and such
+    pub flags: u8,
+    // sha-256 of the original data, when digests are on
+    pub sha256: Option<[u8; 32]>,
+    pub filename: Option<String>
+}
+
+pub(crate) const MAJOR_UINT: u8 = 0;
+const MAJOR_BYTES: u8 = 2;
+pub(crate) const MAJOR_TEXT: u8 = 3;
+pub(crate) const MAJOR_MAP: u8 = 5;
+
+impl Manifest {
+    // the manifest code's payload, index byte included
+    pub fn to_payload(&self) -> Vec<u8> {
+        let mut entries: Vec<(&str, Value)> = vec![
+            ("total", Value::Uint(self.total as u64)),
+            ("length", Value::Uint(self.length as u64)),
+            ("crc32", Value::Uint(self.crc32 as u64)),
+            ("flags", Value::Uint(self.flags as u64))
+        ];
+        if let Some(digest) = &self.sha256 {
+            entries.push(("sha256", Value::Bytes(digest)));
+        }
+        if let Some(filename) = &self.filename {
+            entries.push(("filename", Value::Text(filename)));
+        }
+
+        let mut payload = vec![0];
+        write_map(&mut payload, &entries);
+        payload
+    }
+
+    // parses a scanned payload, Malformed if it isn't a manifest code. unknown keys are skipped
+    pub fn read(payload: &[u8]) -> Result<Self, DecodeError> {
+        let (&index, bytes) = payload.split_first().ok_or(DecodeError::EmptyChunk)?;
+        if index != 0 {
+            return Err(DecodeError::Malformed)
+        }
+        let mut pos = 0;
+        let (major, entries) = cbor_read_head(bytes, &mut pos)?;
+        if major != MAJOR_MAP {
+            return Err(DecodeError::Malformed)
+        }
+
+        let mut manifest = Manifest::default();
+        let mut seen = 0;
+        for _ in 0..entries {
+            let key = read_string(bytes, &mut pos, MAJOR_TEXT)?;
+            let uint = |pos: &mut usize| match cbor_read_head(bytes, pos)? {
+                (MAJOR_UINT, n) => Ok(n),
+                _ => Err(DecodeError::Malformed)
+            };
+            match key {
+                b"total" => manifest.total = usize::try_from(uint(&mut pos)?).map_err(|_| DecodeError::Malformed)?,
+                b"length" => manifest.length = usize::try_from(uint(&mut pos)?).map_err(|_| DecodeError::Malformed)?,
+                b"crc32" => manifest.crc32 = u32::try_from(uint(&mut pos)?).map_err(|_| DecodeError::Malformed)?,
+                b"flags" => manifest.flags = u8::try_from(uint(&mut pos)?).map_err(|_| DecodeError::Malformed)?,
+                b"sha256" => manifest.sha256 = Some(read_string(bytes, &mut pos, MAJOR_BYTES)?.try_into().map_err(|_| DecodeError::Malformed)?),
+                b"filename" => manifest.filename = Some(String::from_utf8(read_string(bytes, &mut pos, MAJOR_TEXT)?.to_vec()).map_err(|_| DecodeError::Malformed)?),
+                _ => {
+                    skip(bytes, &mut pos)?;
+                    continue
+                }
+            }
+            seen += 1;
+        }
+        // total, length, crc32 and flags are always written
+        if seen < 4 {
+            return Err(DecodeError::Malformed)
+        }
+        Ok(manifest)
+    }
+}
+
+// e.g. "expecting 12 codes, 980 KB, file report.pdf"
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expecting {} code{}, ", self.total, if self.total == 1 { "" } else { "s" })?;
+        match self.length {
+            0..=999 => write!(f, "{} bytes", self.length)?,
+            1000..=999_999 => write!(f, "{} KB", self.length / 1000)?,
+            _ => write!(f, "{:.1} MB", self.length as f64 / 1e6)?
+        }
+        if let Some(filename) = &self.filename {
+            write!(f, ", file {}", filename)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) enum Value<'a> {
+    Uint(u64),
+    Bytes(&'a [u8]),
+    Text(&'a str)
+}
+
+// a CBOR map with text keys
+pub(crate) fn write_map(buf: &mut Vec<u8>, entries: &[(&str, Value)]) {
+    cbor_head(buf, MAJOR_MAP, entries.len() as u64);
+    for (key, value) in entries {
+        cbor_head(buf, MAJOR_TEXT, key.len() as u64);
+        buf.extend_from_slice(key.as_bytes());
+        match *value {
+            Value::Uint(n) => cbor_head(buf, MAJOR_UINT, n),
+            Value::Bytes(bytes) => {
+                cbor_head(buf, MAJOR_BYTES, bytes.len() as u64);
+                buf.extend_from_slice(bytes);
+            }
+            Value::Text(text) => {
+                cbor_head(buf, MAJOR_TEXT, text.len() as u64);
+                buf.extend_from_slice(text.as_bytes());
+            }
+        }
+    }
+}
+
+pub(crate) fn read_string<'a>(bytes: &'a [u8], pos: &mut usize, expected: u8) -> Result<&'a [u8], DecodeError> {
+    let (major, len) = cbor_read_head(bytes, pos)?;
+    if major != expected {
+        return Err(DecodeError::Malformed)
+    }
+    let end = usize::try_from(len).ok().and_then(|len| pos.checked_add(len)).ok_or(DecodeError::Malformed)?;
+    let string = bytes.get(*pos..end).ok_or(DecodeError::Malformed)?;
+    *pos = end;
+    Ok(string)
+}
+
+// steps over a value written by a newer version, only integers and strings are expected
+pub(crate) fn skip(bytes: &[u8], pos: &mut usize) -> Result<(), DecodeError> {
+    let start = *pos;
+    match cbor_read_head(bytes, pos)? {
+        (0 | 1, _) => Ok(()),
+        (major @ (MAJOR_BYTES | MAJOR_TEXT), _) => {
+            *pos = start;
+            read_string(bytes, pos, major).map(|_| ())
+        }
+        _ => Err(DecodeError::Malformed)
+    }
+}
+
+// the manifest code followed by every chunk with its index byte
+pub(crate) fn with_manifest(manifest: &Manifest, chunks: &[&[u8]]) -> Result<Vec<Vec<u8>>, QrError> {
+    if chunks.len() + 1 > HeaderFormat::Legacy.max_chunks() {
+        return Err(QrError::DataTooLong)
+    }
+    let mut payloads = vec![manifest.to_payload()];
+    payloads.extend(chunks.iter().enumerate().map(|(i, chunk)| {
+        let mut payload = Vec::with_capacity(1 + chunk.len());
+        HeaderFormat::Legacy.write(&mut payload, i + 1, 0, 0, 0);
+        payload.extend_from_slice(chunk);
+        payload
+    }));
+    Ok(payloads)
+}
+
+// the manifest and the data as it was chunked, checked against the manifest crc32
+pub(crate) fn join<I, P>(payloads: I) -> Result<(Manifest, Vec<u8>), DecodeError>
+where I: IntoIterator<Item = P>, P: AsRef<[u8]> {
+    let mut manifest = None;
+    let mut chunks: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+    for payload in payloads {
+        let payload = payload.as_ref();
+        let (header, data) = HeaderFormat::Legacy.read(payload)?;
+        match header.index {
+            0 if manifest.is_none() => manifest = Some(Manifest::read(payload)?),
+            0 => {}
+            index => decode::insert_chunk(&mut chunks, index, data)?
+        }
+    }
+    let manifest = manifest.ok_or(DecodeError::MissingChunk(0))?;
+    if chunks.keys().any(|&i| i > manifest.total) {
+        return Err(DecodeError::InconsistentHeader)
+    }
+
+    let mut res = Vec::new();
+    for i in 1..=manifest.total {
+        res.extend(chunks.remove(&i).ok_or(DecodeError::MissingChunk(i))?);
+    }
+    if crc32fast::hash(&res) != manifest.crc32 {
+        return Err(DecodeError::ChecksumMismatch)
+    }
+    Ok((manifest, res))
+}
@@ -0,0 +1,60 @@
+// the modules of a code for drawing it with anything, no quiet zone included. stored 8 to a
+// byte row after row with the first module in the high bit, the packing of QrData::Raw
+use qrcode::{Color, QrCode};
+use crate::dataset::pack;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitMatrix {
+    width: usize,
+    bits: Vec<u8>
+}
+
+impl BitMatrix {
+    pub fn from_code(code: &QrCode) -> Self {
+        BitMatrix { width: code.width(), bits: pack(&code.to_colors()) }
+    }
+
+    // a symbol of another symbology, dark where f(x, y) is true
+    #[cfg(any(feature = "datamatrix", feature = "aztec"))]
+    pub(crate) fn from_fn<F: Fn(usize, usize) -> bool>(width: usize, f: F) -> Self {
+        let colors: Vec<Color> = (0..width * width).map(|i| if f(i % width, i / width) { Color::Dark } else { Color::Light }).collect();
+        BitMatrix { width, bits: pack(&colors) }
+    }
+
+    // modules per side
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // whether the module at column x of row y is dark, false outside the code
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.width {
+            return false
+        }
+        let i = y * self.width + x;
+        self.bits[i / 8] & (0x80 >> (i % 8)) != 0
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = Vec<bool>> + '_ {
+        (0..self.width).map(move |y| (0..self.width).map(|x| self.get(x, y)).collect())
+    }
+
+    pub fn to_vec(&self) -> Vec<Vec<bool>> {
+        self.rows().collect()
+    }
+
+    pub fn to_colors(&self) -> Vec<Color> {
+        self.rows().flatten().map(|dark| if dark { Color::Dark } else { Color::Light }).collect()
+    }
+
+    // the packed bytes, the last one padded with light modules
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bits
+    }
+}
+
+impl From<&QrCode> for BitMatrix {
+    fn from(code: &QrCode) -> Self {
+        Self::from_code(code)
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(all(feature = "fs", feature = "render-image"))]
+use crate::MqrError;
+
+// the naming save() has always used, e.g. backup.0.png
+pub const DEFAULT_TEMPLATE: &str = "{stem}.{index}.{ext}";
+
+// the caption SaveOptions::captioned draws under every code, e.g. "3 / 12"
+pub const DEFAULT_CAPTION: &str = "{number} / {total}";
+
+// expands a caption template, which knows the {index}, {number} and {total} placeholders of
+// file names but not their widths
+pub(crate) fn caption(template: &str, index: usize, total: usize) -> String {
+    template.replace("{index}", &index.to_string()).replace("{number}", &(index + 1).to_string()).replace("{total}", &total.to_string())
+}
+
+// expands a file name template. placeholders are {stem}, {ext}, {total}, {index} (from 0)
+// and {number} (from 1), the last two take a zero padded width like {index:03}
+#[cfg(all(feature = "fs", feature = "render-image"))]
+pub(crate) fn file_name(template: &str, stem: &str, ext: &str, index: usize, total: usize) -> Result<String, MqrError> {
+    let mut res = String::with_capacity(template.len() + stem.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        res.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or(MqrError::InvalidOptions("unclosed placeholder in file name template"))? + start;
+        let (name, width) = match rest[start + 1..end].split_once(':') {
+            Some((name, width)) => (name, Some(width)),
+            None => (&rest[start + 1..end], None)
+        };
+        let value = match name {
+            "stem" => stem.to_string(),
+            "ext" => ext.to_string(),
+            "total" => total.to_string(),
+            "index" => index.to_string(),
+            "number" => (index + 1).to_string(),
+            _ => return Err(MqrError::InvalidOptions("unknown placeholder in file name template"))
+        };
+        match width {
+            None => res.push_str(&value),
+            Some(width) => {
+                let width: usize = width.parse().map_err(|_| MqrError::InvalidOptions("invalid width in file name template"))?;
+                res.push_str(&format!("{:0>width$}", value, width = width));
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    res.push_str(rest);
+    Ok(res)
+}
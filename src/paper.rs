@@ -0,0 +1,161 @@
+// a printable cold storage backup: a cover page with the title, date, a hash of the data and
+// how to restore it, followed by the labelled codes
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::{HeaderFormat, IndexWidth, MultiQrCode, MqrError, PageOptions, WireFormat, compression, crypto, decode, digest, header, parity, pdf, stripe, stats::SetInfo};
+
+const TITLE_SIZE: f32 = 20.0;
+const TEXT_SIZE: f32 = 11.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupOptions {
+    pub title: String,
+    // printed as is, None uses today's date in UTC as YYYY-MM-DD
+    pub date: Option<String>,
+    // the header the set was encoded with, for the hash and the restore instructions
+    pub header: HeaderFormat,
+    pub page: PageOptions
+}
+
+impl Default for BackupOptions {
+    fn default() -> Self {
+        BackupOptions { title: "Paper backup".to_string(), date: None, header: HeaderFormat::Legacy, page: PageOptions::default() }
+    }
+}
+
+pub(crate) fn render(qr: &MultiQrCode, options: &BackupOptions) -> Result<Vec<u8>, MqrError> {
+    if options.page.per_row == 0 {
+        return Err(MqrError::InvalidOptions("per_row must be at least 1"))
+    }
+    let mut pages = vec![pdf::text_page(&cover(qr, options), &options.page)];
+    pages.extend(pdf::code_pages(&qr.codes, &options.page));
+    let (width, height) = options.page.size.dimensions();
+    Ok(pdf::write_document(&pages, width, height))
+}
+
+fn cover(qr: &MultiQrCode, options: &BackupOptions) -> Vec<(f32, String)> {
+    let date = options.date.clone().unwrap_or_else(today);
+    let set = SetInfo::of(qr, options.header);
+    let needed = if set.flags & parity::FLAG_PARITY != 0 {
+        format!("any {} of the {} codes are enough", set.total, qr.len())
+    } else {
+        "every one of them is needed".to_string()
+    };
+    let mut lines = vec![
+        (TITLE_SIZE, options.title.clone()),
+        (TEXT_SIZE, String::new()),
+        (TEXT_SIZE, format!("Created: {}", date)),
+        (TEXT_SIZE, format!("Codes: {}", qr.len())),
+        (TEXT_SIZE, hash(qr, options.header, &set)),
+        (TEXT_SIZE, String::new()),
+        (TEXT_SIZE, "To restore:".to_string()),
+        (TEXT_SIZE, format!("1. Scan or photograph every code on the following pages. They can be read in any order, {}.", needed)),
+    ];
+    let encrypted = set.flags & (crypto::FLAG_AES_GCM | crypto::FLAG_PASSPHRASE) != 0;
+    match cli_header(options.header) {
+        // the tool has no way to take a key or passphrase
+        _ if encrypted => lines.push((TEXT_SIZE, "2. Decode the scanned payloads with the mqrcode library and the key or passphrase the data was encrypted with.".to_string())),
+        _ if set.manifest => lines.push((TEXT_SIZE, "2. Decode the images with the mqrcode tool: mqrcode decode <images...> --manifest --out <file>".to_string())),
+        Some(name) => lines.push((TEXT_SIZE, format!("2. Decode the images with the mqrcode tool: mqrcode decode <images...> --header {} --out <file>", name))),
+        None => lines.push((TEXT_SIZE, "2. Decode the scanned payloads with the mqrcode library, using the header format below.".to_string()))
+    }
+    lines.extend([
+        (TEXT_SIZE, "3. Check the restored data against the hash above.".to_string()),
+        (TEXT_SIZE, String::new()),
+        (TEXT_SIZE, "Without the tool: every code holds binary data, a header followed by a chunk of the data.".to_string())
+    ]);
+    lines.extend(layout(options.header, &set).into_iter().map(|line| (TEXT_SIZE, line)));
+    lines
+}
+
+// of the restored data, or of the payloads when the data needs a key to be read
+fn hash(qr: &MultiQrCode, format: HeaderFormat, set: &SetInfo) -> String {
+    let data = if set.manifest {
+        decode::reassemble_manifest(qr.payloads()).map(|(_, data)| data)
+    } else {
+        decode::reassemble_with(qr.payloads(), format)
+    };
+    let (what, bytes) = match data {
+        Ok(data) => ("data", data),
+        Err(_) => ("code payloads, in order", qr.payloads().concat())
+    };
+    #[cfg(feature = "sha256")]
+    let hash = format!("SHA-256 of the {}: {}", what, crate::digest::sha256(&bytes).iter().map(|b| format!("{:02x}", b)).collect::<String>());
+    #[cfg(not(feature = "sha256"))]
+    let hash = format!("CRC-32 of the {}: {:08x}", what, crc32fast::hash(&bytes));
+    hash
+}
+// the --header value of the command line tool
+fn cli_header(format: HeaderFormat) -> Option<&'static str> {
+    match format {
+        HeaderFormat::Legacy => Some("legacy"),
+        HeaderFormat::Compact => Some("compact"),
+        HeaderFormat::Extended(IndexWidth::U16) => Some("extended"),
+        HeaderFormat::Versioned(_) => Some("versioned"),
+        HeaderFormat::Extended(_) => None
+    }
+}
+
+// the header bytes, then how the chunks go back together and what was done to the data,
+// undone in the order given
+fn layout(format: HeaderFormat, set: &SetInfo) -> Vec<String> {
+    let mut lines = vec![if set.manifest {
+        "Header: 1 byte, the index of the code. Code 0 is a manifest instead of a chunk, a CBOR map with the number of chunks, the length, crc32 and flags of the data.".to_string()
+    } else {
+        format!("Header: {}", match format {
+            HeaderFormat::Legacy => "1 byte, the index of the chunk.".to_string(),
+            HeaderFormat::Compact => "1 byte, the index in the high 4 bits and the number of codes minus one in the low 4 bits.".to_string(),
+            HeaderFormat::Extended(width) => format!(
+                "{} bytes: index and number of data codes as big-endian {} byte integers, a flags byte and the crc32 of the joined chunks.",
+                format.size(), width.bytes()),
+            HeaderFormat::Versioned(WireFormat::V1) => format!(
+                "{} bytes: the version byte b1, index and number of data codes as big-endian 2 byte integers, a flags byte and the crc32 of the joined chunks.",
+                format.size()),
+            HeaderFormat::Versioned(wire) => format!("{} bytes, starting with the version byte b{:x}.", format.size(), wire.version())
+        })
+    }];
+    let flags = set.flags;
+    if format.has_flags() || set.manifest {
+        lines.push(format!("Flags: {:08b}.", flags));
+    }
+    if flags & header::FLAG_CHUNK_CRC != 0 {
+        lines.push("Every header is followed by 4 more bytes, the crc32 of the header and chunk, which are not part of the chunk.".to_string());
+    }
+    if flags & parity::FLAG_PARITY != 0 {
+        lines.push(format!("Codes from index {} on are Reed-Solomon parity codes, only needed to rebuild missing chunks. Leave them out when joining.", set.total));
+    }
+    if flags & stripe::FLAG_STRIPED != 0 {
+        lines.push(format!("The data is striped over the {} chunks: byte j of the data is byte j / {} of the chunk with index j mod {}.", set.total, set.total, set.total));
+    } else {
+        lines.push(format!("Sort the chunks by index from {} and join them.", if set.manifest { 1 } else { 0 }));
+    }
+    if flags & digest::FLAG_SHA256 != 0 {
+        lines.push("The joined data starts with the 32 byte SHA-256 digest of the original data.".to_string());
+    }
+    match flags & (crypto::FLAG_AES_GCM | crypto::FLAG_PASSPHRASE) {
+        0 => {}
+        crypto::FLAG_AES_GCM => lines.push("It is encrypted with AES-256-GCM: a 12 byte nonce, the ciphertext and a 16 byte tag.".to_string()),
+        _ => lines.push("It is encrypted with AES-256-GCM under a key derived from a passphrase with Argon2id: the memory, time and parallelism costs as big-endian 4 byte integers, a 16 byte salt, a 12 byte nonce, the ciphertext and a 16 byte tag.".to_string())
+    }
+    match flags & compression::FLAG_MASK {
+        0 => {}
+        1 => lines.push("It is compressed with raw deflate.".to_string()),
+        2 => lines.push("It is compressed with zstd.".to_string()),
+        _ => lines.push("It is text in Shift JIS.".to_string())
+    }
+    lines
+}
+
+// civil date from days since 1970-01-01, after Howard Hinnant's days_from_civil inverse
+fn today() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs() / 86400) as i64;
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
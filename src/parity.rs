@@ -0,0 +1,129 @@
+use std::{collections::BTreeMap, sync::OnceLock};
+use crate::DecodeError;
+
+// set when parity payloads follow the data chunks. their indices continue after the
+// header total, which only counts data chunks
+pub(crate) const FLAG_PARITY: u8 = 0b1000_0000;
+// every parity payload starts with the length of the chunked stream, u32 big endian,
+// so the padding of a rebuilt last chunk can be cut off again
+pub(crate) const PARITY_PREFIX_SIZE: usize = 4;
+// data and parity chunks each take one element of GF(256)
+pub const MAX_SHARDS: usize = 256;
+
+struct Tables {
+    exp: [u8; 512],
+    log: [u8; 256]
+}
+
+// GF(256) with the same 0x11d polynomial qr codes use internally
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tables = Tables { exp: [0; 512], log: [0; 256] };
+        let mut x: u16 = 1;
+        for i in 0..255 {
+            tables.exp[i] = x as u8;
+            tables.log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= 0x11d;
+            }
+        }
+        for i in 255..512 {
+            tables.exp[i] = tables.exp[i - 255];
+        }
+        tables
+    })
+}
+
+fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0
+    }
+    let tables = tables();
+    tables.exp[tables.log[a as usize] as usize + tables.log[b as usize] as usize]
+}
+
+// a must not be 0
+fn inv(a: u8) -> u8 {
+    let tables = tables();
+    tables.exp[255 - tables.log[a as usize] as usize]
+}
+
+// weight of data chunk i in parity chunk j. the weights form a cauchy matrix, so any n rows
+// of it stacked with the identity rows of the received data chunks can be inverted
+fn coefficient(n: usize, j: usize, i: usize) -> u8 {
+    inv(((n + j) ^ i) as u8)
+}
+
+// dst += c * src, shorter rows count as zero padded
+fn mul_add(dst: &mut [u8], c: u8, src: &[u8]) {
+    for (d, &s) in dst.iter_mut().zip(src) {
+        *d ^= mul(c, s);
+    }
+}
+
+// k reed-solomon parity chunks over the data chunks, each as long as the longest data chunk.
+// chunks.len() + k must not exceed MAX_SHARDS
+pub(crate) fn encode(chunks: &[&[u8]], k: usize) -> Vec<Vec<u8>> {
+    let n = chunks.len();
+    let block = chunks.iter().map(|chunk| chunk.len()).max().unwrap_or(0);
+    (0..k).map(|j| {
+        let mut parity = vec![0u8; block];
+        for (i, chunk) in chunks.iter().enumerate() {
+            mul_add(&mut parity, coefficient(n, j, i), chunk);
+        }
+        parity
+    }).collect()
+}
+
+// fills in the missing data chunks below n from parity chunks, keyed by parity number.
+// rebuilt chunks are padded to the parity length
+pub(crate) fn recover(data: &mut BTreeMap<usize, Vec<u8>>, parity: &BTreeMap<usize, Vec<u8>>, n: usize) -> Result<(), DecodeError> {
+    let missing: Vec<usize> = (0..n).filter(|i| !data.contains_key(i)).collect();
+    if missing.is_empty() {
+        return Ok(())
+    }
+    if parity.len() < missing.len() {
+        return Err(DecodeError::MissingChunk(missing[0]))
+    }
+    let block = parity.values().map(Vec::len).max().unwrap_or(0);
+
+    // every parity chunk used, minus what the received data chunks contributed,
+    // is one equation in the missing chunks
+    let rows: Vec<(usize, &Vec<u8>)> = parity.iter().take(missing.len()).map(|(&j, shard)| (j, shard)).collect();
+    let mut matrix: Vec<Vec<u8>> = rows.iter().map(|&(j, _)| missing.iter().map(|&i| coefficient(n, j, i)).collect()).collect();
+    let mut values: Vec<Vec<u8>> = rows.iter().map(|&(j, shard)| {
+        let mut value = shard.clone();
+        value.resize(block, 0);
+        for (&i, chunk) in data.range(..n) {
+            mul_add(&mut value, coefficient(n, j, i), chunk);
+        }
+        value
+    }).collect();
+
+    // gauss-jordan elimination, a cauchy submatrix always has a pivot
+    let size = missing.len();
+    for col in 0..size {
+        let pivot = (col..size).find(|&row| matrix[row][col] != 0).ok_or(DecodeError::Malformed)?;
+        matrix.swap(col, pivot);
+        values.swap(col, pivot);
+        let scale = inv(matrix[col][col]);
+        matrix[col].iter_mut().for_each(|v| *v = mul(*v, scale));
+        values[col].iter_mut().for_each(|v| *v = mul(*v, scale));
+
+        let (pivot_row, pivot_value) = (matrix[col].clone(), values[col].clone());
+        for row in (0..size).filter(|&row| row != col) {
+            let factor = matrix[row][col];
+            if factor != 0 {
+                mul_add(&mut matrix[row], factor, &pivot_row);
+                mul_add(&mut values[row], factor, &pivot_value);
+            }
+        }
+    }
+
+    for (i, value) in missing.into_iter().zip(values) {
+        data.insert(i, value);
+    }
+    Ok(())
+}
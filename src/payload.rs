@@ -0,0 +1,132 @@
+// a file's name, mime type and modification time carried in front of its bytes, so a scanned
+// set restores the file rather than an anonymous blob. the envelope is a marker and a CBOR map
+// like the manifest's, and goes through compression and encryption along with the data
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "fs")]
+use std::{fs::File, io, path::{Path, PathBuf}};
+use crate::DecodeError;
+use crate::manifest::{MAJOR_MAP, MAJOR_TEXT, MAJOR_UINT, Value, read_string, skip, write_map};
+use crate::ur::cbor_read_head;
+
+// "MQF" and the envelope version
+const MARKER: &[u8; 4] = b"MQF\x01";
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Payload {
+    pub name: Option<String>,
+    pub mime: Option<String>,
+    pub modified: Option<SystemTime>,
+    pub data: Vec<u8>
+}
+
+impl Payload {
+    pub fn bytes<N: Into<String>, M: Into<String>, D: Into<Vec<u8>>>(name: N, mime: M, data: D) -> Self {
+        Payload { name: Some(name.into()), mime: Some(mime.into()), modified: None, data: data.into() }
+    }
+
+    // the file's contents with its name, modification time and a mime type guessed from the extension
+    #[cfg(feature = "fs")]
+    pub fn file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let modified = file.metadata()?.modified().ok();
+        let mut data = Vec::new();
+        io::Read::read_to_end(&mut &file, &mut data)?;
+        Ok(Payload {
+            name: path.file_name().map(|name| name.to_string_lossy().into_owned()),
+            mime: Some(mime_type(path).to_string()),
+            modified,
+            data
+        })
+    }
+
+    // the envelope followed by the data, what gets chunked
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries = Vec::new();
+        if let Some(name) = &self.name {
+            entries.push(("name", Value::Text(name)));
+        }
+        if let Some(mime) = &self.mime {
+            entries.push(("mime", Value::Text(mime)));
+        }
+        // seconds since the epoch and the nanoseconds past them, earlier times aren't kept
+        if let Some(modified) = self.modified.and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+            entries.push(("modified", Value::Uint(modified.as_secs())));
+            if modified.subsec_nanos() != 0 {
+                entries.push(("nanos", Value::Uint(modified.subsec_nanos() as u64)));
+            }
+        }
+        let mut res = MARKER.to_vec();
+        write_map(&mut res, &entries);
+        res.extend_from_slice(&self.data);
+        res
+    }
+
+    // inverse of to_bytes, Malformed for data without an envelope. unknown keys are skipped
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let bytes = bytes.strip_prefix(MARKER).ok_or(DecodeError::Malformed)?;
+        let mut pos = 0;
+        let (major, entries) = cbor_read_head(bytes, &mut pos)?;
+        if major != MAJOR_MAP {
+            return Err(DecodeError::Malformed)
+        }
+        let mut payload = Payload::default();
+        let mut nanos = 0;
+        for _ in 0..entries {
+            let key = read_string(bytes, &mut pos, MAJOR_TEXT)?;
+            let text = |pos: &mut usize| String::from_utf8(read_string(bytes, pos, MAJOR_TEXT)?.to_vec()).map_err(|_| DecodeError::Malformed);
+            match key {
+                b"name" => payload.name = Some(text(&mut pos)?),
+                b"mime" => payload.mime = Some(text(&mut pos)?),
+                b"modified" => match cbor_read_head(bytes, &mut pos)? {
+                    (MAJOR_UINT, secs) => payload.modified = UNIX_EPOCH.checked_add(Duration::from_secs(secs)),
+                    _ => return Err(DecodeError::Malformed)
+                },
+                b"nanos" => match cbor_read_head(bytes, &mut pos)? {
+                    (MAJOR_UINT, n) if n < 1_000_000_000 => nanos = n as u32,
+                    _ => return Err(DecodeError::Malformed)
+                },
+                _ => skip(bytes, &mut pos)?
+            }
+        }
+        payload.modified = payload.modified.and_then(|time| time.checked_add(Duration::from_nanos(nanos as u64)));
+        payload.data = bytes[pos..].to_vec();
+        Ok(payload)
+    }
+
+    // writes the data to dir under its original name and restores the modification time.
+    // only the last component of the name is used, so a crafted name can't escape dir, and an
+    // existing file is never overwritten, that fails with AlreadyExists
+    #[cfg(feature = "fs")]
+    pub fn save_in<P: AsRef<Path>>(&self, dir: P) -> io::Result<PathBuf> {
+        let name = self.name.as_deref().map(Path::new).and_then(Path::file_name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "payload has no file name"))?;
+        let path = dir.as_ref().join(name);
+        let mut file = File::create_new(&path)?;
+        io::Write::write_all(&mut file, &self.data)?;
+        if let Some(modified) = self.modified {
+            file.set_modified(modified)?;
+        }
+        Ok(path)
+    }
+}
+
+// the common types by extension, application/octet-stream for anything else
+#[cfg(feature = "fs")]
+fn mime_type(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("txt") => "text/plain",
+        Some("html" | "htm") => "text/html",
+        Some("csv") => "text/csv",
+        Some("json") => "application/json",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        Some("gz") => "application/gzip",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream"
+    }
+}
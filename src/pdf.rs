@@ -0,0 +1,207 @@
+use std::fmt::Write;
+use qrcode::{QrCode, Color};
+use crate::naming;
+
+// paper sizes, in points (1/72 inch)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+    Custom { width: f32, height: f32 }
+}
+
+impl PageSize {
+    pub fn dimensions(&self) -> (f32, f32) {
+        match *self {
+            PageSize::A4 => (595.28, 841.89),
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::Custom { width, height } => (width, height)
+        }
+    }
+}
+
+// how codes are tiled onto pages, margin is in points
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageOptions {
+    pub size: PageSize,
+    pub margin: f32,
+    pub per_row: usize,
+    // "3 of 12" under every code
+    pub captions: bool,
+    // replaces "3 of 12" with a template taking the placeholders of naming::caption
+    pub caption: Option<String>,
+    // light border around every code in modules, inside its cell. 0 draws codes edge to edge
+    pub quiet_zone: usize
+}
+
+impl Default for PageOptions {
+    fn default() -> Self {
+        PageOptions { size: PageSize::A4, margin: 36.0, per_row: 2, captions: true, caption: None, quiet_zone: QUIET_ZONE }
+    }
+}
+
+pub(crate) const CAPTION_SIZE: f32 = 10.0;
+// the standard four modules of quiet zone on every side
+pub(crate) const QUIET_ZONE: usize = 4;
+
+// a self contained pdf with the codes drawn as vector rectangles, so they print sharp at any size.
+// per_row must be at least 1
+pub(crate) fn render(codes: &[QrCode], options: &PageOptions) -> Vec<u8> {
+    let (page_width, page_height) = options.size.dimensions();
+    write_document(&code_pages(codes, options), page_width, page_height)
+}
+
+// lines of text from the top of a page, each with its font size. lines too wide for the page
+// are wrapped at spaces
+pub(crate) fn text_page(lines: &[(f32, String)], options: &PageOptions) -> String {
+    let (page_width, page_height) = options.size.dimensions();
+    let usable_width = page_width - 2.0 * options.margin;
+    let mut content = String::new();
+    let mut y = page_height - options.margin;
+    for (size, line) in lines {
+        let mut rest = line.as_str();
+        loop {
+            // helvetica averages about half the font size per character
+            let fit = ((usable_width / (0.5 * size)) as usize).max(1);
+            let end = match rest.char_indices().nth(fit) {
+                Some((end, _)) => rest[..end].rfind(' ').unwrap_or(end),
+                None => rest.len()
+            };
+            y -= 1.4 * size;
+            let _ = writeln!(content, "BT /F1 {} Tf {:.2} {:.2} Td ({}) Tj ET", size, options.margin, y, escape(&rest[..end]));
+            rest = rest[end..].trim_start();
+            if rest.is_empty() {
+                break
+            }
+        }
+    }
+    content
+}
+
+// the page contents of render, for documents with pages of their own in front
+pub(crate) fn code_pages(codes: &[QrCode], options: &PageOptions) -> Vec<String> {
+    let (page_width, page_height) = options.size.dimensions();
+    let usable_width = page_width - 2.0 * options.margin;
+    let usable_height = page_height - 2.0 * options.margin;
+    let caption_height = if options.captions { 2.0 * CAPTION_SIZE } else { 0.0 };
+
+    let cell_width = usable_width / options.per_row as f32;
+    let side = cell_width.min(usable_height - caption_height).max(0.0);
+    let cell_height = side + caption_height;
+    let rows = ((usable_height / cell_height) as usize).max(1);
+    let per_page = rows * options.per_row;
+
+    let mut pages = Vec::new();
+    for (page, page_codes) in codes.chunks(per_page.max(1)).enumerate() {
+        let mut content = String::new();
+        for (i, code) in page_codes.iter().enumerate() {
+            let index = page * per_page + i;
+            let left = options.margin + (i % options.per_row) as f32 * cell_width + (cell_width - side) / 2.0;
+            let top = page_height - options.margin - (i / options.per_row) as f32 * cell_height;
+            draw_code(&mut content, code, left, top, side, options.quiet_zone);
+
+            if options.captions {
+                let caption = match &options.caption {
+                    Some(template) => naming::caption(template, index, codes.len()),
+                    None => format!("{} of {}", index + 1, codes.len())
+                };
+                let x = left + (side - caption_width(&caption)) / 2.0;
+                let y = top - side - 1.5 * CAPTION_SIZE;
+                let _ = writeln!(content, "BT /F1 {} Tf {:.2} {:.2} Td ({}) Tj ET", CAPTION_SIZE, x, y, escape(&caption));
+            }
+        }
+        pages.push(content);
+    }
+    // an empty document still gets a blank page
+    if pages.is_empty() {
+        pages.push(String::new());
+    }
+    pages
+}
+
+// dark modules as filled rectangles
+fn draw_code(content: &mut String, code: &QrCode, left: f32, top: f32, side: f32, quiet_zone: usize) {
+    let module = side / (code.width() + 2 * quiet_zone) as f32;
+    for (x, y, len) in dark_runs(code) {
+        let _ = writeln!(content, "{:.3} {:.3} {:.3} {:.3} re",
+            left + (quiet_zone + x) as f32 * module,
+            top - (quiet_zone + y + 1) as f32 * module,
+            len as f32 * module,
+            module);
+    }
+    content.push_str("f\n");
+}
+
+// horizontal runs of dark modules as (x, y, length), merged to keep vector output small
+pub(crate) fn dark_runs(code: &QrCode) -> Vec<(usize, usize, usize)> {
+    let width = code.width();
+    let colors = code.to_colors();
+    let mut runs = Vec::new();
+    for (y, row) in colors.chunks(width).enumerate() {
+        let mut x = 0;
+        while x < width {
+            if row[x] != Color::Dark {
+                x += 1;
+                continue
+            }
+            let start = x;
+            while x < width && row[x] == Color::Dark {
+                x += 1;
+            }
+            runs.push((start, y, x - start));
+        }
+    }
+    runs
+}
+
+// helvetica advance widths of the few characters captions use
+pub(crate) fn caption_width(caption: &str) -> f32 {
+    caption.chars().map(|c| match c {
+        ' ' | 'f' => 278.0,
+        _ => 556.0
+    }).sum::<f32>() * CAPTION_SIZE / 1000.0
+}
+
+// backslashes and parentheses would end or break a pdf or postscript string literal
+pub(crate) fn escape(text: &str) -> String {
+    text.chars().fold(String::with_capacity(text.len()), |mut res, c| {
+        if matches!(c, '\\' | '(' | ')') {
+            res.push('\\');
+        }
+        res.push(c);
+        res
+    })
+}
+
+pub(crate) fn write_document(pages: &[String], page_width: f32, page_height: f32) -> Vec<u8> {
+    // 1 is the catalog, 2 the page tree, 3 the font, then a page and its content stream for every page
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 4 + 2 * i).collect();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" "), pages.len()),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string()
+    ];
+    for (content, id) in pages.iter().zip(&page_ids) {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            page_width, page_height, id + 1));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+    }
+
+    let mut res = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(res.len());
+        res.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).into_bytes());
+    }
+    let xref = res.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(trailer, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(trailer, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref);
+    res.extend(trailer.into_bytes());
+    res
+}
@@ -0,0 +1,67 @@
+use std::{io::{self, Write}, time::{Duration, Instant}};
+use crossterm::{
+    execute, queue,
+    cursor::{Hide, Show, MoveTo},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen}
+};
+
+const HELP: &str = "space: pause, left/right: step, q: quit";
+
+// cycles through frames on the alternate screen until the user quits, fps must be at least 1
+pub(crate) fn play(frames: &[String], fps: u32) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    let res = execute!(stdout, EnterAlternateScreen, Hide).and_then(|_| run(&mut stdout, frames, fps));
+    // restore the terminal even if drawing failed
+    let restored = execute!(stdout, Show, LeaveAlternateScreen).and_then(|_| terminal::disable_raw_mode());
+    res.and(restored)
+}
+
+fn run<W: Write>(out: &mut W, frames: &[String], fps: u32) -> io::Result<()> {
+    if frames.is_empty() {
+        return Ok(())
+    }
+    let interval = Duration::from_millis(1000 / fps as u64);
+    let mut index = 0;
+    let mut paused = false;
+    let mut next = Instant::now() + interval;
+
+    loop {
+        draw(out, frames, index, paused)?;
+
+        let timeout = next.saturating_duration_since(Instant::now());
+        if !event::poll(timeout)? {
+            if !paused {
+                index = (index + 1) % frames.len();
+            }
+            next = Instant::now() + interval;
+            continue
+        }
+
+        if let Event::Key(KeyEvent { code, modifiers, kind: KeyEventKind::Press, .. }) = event::read()? {
+            match code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                KeyCode::Char(' ') => paused = !paused,
+                KeyCode::Left => index = (index + frames.len() - 1) % frames.len(),
+                KeyCode::Right => index = (index + 1) % frames.len(),
+                _ => {}
+            }
+        }
+    }
+}
+
+// raw mode doesn't translate newlines, so every line is positioned explicitly
+fn draw<W: Write>(out: &mut W, frames: &[String], index: usize, paused: bool) -> io::Result<()> {
+    queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+    let mut row = 0;
+    for line in frames[index].lines() {
+        queue!(out, MoveTo(0, row))?;
+        write!(out, "{}", line)?;
+        row += 1;
+    }
+    queue!(out, MoveTo(0, row))?;
+    write!(out, "{} of {}{}  {}", index + 1, frames.len(), if paused { " (paused)" } else { "" }, HELP)?;
+    out.flush()
+}
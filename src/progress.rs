@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use crate::MqrError;
+
+// one step of a long encode or render, done of total codes. Scanned is a new chunk seen by
+// live::LiveDecoder, total is None until a header with a total was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Progress {
+    Encoded { done: usize, total: usize },
+    Rendered { done: usize, total: usize },
+    Scanned { index: usize, received: usize, total: Option<usize> }
+}
+
+// feedback for progress bars. report is called once per code encoded and once per image
+// rendered, from worker threads with the parallel feature, so steps can arrive out of order.
+// returning false cancels the operation, which then fails with MqrError::Cancelled
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, progress: Progress) -> bool;
+}
+
+impl<F: Fn(Progress) -> bool + Send + Sync> ProgressSink for F {
+    fn report(&self, progress: Progress) -> bool {
+        self(progress)
+    }
+}
+
+// counts the finished steps of one operation for its sink, if there is one
+pub(crate) struct Tracker<'a> {
+    sink: Option<&'a dyn ProgressSink>,
+    step: fn(usize, usize) -> Progress,
+    total: usize,
+    done: AtomicUsize,
+    cancelled: AtomicBool
+}
+
+impl<'a> Tracker<'a> {
+    pub(crate) fn encoding(sink: Option<&'a dyn ProgressSink>, total: usize) -> Self {
+        Self::new(sink, total, |done, total| Progress::Encoded { done, total })
+    }
+
+    #[cfg(feature = "render-image")]
+    pub(crate) fn rendering(sink: Option<&'a dyn ProgressSink>, total: usize) -> Self {
+        Self::new(sink, total, |done, total| Progress::Rendered { done, total })
+    }
+
+    fn new(sink: Option<&'a dyn ProgressSink>, total: usize, step: fn(usize, usize) -> Progress) -> Self {
+        Tracker { sink, step, total, done: AtomicUsize::new(0), cancelled: AtomicBool::new(false) }
+    }
+
+    // before starting a step, so work queued on other threads stops soon after a cancel
+    pub(crate) fn check(&self) -> Result<(), MqrError> {
+        match self.cancelled.load(Ordering::Relaxed) {
+            true => Err(MqrError::Cancelled),
+            false => Ok(())
+        }
+    }
+
+    // after finishing a step
+    pub(crate) fn step(&self) -> Result<(), MqrError> {
+        let Some(sink) = self.sink else {
+            return Ok(())
+        };
+        self.check()?;
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if !sink.report((self.step)(done, self.total)) {
+            self.cancelled.store(true, Ordering::Relaxed);
+            return Err(MqrError::Cancelled)
+        }
+        Ok(())
+    }
+}
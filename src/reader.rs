@@ -0,0 +1,117 @@
+use std::{collections::BTreeMap, io::{self, Read}};
+use crate::{DecodeError, HeaderFormat, compression, crypto, digest, parity, stripe};
+
+// the decode side counterpart of MultiQrWriter: scanned payloads are pushed in any order
+// and the original bytes can be read as soon as the chunks before them have arrived.
+// reads return WouldBlock while waiting for the next chunk
+pub struct MultiQrReader {
+    format: HeaderFormat,
+    meta: Option<(Option<usize>, Option<u32>)>,
+    pending: BTreeMap<usize, Vec<u8>>,
+    current: Vec<u8>,
+    pos: usize,
+    next: usize,
+    hasher: crc32fast::Hasher,
+    closed: bool
+}
+
+impl MultiQrReader {
+    pub fn new() -> Self {
+        Self::with_header(HeaderFormat::Legacy)
+    }
+
+    pub fn with_header(format: HeaderFormat) -> Self {
+        MultiQrReader {
+            format,
+            meta: None,
+            pending: BTreeMap::new(),
+            current: Vec::new(),
+            pos: 0,
+            next: 0,
+            hasher: crc32fast::Hasher::new(),
+            closed: false
+        }
+    }
+
+    // queue one scanned payload, chunks that were already read are ignored
+    pub fn push(&mut self, payload: &[u8]) -> Result<(), DecodeError> {
+        let (header, data) = self.format.read(payload)?;
+        // compressed, encrypted, shift jis, digested or striped payloads only make sense once complete, use reassemble_with for those
+        let whole = compression::FLAG_MASK | crypto::FLAG_AES_GCM | crypto::FLAG_PASSPHRASE | digest::FLAG_SHA256 | parity::FLAG_PARITY | stripe::FLAG_STRIPED;
+        if header.flags & whole != 0 {
+            return Err(DecodeError::Unsupported)
+        }
+        match self.meta {
+            None => self.meta = Some((header.total, header.checksum)),
+            Some(m) if m != (header.total, header.checksum) => return Err(DecodeError::InconsistentHeader),
+            _ => {}
+        }
+        if header.total.is_some_and(|total| header.index >= total) {
+            return Err(DecodeError::InconsistentHeader)
+        }
+        if header.index >= self.next {
+            self.pending.entry(header.index).or_insert_with(|| data.to_vec());
+        }
+        Ok(())
+    }
+
+    // no more payloads will be pushed. only needed for the legacy header,
+    // which doesn't store how many chunks there are
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    fn total(&self) -> Option<usize> {
+        self.meta.and_then(|(total, _)| total)
+    }
+
+    fn verify(&self) -> io::Result<()> {
+        match self.meta {
+            Some((_, Some(checksum))) if self.hasher.clone().finalize() != checksum =>
+                Err(io::Error::new(io::ErrorKind::InvalidData, DecodeError::ChecksumMismatch)),
+            _ => Ok(())
+        }
+    }
+}
+
+impl Default for MultiQrReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Read for MultiQrReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+
+        while self.pos == self.current.len() {
+            if let Some(chunk) = self.pending.remove(&self.next) {
+                self.hasher.update(&chunk);
+                self.current = chunk;
+                self.pos = 0;
+                self.next += 1;
+                continue
+            }
+
+            let done = match self.total() {
+                Some(total) => self.next >= total,
+                None => self.closed && self.pending.is_empty()
+            };
+            if done {
+                self.verify()?;
+                return Ok(0)
+            }
+            if self.closed {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, DecodeError::MissingChunk(self.next)))
+            }
+            return Err(io::Error::new(io::ErrorKind::WouldBlock, "waiting for the next chunk"))
+        }
+
+        let n = buf.len().min(self.current.len() - self.pos);
+        buf[..n].copy_from_slice(&self.current[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
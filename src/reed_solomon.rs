@@ -0,0 +1,86 @@
+// reed-solomon check words over GF(2^m) with the generator roots a^1..a^n, the flavour
+// datamatrix and aztec both use. qr codes get theirs from the qrcode crate
+pub(crate) struct Field {
+    exp: Vec<u16>,
+    log: Vec<u16>,
+    // elements in the field, 2^m
+    size: usize
+}
+
+impl Field {
+    // poly is the primitive polynomial including its x^m term
+    pub(crate) fn new(poly: u32, bits: u32) -> Self {
+        let size = 1 << bits;
+        let mut field = Field { exp: vec![0; 2 * size], log: vec![0; size], size };
+        let mut x = 1u32;
+        for i in 0..size - 1 {
+            field.exp[i] = x as u16;
+            field.log[x as usize] = i as u16;
+            x <<= 1;
+            if x & size as u32 != 0 {
+                x ^= poly;
+            }
+        }
+        for i in size - 1..2 * size {
+            field.exp[i] = field.exp[i - (size - 1)];
+        }
+        field
+    }
+
+    pub(crate) fn mul(&self, a: u16, b: u16) -> u16 {
+        if a == 0 || b == 0 {
+            return 0
+        }
+        self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+    }
+
+    // a^i
+    pub(crate) fn pow(&self, i: usize) -> u16 {
+        self.exp[i % (self.size - 1)]
+    }
+
+    // the n check words to append to data, highest degree first
+    pub(crate) fn check_words(&self, data: &[u16], n: usize) -> Vec<u16> {
+        // (x + a^1)(x + a^2)...(x + a^n), leading coefficient first
+        let mut generator = vec![1u16];
+        for i in 1..=n {
+            let root = self.pow(i);
+            generator.push(0);
+            for j in (1..generator.len()).rev() {
+                generator[j] ^= self.mul(generator[j - 1], root);
+            }
+        }
+        let mut check = vec![0u16; n];
+        for &word in data {
+            let factor = word ^ check.first().copied().unwrap_or(0);
+            check.rotate_left(1);
+            if let Some(last) = check.last_mut() {
+                *last = 0;
+            }
+            for (c, g) in check.iter_mut().zip(&generator[1..]) {
+                *c ^= self.mul(*g, factor);
+            }
+        }
+        check
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Field;
+
+    // every root of the generator is a root of a valid codeword
+    #[test]
+    fn syndromes_vanish() {
+        for (poly, bits) in [(0x12d, 8), (0x43, 6), (0x13, 4), (0x1069, 12)] {
+            let field = Field::new(poly, bits);
+            let max = (1u16 << bits) - 1;
+            let data: Vec<u16> = (0..20u16).map(|i| (i * 37 + 11) & max).collect();
+            let codeword: Vec<u16> = data.iter().copied().chain(field.check_words(&data, 7)).collect();
+            for i in 1..=7 {
+                let root = field.pow(i);
+                assert_eq!(codeword.iter().fold(0, |acc, &c| field.mul(acc, root) ^ c), 0);
+            }
+        }
+    }
+}
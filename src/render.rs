@@ -0,0 +1,293 @@
+use std::{fmt, sync::Arc};
+use image::{Rgba, RgbaImage, imageops::{self, FilterType}};
+use qrcode::{QrCode, Version};
+use crate::{naming, ImageFormat, ProgressSink, text::{self, modules}};
+
+// colors of rendered images, a zero alpha light color gives a transparent background
+#[derive(Clone)]
+pub struct RenderOptions {
+    pub dark: Rgba<u8>,
+    pub light: Rgba<u8>,
+    // drawn over the center of every code, codes below EcLevel::H are re-encoded at H to stay scannable
+    pub logo: Option<Logo>,
+    // how dark modules outside the finder patterns are drawn
+    pub shape: Arc<dyn ModuleShape + Send + Sync>,
+    // light modules on a dark background for dark themed screens. the quiet zone, logo backdrop
+    // and captions take the dark color too, so the code still stands out from its surroundings
+    pub invert: bool
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions { dark: Rgba([0, 0, 0, 255]), light: Rgba([255, 255, 255, 255]), logo: None, shape: Arc::new(Square), invert: false }
+    }
+}
+
+impl fmt::Debug for RenderOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RenderOptions").field("dark", &self.dark).field("light", &self.light).field("logo", &self.logo).field("invert", &self.invert).finish_non_exhaustive()
+    }
+}
+
+// pixel scale and border of every image export. the defaults match qrcode's own renderer
+#[derive(Clone)]
+pub struct SaveOptions {
+    pub module_px: u32,
+    // light border around every code in modules, also used by svgs and sheets. 0 leaves it
+    // out for layouts that already have a margin, scanners need one from somewhere
+    pub quiet_zone_modules: u32,
+    // module_px is raised until the image is at least this large
+    pub min_dimensions: Option<(u32, u32)>,
+    pub render: RenderOptions,
+    // names of saved files, see naming::file_name for the placeholders
+    pub file_name: String,
+    // None picks the format from the file extension, falling back to png
+    pub format: Option<ImageFormat>,
+    // label drawn under every code, a template taking the placeholders of naming::caption
+    pub caption: Option<String>,
+    // told about every image rendered by exports of the whole set
+    pub progress: Option<Arc<dyn ProgressSink>>,
+    // save_with also writes a manifest.json listing the set and its files
+    pub manifest_json: bool,
+    // save_with creates the directory of the files if it doesn't exist yet
+    pub create_dirs: bool,
+    // what save_with does about files that are already there
+    pub overwrite: Overwrite
+}
+
+// what happens to existing files when saving
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overwrite {
+    // nothing is written if any of the files exists
+    Error,
+    // existing files are kept and left out of the files written
+    Skip,
+    #[default]
+    Replace
+}
+
+#[cfg(feature = "fs")]
+impl Overwrite {
+    // only Replace opens a file that appeared after the checks
+    pub(crate) fn create(&self, path: &std::path::Path) -> std::io::Result<std::fs::File> {
+        match self {
+            Overwrite::Replace => std::fs::File::create(path),
+            Overwrite::Error | Overwrite::Skip => std::fs::OpenOptions::new().write(true).create_new(true).open(path)
+        }
+    }
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        SaveOptions {
+            module_px: 8,
+            quiet_zone_modules: 4,
+            min_dimensions: None,
+            render: RenderOptions::default(),
+            file_name: naming::DEFAULT_TEMPLATE.to_string(),
+            format: None,
+            caption: None,
+            progress: None,
+            manifest_json: false,
+            create_dirs: false,
+            overwrite: Overwrite::Replace
+        }
+    }
+}
+
+impl fmt::Debug for SaveOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SaveOptions")
+            .field("module_px", &self.module_px)
+            .field("quiet_zone_modules", &self.quiet_zone_modules)
+            .field("min_dimensions", &self.min_dimensions)
+            .field("render", &self.render)
+            .field("file_name", &self.file_name)
+            .field("format", &self.format)
+            .field("caption", &self.caption)
+            .field("manifest_json", &self.manifest_json)
+            .field("create_dirs", &self.create_dirs)
+            .field("overwrite", &self.overwrite)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SaveOptions {
+    // "3 / 12" under every code, so printed codes are scanned in order and gaps stand out
+    pub fn captioned() -> Self {
+        SaveOptions { caption: Some(naming::DEFAULT_CAPTION.to_string()), ..Default::default() }
+    }
+
+    fn module_px_for(&self, modules: u32) -> u32 {
+        let min = self.min_dimensions.map_or(0, |(width, height)| width.max(height).div_ceil(modules.max(1)));
+        self.module_px.max(min).max(1)
+    }
+}
+
+// size is the logo's longer side as a fraction of the symbol width, quiet zone excluded.
+// anything much above the default eats into what EcLevel::H can recover
+#[derive(Debug, Clone, PartialEq)]
+pub struct Logo {
+    pub image: RgbaImage,
+    pub size: f32
+}
+
+impl Logo {
+    pub fn new(image: RgbaImage) -> Self {
+        Logo { image, size: 0.2 }
+    }
+}
+
+impl RenderOptions {
+    // fully transparent background, for placing codes on colored documents
+    pub fn transparent(dark: Rgba<u8>) -> Self {
+        RenderOptions { dark, light: Rgba([0, 0, 0, 0]), ..Default::default() }
+    }
+
+    // the colors modules and the background are drawn in, swapped when inverted
+    pub fn colors(&self) -> (Rgba<u8>, Rgba<u8>) {
+        match self.invert {
+            false => (self.dark, self.light),
+            true => (self.light, self.dark)
+        }
+    }
+}
+
+// which of the four direct neighbours of a module are dark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Neighbours {
+    pub top: bool,
+    pub right: bool,
+    pub bottom: bool,
+    pub left: bool
+}
+
+// decides which pixels of a dark module are painted. x and y are the pixel center
+// relative to the module, both in 0.0..1.0. finder patterns are always drawn square
+pub trait ModuleShape {
+    fn contains(&self, x: f32, y: f32, neighbours: Neighbours) -> bool;
+}
+
+// the classic hard square
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Square;
+
+impl ModuleShape for Square {
+    fn contains(&self, _: f32, _: f32, _: Neighbours) -> bool {
+        true
+    }
+}
+
+// a circle, radius is a fraction of the module size with 0.5 touching the edges
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dot {
+    pub radius: f32
+}
+
+impl Default for Dot {
+    fn default() -> Self {
+        Dot { radius: 0.45 }
+    }
+}
+
+impl ModuleShape for Dot {
+    fn contains(&self, x: f32, y: f32, _: Neighbours) -> bool {
+        (x - 0.5).powi(2) + (y - 0.5).powi(2) <= self.radius.powi(2)
+    }
+}
+
+// corners are rounded off unless a neighbour continues on either side of them,
+// so runs of modules merge into smooth blobs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rounded;
+
+impl ModuleShape for Rounded {
+    fn contains(&self, x: f32, y: f32, n: Neighbours) -> bool {
+        let vertical = if y < 0.5 { n.top } else { n.bottom };
+        let horizontal = if x < 0.5 { n.left } else { n.right };
+        vertical || horizontal || (x - 0.5).powi(2) + (y - 0.5).powi(2) <= 0.25
+    }
+}
+
+pub(crate) fn render(code: &QrCode, save: &SaveOptions) -> RgbaImage {
+    let options = &save.render;
+    let (foreground, background) = options.colors();
+    let quiet_zone = save.quiet_zone_modules as usize;
+    let (size, dark) = modules(code, quiet_zone);
+    let module_px = save.module_px_for(size as u32);
+    let finders = finder_origins(code);
+    let is_dark = |x: usize, y: usize| x < size && y < size && dark[y * size + x];
+
+    let mut image = RgbaImage::from_pixel(size as u32 * module_px, size as u32 * module_px, background);
+    for y in 0..size {
+        for x in 0..size {
+            if !is_dark(x, y) {
+                continue
+            }
+            let in_finder = finders.iter().any(|&(fx, fy)| (fx..fx + 7).contains(&(x - quiet_zone)) && (fy..fy + 7).contains(&(y - quiet_zone)));
+            let neighbours = Neighbours {
+                top: y > 0 && is_dark(x, y - 1),
+                right: is_dark(x + 1, y),
+                bottom: is_dark(x, y + 1),
+                left: x > 0 && is_dark(x - 1, y)
+            };
+            for py in 0..module_px {
+                for px in 0..module_px {
+                    let (fx, fy) = ((px as f32 + 0.5) / module_px as f32, (py as f32 + 0.5) / module_px as f32);
+                    if in_finder || options.shape.contains(fx, fy, neighbours) {
+                        image.put_pixel(x as u32 * module_px + px, y as u32 * module_px + py, foreground);
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(logo) = &options.logo {
+        overlay_logo(&mut image, logo, module_px * code.width() as u32, module_px, background);
+    }
+    image
+}
+
+// an svg with the colors, quiet zone and scale of save. module shapes and logos are left out,
+// every dark run is a rectangle of the path
+pub(crate) fn render_svg(code: &QrCode, save: &SaveOptions) -> String {
+    let (foreground, background) = save.render.colors();
+    let quiet_zone = save.quiet_zone_modules as usize;
+    let size = code.width() + 2 * quiet_zone;
+    text::svg(code, quiet_zone, save.module_px_for(size as u32), &svg_fill(background), &svg_fill(foreground))
+}
+
+// fill and, unless opaque, fill-opacity attributes for a color
+fn svg_fill(color: Rgba<u8>) -> String {
+    let [r, g, b, a] = color.0;
+    match a {
+        255 => format!("fill=\"#{:02x}{:02x}{:02x}\"", r, g, b),
+        _ => format!("fill=\"#{:02x}{:02x}{:02x}\" fill-opacity=\"{:.3}\"", r, g, b, a as f32 / 255.0)
+    }
+}
+
+// top left module of each 7x7 finder pattern, quiet zone excluded
+fn finder_origins(code: &QrCode) -> Vec<(usize, usize)> {
+    let far = code.width() - 7;
+    match code.version() {
+        Version::Normal(_) => vec![(0, 0), (far, 0), (0, far)],
+        Version::Micro(_) => vec![(0, 0)]
+    }
+}
+
+// centered on a light backdrop one module wider than the logo, so no module is cut in half visually
+fn overlay_logo(image: &mut RgbaImage, logo: &Logo, symbol_width: u32, module: u32, backdrop: Rgba<u8>) {
+    let (logo_width, logo_height) = logo.image.dimensions();
+    if logo_width == 0 || logo_height == 0 {
+        return
+    }
+    let side = (symbol_width as f32 * logo.size.clamp(0.0, 1.0)) as u32;
+    let scale = side as f32 / logo_width.max(logo_height) as f32;
+    let (width, height) = (((logo_width as f32 * scale) as u32).max(1), ((logo_height as f32 * scale) as u32).max(1));
+    let resized = imageops::resize(&logo.image, width, height, FilterType::Lanczos3);
+
+    let backdrop = RgbaImage::from_pixel(width + 2 * module, height + 2 * module, backdrop);
+    let (center_x, center_y) = (image.width() / 2, image.height() / 2);
+    imageops::replace(image, &backdrop, center_x.saturating_sub(backdrop.width() / 2), center_y.saturating_sub(backdrop.height() / 2));
+    imageops::overlay(image, &resized, center_x.saturating_sub(width / 2), center_y.saturating_sub(height / 2));
+}
@@ -0,0 +1,96 @@
+// a set rendered once and exported many times. MultiQrCode's to_ methods start from the codes
+// on every call, here the packed module matrices, the text and the images are each computed
+// the first time an export needs them and reused by every export after that
+use std::sync::OnceLock;
+#[cfg(feature = "render-image")]
+use image::{DynamicImage, GrayImage};
+#[cfg(feature = "render-image")]
+use crate::MqrError;
+use crate::{Base64Variant, MultiQrCode, QrData, TextStyle, base45, pack_modules, text};
+
+pub struct RenderedMultiQrCode<'a> {
+    qr: &'a MultiQrCode,
+    packed: OnceLock<Vec<Vec<u8>>>,
+    strings: OnceLock<Vec<String>>,
+    #[cfg(feature = "render-image")]
+    images: OnceLock<Vec<GrayImage>>
+}
+
+impl<'a> RenderedMultiQrCode<'a> {
+    pub fn new(qr: &'a MultiQrCode) -> Self {
+        RenderedMultiQrCode {
+            qr,
+            packed: OnceLock::new(),
+            strings: OnceLock::new(),
+            #[cfg(feature = "render-image")]
+            images: OnceLock::new()
+        }
+    }
+
+    pub fn codes(&self) -> &'a MultiQrCode {
+        self.qr
+    }
+
+    // modules of every code packed 8 to a byte, the matrix behind the base64, base45, hex and raw exports
+    pub fn packed(&self) -> &[Vec<u8>] {
+        self.packed.get_or_init(|| self.qr.map_codes(pack_modules))
+    }
+
+    // the Full text of every code, as MultiQrCode::to_strings
+    pub fn strings(&self) -> &[String] {
+        self.strings.get_or_init(|| self.qr.map_codes(|code| text::render(code, TextStyle::Full)))
+    }
+
+    pub fn to_strings(&self) -> Vec<QrData> {
+        self.strings().iter().cloned().map(QrData::String).collect()
+    }
+
+    pub fn to_base64(&self) -> Vec<QrData> {
+        self.to_base64_with(Base64Variant::Standard)
+    }
+
+    pub fn to_base64_with(&self, variant: Base64Variant) -> Vec<QrData> {
+        self.map_packed(|width, packed| QrData::Base64 { width, data: variant.encode(packed) })
+    }
+
+    pub fn to_base45(&self) -> Vec<QrData> {
+        self.map_packed(|width, packed| QrData::Base45 { width, data: base45::encode(packed) })
+    }
+
+    pub fn to_hex(&self) -> Vec<QrData> {
+        self.map_packed(|width, packed| QrData::Hex { width, data: packed.iter().map(|byte| format!("{:02x}", byte)).collect() })
+    }
+
+    pub fn to_raw(&self) -> Vec<QrData> {
+        self.map_packed(|width, packed| QrData::Raw { width, data: packed.to_vec() })
+    }
+
+    fn map_packed<F: Fn(usize, &[u8]) -> QrData>(&self, f: F) -> Vec<QrData> {
+        self.qr.codes.iter().zip(self.packed()).map(|(code, packed)| f(code.width(), packed)).collect()
+    }
+
+    // the images save and to_png_bytes write, as MultiQrCode::to_images
+    #[cfg(feature = "render-image")]
+    pub fn images(&self) -> &[GrayImage] {
+        self.images.get_or_init(|| self.qr.to_images())
+    }
+
+    #[cfg(feature = "render-image")]
+    pub fn to_png_bytes(&self) -> Result<Vec<Vec<u8>>, MqrError> {
+        self.images().iter().map(|image| {
+            let mut bytes = Vec::new();
+            DynamicImage::ImageLuma8(image.clone()).write_to(&mut bytes, image::ImageFormat::Png)?;
+            Ok(bytes)
+        }).collect()
+    }
+
+    // the same files as MultiQrCode::save
+    #[cfg(all(feature = "fs", feature = "render-image"))]
+    pub fn save(&self, path: &str) -> Result<(), MqrError> {
+        let path = std::path::Path::new(path);
+        for (i, image) in self.images().iter().enumerate() {
+            image.save(path.with_extension(format!("{}.png", i)))?;
+        }
+        Ok(())
+    }
+}
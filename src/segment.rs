@@ -0,0 +1,113 @@
+use qrcode::{QrCode, Version, EcLevel, bits::Bits, types::{Mode, QrError}, optimize::{Parser, Segment, total_encoded_len}};
+use crate::charset::ECI_UTF8;
+
+// mode indicator and 8 bit designator of a utf-8 ECI header
+pub(crate) const ECI_BITS: usize = 12;
+
+// chunks as long as still fit version once digits and uppercase runs go into numeric and
+// alphanumeric segments. the header always stays a byte segment of its own, so its
+// contents (and the total it holds) can't change the size of a chunk
+pub(crate) fn split(data: &[u8], version: Version, ec: EcLevel, header_size: usize, eci: bool) -> Result<Vec<&[u8]>, QrError> {
+    let capacity = capacity(version, ec, eci)?;
+    let mut chunks = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        // the largest prefix that fits, encoded length only grows with the prefix
+        let (mut low, mut high) = (0, rest.len());
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if encoded_len(header_size, &rest[..mid], version) <= capacity {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        if low == 0 {
+            return Err(QrError::DataTooLong)
+        }
+        let (chunk, tail) = rest.split_at(low);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    Ok(chunks)
+}
+
+// the code for a payload from split, laid out exactly as split measured it
+pub(crate) fn code(payload: &[u8], header_size: usize, version: Version, ec: EcLevel, eci: bool) -> Result<QrCode, QrError> {
+    let (header, data) = payload.split_at(header_size.min(payload.len()));
+    if encoded_len(header.len(), data, version) > capacity(version, ec, eci)? {
+        return Err(QrError::DataTooLong)
+    }
+    let mut bits = Bits::new(version);
+    if eci {
+        bits.push_eci_designator(ECI_UTF8)?;
+    }
+    bits.push_byte_data(header)?;
+    bits.push_segments(data, Parser::new(data).optimize(version))?;
+    bits.push_terminator(ec)?;
+    QrCode::with_bits(bits, ec)
+}
+
+// the payload as one byte segment, which always fits the size MultiQrCode::new chunks to
+// unlike the mixed segments qrcode picks for multi-byte text. eci puts a utf-8 ECI header
+// in front so scanners don't guess the charset
+pub(crate) fn byte_code(payload: &[u8], version: Version, ec: EcLevel, eci: bool) -> Result<QrCode, QrError> {
+    let mut bits = Bits::new(version);
+    if eci {
+        bits.push_eci_designator(ECI_UTF8)?;
+    }
+    bits.push_byte_data(payload)?;
+    bits.push_terminator(ec)?;
+    QrCode::with_bits(bits, ec)
+}
+
+// how a payload is put into segments, the same at any version and ec level, so a code can be
+// encoded again in another symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Segments {
+    // a single byte segment, see byte_code
+    Bytes { eci: bool },
+    // the first prefix bytes in a byte segment and the shortest segments after them, see code
+    Optimized { prefix: usize, eci: bool },
+    // whatever QrCode::with_version picks
+    Auto
+}
+
+impl Segments {
+    pub(crate) fn code(&self, payload: &[u8], version: Version, ec: EcLevel) -> Result<QrCode, QrError> {
+        match *self {
+            Segments::Bytes { eci } => byte_code(payload, version, ec, eci),
+            Segments::Optimized { prefix, eci } => code(payload, prefix, version, ec, eci),
+            Segments::Auto => QrCode::with_version(payload, version, ec)
+        }
+    }
+
+    // bits of the code for payload spent on mode indicators, length fields and the ECI header
+    pub(crate) fn overhead_bits(&self, payload: &[u8], version: Version) -> usize {
+        let indicator = |mode: Mode| version.mode_bits_count() + mode.length_bits_count(version);
+        let optimized = |data: &[u8]| Parser::new(data).optimize(version).map(|segment| indicator(segment.mode)).sum::<usize>();
+        let eci = |eci: bool| if eci { ECI_BITS } else { 0 };
+        match *self {
+            Segments::Bytes { eci: on } => eci(on) + indicator(Mode::Byte),
+            Segments::Optimized { prefix, eci: on } => eci(on) + indicator(Mode::Byte) + optimized(&payload[prefix.min(payload.len())..]),
+            Segments::Auto => optimized(payload)
+        }
+    }
+}
+
+fn encoded_len(header_size: usize, data: &[u8], version: Version) -> usize {
+    let header = Segment { mode: Mode::Byte, begin: 0, end: header_size };
+    let segments: Vec<Segment> = Parser::new(data).optimize(version).collect();
+    header.encoded_len(version) + total_encoded_len(&segments, version)
+}
+
+// data bits of the symbol left for segments. micro symbols ending in a half codeword are only
+// filled to the last whole byte, qrcode can't pad them otherwise
+fn capacity(version: Version, ec: EcLevel, eci: bool) -> Result<usize, QrError> {
+    let bits = Bits::new(version).max_len(ec)?;
+    let bits = match version {
+        Version::Micro(_) => bits / 8 * 8,
+        Version::Normal(_) => bits
+    };
+    Ok(bits.saturating_sub(if eci { ECI_BITS } else { 0 }))
+}
@@ -0,0 +1,101 @@
+use std::collections::{BTreeMap, BTreeSet};
+use crate::{DecodeError, HeaderFormat, crypto, decode, parity};
+
+// collects scanned payloads one at a time for an interactive scanner: repeats are ignored,
+// received and missing chunks can be shown, and the data is returned once everything is there.
+// only the extended and compact headers know the total, with the legacy header call finish
+// once the user is done scanning. encrypted sets are only reported complete, see is_encrypted
+#[derive(Debug, Clone)]
+pub struct DecodeSession {
+    format: HeaderFormat,
+    meta: Option<(Option<usize>, u8, Option<u32>)>,
+    payloads: BTreeMap<usize, Vec<u8>>,
+    done: bool
+}
+
+impl DecodeSession {
+    pub fn new() -> Self {
+        Self::with_header(HeaderFormat::Legacy)
+    }
+
+    pub fn with_header(format: HeaderFormat) -> Self {
+        DecodeSession { format, meta: None, payloads: BTreeMap::new(), done: false }
+    }
+
+    // adds one scanned payload, returning the reassembled data the first time the set is complete.
+    // a complete set that fails to reassemble, e.g. on a misread, is tried again with every later
+    // push, so more parity codes can still make up for it. a different payload under an index
+    // already seen fails with ConflictingChunk and isn't kept
+    pub fn push(&mut self, payload: &[u8]) -> Result<Option<Vec<u8>>, DecodeError> {
+        let (header, data) = self.format.read(payload)?;
+        let meta = (header.total, header.flags, header.checksum);
+        match self.meta {
+            None => self.meta = Some(meta),
+            Some(m) if m != meta => return Err(DecodeError::InconsistentHeader),
+            _ => {}
+        }
+        if header.flags & parity::FLAG_PARITY == 0 && header.total.is_some_and(|total| header.index >= total) {
+            return Err(DecodeError::InconsistentHeader)
+        }
+        if let Some(first) = self.payloads.get(&header.index).filter(|first| first[..] != *payload) {
+            let (_, first) = self.format.read(first)?;
+            return Err(DecodeError::ConflictingChunk { index: header.index, first: first.to_vec(), second: data.to_vec() })
+        }
+        self.payloads.entry(header.index).or_insert_with(|| payload.to_vec());
+
+        if self.done || !self.is_complete() || self.is_encrypted() {
+            return Ok(None)
+        }
+        let data = self.finish()?;
+        self.done = true;
+        Ok(Some(data))
+    }
+
+    // chunk count from the headers, None until a payload with a total was pushed
+    pub fn total(&self) -> Option<usize> {
+        self.meta.and_then(|(total, _, _)| total)
+    }
+
+    pub fn received(&self) -> BTreeSet<usize> {
+        self.payloads.keys().copied().collect()
+    }
+
+    // without a total only the gaps below the highest index seen are known. parity codes
+    // aren't listed, with them the set can be complete while data chunks are still missing
+    pub fn missing(&self) -> BTreeSet<usize> {
+        let end = self.total().unwrap_or_else(|| self.payloads.keys().next_back().map_or(0, |i| i + 1));
+        (0..end).filter(|i| !self.payloads.contains_key(i)).collect()
+    }
+
+    // fraction of chunks received from 0.0 to 1.0, None while the total is unknown
+    pub fn progress(&self) -> Option<f32> {
+        self.total().map(|total| if total == 0 { 1.0 } else { (self.payloads.len() as f32 / total as f32).min(1.0) })
+    }
+
+    // any total codes will do once parity codes are involved
+    pub fn is_complete(&self) -> bool {
+        self.total().is_some_and(|total| self.payloads.len() >= total)
+    }
+
+    // whether the headers say the data is encrypted. push never returns such a set, once
+    // is_complete pass payloads to reassemble_decrypt or reassemble_passphrase
+    pub fn is_encrypted(&self) -> bool {
+        self.meta.is_some_and(|(_, flags, _)| flags & (crypto::FLAG_AES_GCM | crypto::FLAG_PASSPHRASE) != 0)
+    }
+
+    // every distinct payload received so far in index order, e.g. for reassemble_decrypt
+    pub fn payloads(&self) -> impl Iterator<Item = &[u8]> {
+        self.payloads.values().map(Vec::as_slice)
+    }
+
+    // the data reassembled from what was received, fails with MissingChunk while incomplete
+    pub fn finish(&self) -> Result<Vec<u8>, DecodeError> {
+        decode::reassemble_with(self.payloads(), self.format)
+    }
+}
+
+impl Default for DecodeSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
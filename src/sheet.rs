@@ -0,0 +1,115 @@
+use image::{Rgba, RgbaImage, imageops};
+
+// 3x5 pixel glyphs, one row per byte with the leftmost pixel in bit 2. lowercase letters other
+// than o and f are drawn as capitals, anything else missing is left blank
+const FONT: &[(char, [u8; 5])] = &[
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('o', [0b000, 0b111, 0b101, 0b101, 0b111]),
+    ('f', [0b011, 0b010, 0b111, 0b010, 0b010]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('_', [0b000, 0b000, 0b000, 0b000, 0b111]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('#', [0b101, 0b111, 0b101, 0b111, 0b101]),
+    ('(', [0b001, 0b010, 0b010, 0b010, 0b001]),
+    (')', [0b100, 0b010, 0b010, 0b010, 0b100])
+];
+
+// every rendered code in a grid of cols columns, each labelled underneath with labels[i].
+// spacing is the gap in pixels between cells and around the edges, cols must be at least 1
+pub(crate) fn render(tiles: &[RgbaImage], labels: &[String], cols: usize, spacing: u32, dark: Rgba<u8>, light: Rgba<u8>) -> RgbaImage {
+    let tile_width = tiles.iter().map(|t| t.width()).max().unwrap_or(0);
+    let tile_height = tiles.iter().map(|t| t.height()).max().unwrap_or(0);
+    let scale = (tile_width / 100).max(2);
+    let label_height = 7 * scale;
+
+    let cols = cols.min(tiles.len()).max(1) as u32;
+    let rows = (tiles.len() as u32).div_ceil(cols);
+    let cell_width = tile_width + spacing;
+    let cell_height = tile_height + label_height + spacing;
+    let mut sheet = RgbaImage::from_pixel(cols * cell_width + spacing, rows * cell_height + spacing, light);
+
+    for (i, tile) in tiles.iter().enumerate() {
+        let x = spacing + (i as u32 % cols) * cell_width;
+        let y = spacing + (i as u32 / cols) * cell_height;
+        imageops::overlay(&mut sheet, tile, x, y);
+
+        let label = &labels[i];
+        draw_text(&mut sheet, label, x + tile_width.saturating_sub(text_width(label, scale)) / 2, y + tile_height + scale, scale, dark);
+    }
+    sheet
+}
+
+// the tile with a strip below it holding the centered label
+pub(crate) fn caption(tile: &RgbaImage, label: &str, dark: Rgba<u8>, light: Rgba<u8>) -> RgbaImage {
+    let scale = (tile.width() / 100).max(2);
+    let mut image = RgbaImage::from_pixel(tile.width(), tile.height() + 7 * scale, light);
+    imageops::overlay(&mut image, tile, 0, 0);
+    draw_text(&mut image, label, tile.width().saturating_sub(text_width(label, scale)) / 2, tile.height() + scale, scale, dark);
+    image
+}
+
+fn text_width(text: &str, scale: u32) -> u32 {
+    (4 * text.chars().count() as u32).saturating_sub(1) * scale
+}
+
+fn draw_text(image: &mut RgbaImage, text: &str, left: u32, top: u32, scale: u32, color: Rgba<u8>) {
+    for (i, c) in text.chars().enumerate() {
+        let glyph = match FONT.iter().find(|(g, _)| *g == c).or_else(|| FONT.iter().find(|(g, _)| *g == c.to_ascii_uppercase())) {
+            Some((_, glyph)) => glyph,
+            None => continue
+        };
+        let glyph_left = left + 4 * scale * i as u32;
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let (x, y) = (glyph_left + col * scale + dx, top + row as u32 * scale + dy);
+                        if x < image.width() && y < image.height() {
+                            image.put_pixel(x, y, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+// how well a set uses its codes, for picking a version and ec level without doing the math by hand
+use qrcode::Version;
+use crate::{Capacity, HeaderFormat, Manifest, MultiQrCode, byte_mode_slack};
+
+const MM_PER_INCH: f32 = 25.4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub chunks: usize,
+    // payload bytes in every code, header included
+    pub chunk_bytes: Vec<usize>,
+    // bytes of the original data (after compression or encryption) in the data chunks
+    pub data_bytes: usize,
+    // headers, chunk crcs, parity and manifest codes, plus the mode indicators, length fields
+    // and ECI header in every code
+    pub overhead_bytes: usize,
+    // capacity left over in all codes
+    pub unused_bytes: usize,
+    // how full the last code is, from 0.0 to 100.0
+    pub last_chunk_percent: f32,
+    // modules per side of every code, quiet zone included
+    sides: Vec<usize>
+}
+
+impl Stats {
+    pub(crate) fn new(qr: &MultiQrCode, header: HeaderFormat) -> Self {
+        let (codes, payloads) = (&qr.codes, &qr.payloads);
+        let chunk_bytes: Vec<usize> = payloads.iter().map(Vec::len).collect();
+        let capacities: Vec<usize> = codes.iter()
+            .map(|code| Capacity::for_(code.version(), code.error_correction_level()).saturating_sub(byte_mode_slack(code.version())))
+            .collect();
+        let set = SetInfo::of(qr, header);
+        let data_bytes: usize = payloads.iter().enumerate().map(|(i, payload)| match set.manifest {
+            true if i == 0 => 0,
+            true => payload.len().saturating_sub(HeaderFormat::Legacy.size()),
+            false => match header.read(payload) {
+                Ok((chunk, _)) if chunk.index >= set.total => 0,
+                Ok((_, data)) => data.len(),
+                Err(_) => payload.len().saturating_sub(header.size())
+            }
+        }).sum();
+        let segments: usize = codes.iter().zip(payloads).zip(&qr.segments)
+            .map(|((code, payload), segments)| segments.overhead_bits(payload, code.version()).div_ceil(8))
+            .sum();
+        let last_chunk_percent = match (chunk_bytes.last(), capacities.last()) {
+            (Some(&used), Some(&capacity)) if capacity > 0 => (100.0 * used as f32 / capacity as f32).min(100.0),
+            _ => 0.0
+        };
+        Stats {
+            chunks: codes.len(),
+            data_bytes,
+            overhead_bytes: chunk_bytes.iter().sum::<usize>() - data_bytes + segments,
+            unused_bytes: capacities.iter().zip(&chunk_bytes).map(|(capacity, used)| capacity.saturating_sub(*used)).sum(),
+            last_chunk_percent,
+            sides: codes.iter().map(|code| code.width() + 2 * quiet_zone(code.version())).collect(),
+            chunk_bytes
+        }
+    }
+
+    // share of the stored bytes that is data rather than overhead, from 0.0 to 100.0
+    pub fn efficiency(&self) -> f32 {
+        let stored = self.data_bytes + self.overhead_bytes;
+        if stored == 0 { 0.0 } else { 100.0 * self.data_bytes as f32 / stored as f32 }
+    }
+
+    // side length in millimetres of every printed code, module_px dots per module at dpi,
+    // with the standard quiet zone of 4 modules (2 for Micro QR)
+    pub fn printed_sides(&self, dpi: u32, module_px: u32) -> Vec<f32> {
+        let module = module_px as f32 / dpi.max(1) as f32 * MM_PER_INCH;
+        self.sides.iter().map(|&side| side as f32 * module).collect()
+    }
+
+    // paper taken by all codes together in square millimetres, margins and captions not included
+    pub fn printed_area(&self, dpi: u32, module_px: u32) -> f32 {
+        self.printed_sides(dpi, module_px).iter().map(|side| side * side).sum()
+    }
+}
+
+// what the codes themselves say about how the set was encoded
+pub(crate) struct SetInfo {
+    // the first code is a manifest rather than a chunk
+    pub(crate) manifest: bool,
+    // header flags, or the flags stored in the manifest
+    pub(crate) flags: u8,
+    // data codes, parity codes come after them
+    pub(crate) total: usize
+}
+
+impl SetInfo {
+    pub(crate) fn of(qr: &MultiQrCode, format: HeaderFormat) -> Self {
+        let first = qr.payloads().first();
+        // only the legacy header leaves room for a manifest, see Manifest
+        let manifest = first.filter(|_| format == HeaderFormat::Legacy).and_then(|payload| Manifest::read(payload).ok());
+        if let Some(manifest) = manifest {
+            return SetInfo { manifest: true, flags: manifest.flags, total: manifest.total }
+        }
+        let header = first.and_then(|payload| format.read(payload).ok()).map(|(header, _)| header);
+        SetInfo {
+            manifest: false,
+            flags: header.map_or(0, |header| header.flags),
+            total: header.and_then(|header| header.total).unwrap_or(qr.len())
+        }
+    }
+}
+
+fn quiet_zone(version: Version) -> usize {
+    match version {
+        Version::Normal(_) => 4,
+        Version::Micro(_) => 2
+    }
+}
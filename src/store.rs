@@ -0,0 +1,183 @@
+// serde support for MultiQrCode. QrCode can't be built from its modules, so every code is
+// stored with its payload and segment layout as well and rebuilt from those on load. the
+// stored modules pin the exact symbol: the rebuilt code has to match them
+use qrcode::{EcLevel, QrCode, Version};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+use crate::{MultiQrCode, dataset::pack, segment::Segments};
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StoredSet {
+    pub(crate) codes: Vec<StoredCode>
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StoredCode {
+    pub(crate) version: StoredVersion,
+    pub(crate) ec: StoredEcLevel,
+    pub(crate) width: usize,
+    // row after row, 8 modules per byte with the first in the high bit, dark is 1
+    pub(crate) modules: Vec<u8>,
+    pub(crate) payload: Vec<u8>,
+    pub(crate) segments: StoredSegments
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum StoredSegments {
+    Bytes { eci: bool },
+    Optimized { prefix: usize, eci: bool },
+    Auto
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum StoredVersion {
+    Normal(i16),
+    Micro(i16)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum StoredEcLevel {
+    L,
+    M,
+    Q,
+    H
+}
+
+impl From<&MultiQrCode> for StoredSet {
+    fn from(qr: &MultiQrCode) -> Self {
+        let codes = qr.iter().zip(qr.payloads()).zip(&qr.segments).map(|((code, payload), &segments)| StoredCode {
+            version: code.version().into(),
+            ec: code.error_correction_level().into(),
+            width: code.width(),
+            modules: pack(&code.to_colors()),
+            payload: payload.clone(),
+            segments: segments.into()
+        }).collect();
+        StoredSet { codes }
+    }
+}
+
+impl TryFrom<StoredSet> for MultiQrCode {
+    type Error = &'static str;
+
+    fn try_from(set: StoredSet) -> Result<Self, Self::Error> {
+        let mut codes = Vec::with_capacity(set.codes.len());
+        let mut payloads = Vec::with_capacity(set.codes.len());
+        let mut segments = Vec::with_capacity(set.codes.len());
+        for stored in set.codes {
+            codes.push(stored.rebuild()?);
+            segments.push(stored.segments.into());
+            payloads.push(stored.payload);
+        }
+        Ok(MultiQrCode { codes, payloads, segments })
+    }
+}
+
+impl StoredCode {
+    fn rebuild(&self) -> Result<QrCode, &'static str> {
+        let segments = Segments::from(self.segments);
+        let code = segments.code(&self.payload, self.version.into(), self.ec.into()).map_err(|_| "payload doesn't fit the stored version")?;
+        if code.width() != self.width || pack(&code.to_colors()) != self.modules {
+            return Err("stored modules don't match the payload")
+        }
+        Ok(code)
+    }
+}
+
+impl From<Version> for StoredVersion {
+    fn from(version: Version) -> Self {
+        match version {
+            Version::Normal(n) => StoredVersion::Normal(n),
+            Version::Micro(n) => StoredVersion::Micro(n)
+        }
+    }
+}
+
+impl From<StoredVersion> for Version {
+    fn from(version: StoredVersion) -> Self {
+        match version {
+            StoredVersion::Normal(n) => Version::Normal(n),
+            StoredVersion::Micro(n) => Version::Micro(n)
+        }
+    }
+}
+
+impl From<Segments> for StoredSegments {
+    fn from(segments: Segments) -> Self {
+        match segments {
+            Segments::Bytes { eci } => StoredSegments::Bytes { eci },
+            Segments::Optimized { prefix, eci } => StoredSegments::Optimized { prefix, eci },
+            Segments::Auto => StoredSegments::Auto
+        }
+    }
+}
+
+impl From<StoredSegments> for Segments {
+    fn from(segments: StoredSegments) -> Self {
+        match segments {
+            StoredSegments::Bytes { eci } => Segments::Bytes { eci },
+            StoredSegments::Optimized { prefix, eci } => Segments::Optimized { prefix, eci },
+            StoredSegments::Auto => Segments::Auto
+        }
+    }
+}
+
+impl From<EcLevel> for StoredEcLevel {
+    fn from(ec: EcLevel) -> Self {
+        match ec {
+            EcLevel::L => StoredEcLevel::L,
+            EcLevel::M => StoredEcLevel::M,
+            EcLevel::Q => StoredEcLevel::Q,
+            EcLevel::H => StoredEcLevel::H
+        }
+    }
+}
+
+impl From<StoredEcLevel> for EcLevel {
+    fn from(ec: StoredEcLevel) -> Self {
+        match ec {
+            StoredEcLevel::L => EcLevel::L,
+            StoredEcLevel::M => EcLevel::M,
+            StoredEcLevel::Q => EcLevel::Q,
+            StoredEcLevel::H => EcLevel::H
+        }
+    }
+}
+
+impl Serialize for MultiQrCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StoredSet::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MultiQrCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        MultiQrCode::try_from(StoredSet::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+// serde(with) adapters for qrcode's Version and EcLevel, which don't implement serde themselves
+#[cfg(feature = "render-text")]
+pub(crate) mod version {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(version: &Version, serializer: S) -> Result<S::Ok, S::Error> {
+        StoredVersion::from(*version).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Version, D::Error> {
+        StoredVersion::deserialize(deserializer).map(Version::from)
+    }
+}
+
+#[cfg(feature = "render-text")]
+pub(crate) mod ec_level {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(ec: &EcLevel, serializer: S) -> Result<S::Ok, S::Error> {
+        StoredEcLevel::from(*ec).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<EcLevel, D::Error> {
+        StoredEcLevel::deserialize(deserializer).map(EcLevel::from)
+    }
+}
@@ -0,0 +1,38 @@
+// striped chunking: byte j of the data goes to chunk j % n, so losing a code takes every nth
+// byte instead of one contiguous run, which parity codes can then fill back in
+
+// header flags bit marking the data as striped over the chunks
+pub(crate) const FLAG_STRIPED: u8 = 0b0001_0000;
+
+// n chunks of at most size bytes, the first ones one byte longer than the rest
+pub(crate) fn split(data: &[u8], size: usize) -> Vec<Vec<u8>> {
+    let n = data.len().div_ceil(size.max(1));
+    let mut chunks: Vec<Vec<u8>> = (0..n).map(|_| Vec::with_capacity(size)).collect();
+    for (j, &byte) in data.iter().enumerate() {
+        chunks[j % n].push(byte);
+    }
+    chunks
+}
+
+// the len bytes striped over chunks, rebuilt chunks may be padded past their length
+pub(crate) fn join(chunks: &[Vec<u8>], len: usize) -> Vec<u8> {
+    let n = chunks.len();
+    (0..len).filter_map(|j| chunks[j % n].get(j / n).copied()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let data: Vec<u8> = (0..=100).collect();
+        let chunks = split(&data, 30);
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), [26, 25, 25, 25]);
+        assert_eq!(chunks[1][..3], [1, 5, 9]);
+        let mut padded = chunks.clone();
+        padded[3].push(0);
+        assert_eq!(join(&padded, data.len()), data);
+        assert!(split(&[], 30).is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+use qrcode::{Version, EcLevel, Color, types::QrError, canvas::Canvas, ec::construct_codewords, render::{Renderer, Pixel}};
+use crate::Capacity;
+
+// ISO/IEC 18004 allows at most 16 symbols in a structured append set
+pub const MAX_SYMBOLS: usize = 16;
+
+// qrcode's Bits has no way to push the raw sequence indicator and parity bits,
+// so structured append symbols are assembled here and drawn with qrcode's canvas directly
+#[derive(Clone)]
+pub struct Symbol {
+    version: Version,
+    ec: EcLevel,
+    width: usize,
+    content: Vec<Color>
+}
+
+impl Symbol {
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn error_correction_level(&self) -> EcLevel {
+        self.ec
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn to_colors(&self) -> Vec<Color> {
+        self.content.clone()
+    }
+
+    // same renderer QrCode::render returns, so symbols can be drawn the same way
+    pub fn render<P: Pixel>(&self) -> Renderer<'_, P> {
+        Renderer::new(&self.content, self.width, 4)
+    }
+}
+
+// split data into up to 16 symbols carrying standard structured append headers
+pub fn encode(data: &[u8], version: Version, ec: EcLevel) -> Result<Vec<Symbol>, QrError> {
+    if let Version::Micro(_) = version {
+        return Err(QrError::InvalidVersion)
+    }
+
+    let capacity = Capacity::for_(version, ec);
+    let overhead = (HEADER_BITS + 4 + count_bits(version)).div_ceil(8);
+    let chunk_size = capacity.checked_sub(overhead).filter(|&s| s > 0).ok_or(QrError::DataTooLong)?;
+
+    let chunks = data.chunks(chunk_size);
+    let total = chunks.len();
+    if total > MAX_SYMBOLS {
+        return Err(QrError::DataTooLong)
+    }
+    let parity = data.iter().fold(0u8, |acc, b| acc ^ b);
+
+    chunks.enumerate().map(|(i, part)| {
+        let raw = bits(part, i, total, parity, version, capacity);
+        let (data, ec_data) = construct_codewords(&raw, version, ec)?;
+        let mut canvas = Canvas::new(version, ec);
+        canvas.draw_all_functional_patterns();
+        canvas.draw_data(&data, &ec_data);
+        Ok(Symbol { version, ec, width: version.width() as usize, content: canvas.apply_best_mask().into_colors() })
+    }).collect()
+}
+
+// mode indicator, 4 bit position, 4 bit last position and 8 bit parity
+const HEADER_BITS: usize = 4 + 4 + 4 + 8;
+
+fn count_bits(version: Version) -> usize {
+    match version {
+        Version::Normal(1..=9) => 8,
+        _ => 16
+    }
+}
+
+// full data codewords for one symbol: header, a byte mode segment, terminator and padding
+fn bits(part: &[u8], index: usize, total: usize, parity: u8, version: Version, capacity: usize) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    writer.push(4, 0b0011);
+    writer.push(4, index as u32);
+    writer.push(4, (total - 1) as u32);
+    writer.push(8, parity as u32);
+    writer.push(4, 0b0100);
+    writer.push(count_bits(version), part.len() as u32);
+    for &b in part {
+        writer.push(8, b as u32);
+    }
+
+    let terminator = (capacity * 8 - writer.len).min(4);
+    writer.push(terminator, 0);
+    let mut bytes = writer.bytes;
+    bytes.extend([0b1110_1100, 0b0001_0001].iter().cycle().take(capacity - bytes.len()));
+    bytes
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize
+}
+
+impl BitWriter {
+    fn push(&mut self, n: usize, value: u32) {
+        for i in (0..n).rev() {
+            if self.len & 7 == 0 {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 1 << (7 - self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_bits() {
+        let raw = bits(b"A", 1, 3, 0xAB, Version::Normal(1), Capacity::for_(Version::Normal(1), EcLevel::L));
+        assert_eq!(raw[..5], [0b0011_0001, 0b0010_1010, 0b1011_0100, 0b0000_0001, 0b0100_0001]);
+        assert_eq!(raw.len(), 19);
+    }
+
+    #[test]
+    fn symbol_limit() {
+        let capacity = Capacity::for_(Version::Normal(1), EcLevel::L) - 4;
+        assert_eq!(encode(&vec![0; capacity * 16], Version::Normal(1), EcLevel::L).unwrap().len(), 16);
+        assert!(matches!(encode(&vec![0; capacity * 16 + 1], Version::Normal(1), EcLevel::L), Err(QrError::DataTooLong)));
+    }
+}
@@ -0,0 +1,141 @@
+// the chunking, headers and reassembly don't depend on the symbology carrying the payloads. a
+// SymbolEncoder turns one payload into the modules of one symbol, qr codes are the default and
+// data matrix and aztec are behind their features
+#[cfg(feature = "render-image")]
+use image::{GrayImage, Luma};
+use qrcode::{EcLevel, QrCode, Version, types::QrError};
+use crate::{BitMatrix, EncodeOptions, HeaderFormat, MqrError, MultiQrCode, ProgressSink, DEFAULT_EC_LEVEL, DEFAULT_QR_VERSION, boosted_code, byte_mode_slack, check_byte_mode, chunk_size, progress, segment::{self, Segments}};
+
+pub trait SymbolEncoder: Sync {
+    // payload bytes one symbol holds, header included
+    fn capacity(&self) -> usize;
+
+    // light modules the symbology wants around every symbol
+    fn quiet_zone(&self) -> usize;
+
+    fn encode(&self, payload: &[u8]) -> Result<BitMatrix, MqrError>;
+}
+
+// byte mode qr codes of a fixed version and ec level, the backend MultiQrCode's own codes are
+// made with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QrEncoder {
+    pub version: Version,
+    pub ec: EcLevel,
+    // bytes kept free for the byte segment's mode and length, see byte_mode_slack
+    pub slack: usize,
+    // see EncodeOptions::boost_ec
+    pub boost_ec: bool,
+    // see EncodeOptions::utf8_eci
+    pub utf8_eci: bool
+}
+
+impl QrEncoder {
+    pub fn new(version: Version, ec: EcLevel) -> Self {
+        QrEncoder { version, ec, slack: byte_mode_slack(version), boost_ec: false, utf8_eci: false }
+    }
+
+    // the encoder for codes built with options, a logo needs every code at ec level H
+    pub(crate) fn with_options(version: Version, ec: EcLevel, slack: usize, options: &EncodeOptions) -> Self {
+        let ec = if options.logo { EcLevel::H } else { ec };
+        QrEncoder { version, ec, slack, boost_ec: options.boost_ec, utf8_eci: options.utf8_eci }
+    }
+
+    pub(crate) fn code(&self, payload: &[u8], segments: Segments) -> Result<QrCode, QrError> {
+        let code = |payload: &[u8], ec| segments.code(payload, self.version, ec);
+        if self.boost_ec { boosted_code(payload, self.ec, code) } else { code(payload, self.ec) }
+    }
+}
+
+impl Default for QrEncoder {
+    fn default() -> Self {
+        QrEncoder::new(DEFAULT_QR_VERSION, DEFAULT_EC_LEVEL)
+    }
+}
+
+impl SymbolEncoder for QrEncoder {
+    fn capacity(&self) -> usize {
+        if check_byte_mode(self.version).is_err() {
+            return 0
+        }
+        let eci_size = if self.utf8_eci { segment::ECI_BITS.div_ceil(8) } else { 0 };
+        chunk_size(self.version, self.ec, self.slack + eci_size).unwrap_or(0)
+    }
+
+    fn quiet_zone(&self) -> usize {
+        match self.version {
+            Version::Normal(_) => 4,
+            Version::Micro(_) => 2
+        }
+    }
+
+    fn encode(&self, payload: &[u8]) -> Result<BitMatrix, MqrError> {
+        Ok(BitMatrix::from_code(&self.code(payload, Segments::Bytes { eci: self.utf8_eci })?))
+    }
+}
+
+// a set of symbols from any SymbolEncoder, read back with MultiQrCode::reassemble_with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolSet {
+    pub symbols: Vec<BitMatrix>,
+    payloads: Vec<Vec<u8>>,
+    quiet_zone: usize
+}
+
+impl SymbolSet {
+    // data split into as many symbols as needed, each payload starting with a header in format.
+    // MultiQrCodeBuilder::build_symbols takes every other encoding option as well
+    pub fn new<D: AsRef<[u8]>, E: SymbolEncoder + ?Sized>(data: D, encoder: &E, format: HeaderFormat) -> Result<Self, MqrError> {
+        MultiQrCode::builder().header(format).build_symbols(data, encoder)
+    }
+
+    // one symbol per payload, telling progress about every one and stopping when it cancels.
+    // with the parallel feature the symbols are built on all cores
+    pub(crate) fn encode<E: SymbolEncoder + ?Sized>(payloads: Vec<Vec<u8>>, encoder: &E, progress: Option<&dyn ProgressSink>) -> Result<Self, MqrError> {
+        let tracker = progress::Tracker::encoding(progress, payloads.len());
+        let symbol = |payload: &Vec<u8>| {
+            tracker.check()?;
+            let symbol = encoder.encode(payload)?;
+            tracker.step()?;
+            Ok(symbol)
+        };
+        #[cfg(feature = "parallel")]
+        let symbols = {
+            use rayon::prelude::*;
+            payloads.par_iter().map(symbol).collect::<Result<Vec<_>, MqrError>>()?
+        };
+        #[cfg(not(feature = "parallel"))]
+        let symbols = payloads.iter().map(symbol).collect::<Result<Vec<_>, MqrError>>()?;
+        Ok(SymbolSet { symbols, payloads, quiet_zone: encoder.quiet_zone() })
+    }
+
+    // raw bytes (header included) stored in each symbol, in the same order as symbols
+    pub fn payloads(&self) -> &[Vec<u8>] {
+        &self.payloads
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    // every symbol black on white with module_px pixels per module and the encoder's quiet zone
+    #[cfg(feature = "render-image")]
+    pub fn to_images(&self, module_px: u32) -> Vec<GrayImage> {
+        let scale = module_px.max(1) as usize;
+        self.symbols.iter().map(|symbol| {
+            let side = (symbol.width() + 2 * self.quiet_zone) * scale;
+            GrayImage::from_fn(side as u32, side as u32, |x, y| {
+                let x = (x as usize / scale).checked_sub(self.quiet_zone);
+                let y = (y as usize / scale).checked_sub(self.quiet_zone);
+                match (x, y) {
+                    (Some(x), Some(y)) if symbol.get(x, y) => Luma([0]),
+                    _ => Luma([255])
+                }
+            })
+        }).collect()
+    }
+}
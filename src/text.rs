@@ -0,0 +1,134 @@
+use qrcode::{QrCode, Color, Version, render::unicode::Dense1x2};
+
+// how a code is drawn as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextStyle {
+    // one character per module, '#' for dark
+    #[default]
+    Full,
+    // two module rows per line using the ▀ ▄ █ half blocks, fits version 10+ codes on screen
+    HalfBlock,
+    // 2x4 modules per character as braille dots, small enough to preview version 40 codes
+    Braille,
+    // two spaces per module on a black or white ansi background, invert swaps the two for dark themes
+    Ansi { invert: bool }
+}
+
+// characters used by the Full style. dark and light can be any string, e.g. "██" and "  "
+// to keep modules square in a terminal. quiet_zone is the light border in modules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextRenderOptions {
+    pub dark: String,
+    pub light: String,
+    pub invert: bool,
+    pub quiet_zone: usize
+}
+
+impl Default for TextRenderOptions {
+    fn default() -> Self {
+        TextRenderOptions { dark: "#".to_string(), light: " ".to_string(), invert: false, quiet_zone: 4 }
+    }
+}
+
+pub(crate) fn render_with(code: &QrCode, options: &TextRenderOptions) -> String {
+    let (dark, light) = match options.invert {
+        false => (&options.dark, &options.light),
+        true => (&options.light, &options.dark)
+    };
+    let (size, modules) = modules(code, options.quiet_zone);
+    modules.chunks(size.max(1))
+        .map(|row| row.iter().map(|&module| if module { dark.as_str() } else { light.as_str() }).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub(crate) fn render(code: &QrCode, style: TextStyle) -> String {
+    match style {
+        TextStyle::Full => code.render().light_color(' ').dark_color('#').build(),
+        TextStyle::HalfBlock => code.render::<Dense1x2>().build(),
+        TextStyle::Braille => braille(code),
+        TextStyle::Ansi { invert } => ansi(code, invert)
+    }
+}
+
+const ANSI_BLACK: &str = "\x1b[40m";
+const ANSI_WHITE: &str = "\x1b[107m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+fn ansi(code: &QrCode, invert: bool) -> String {
+    let (size, dark) = modules(code, default_quiet_zone(code));
+    let mut lines = Vec::with_capacity(size);
+    for row in dark.chunks(size) {
+        let mut line = String::new();
+        let mut current = None;
+        for &module in row {
+            // escapes are only written when the color changes
+            if current != Some(module) {
+                line.push_str(if module != invert { ANSI_BLACK } else { ANSI_WHITE });
+                current = Some(module);
+            }
+            line.push_str("  ");
+        }
+        line.push_str(ANSI_RESET);
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+// dot bit for each module of a 2 wide, 4 tall cell, indexed [row][col]
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+fn braille(code: &QrCode) -> String {
+    let (size, dark) = modules(code, default_quiet_zone(code));
+    let mut lines = Vec::with_capacity(size.div_ceil(4));
+    for top in (0..size).step_by(4) {
+        let mut line = String::with_capacity(size.div_ceil(2) * 3);
+        for left in (0..size).step_by(2) {
+            let mut bits = 0;
+            for (dy, row) in BRAILLE_DOTS.iter().enumerate() {
+                for (dx, bit) in row.iter().enumerate() {
+                    let (x, y) = (left + dx, top + dy);
+                    if x < size && y < size && dark[y * size + x] {
+                        bits |= bit;
+                    }
+                }
+            }
+            line.push(char::from_u32(0x2800 + bits).unwrap_or(' '));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+pub(crate) fn default_quiet_zone(code: &QrCode) -> usize {
+    match code.version() {
+        Version::Normal(_) => 4,
+        Version::Micro(_) => 2
+    }
+}
+
+// every dark run is a rectangle of the path, module_px is the size of a module in pixels.
+// background and foreground are the fill attributes of the two
+pub(crate) fn svg(code: &QrCode, quiet_zone: usize, module_px: u32, background: &str, foreground: &str) -> String {
+    let size = code.width() + 2 * quiet_zone;
+    let px = size as u32 * module_px;
+    let mut path = String::new();
+    for (x, y, len) in crate::pdf::dark_runs(code) {
+        path.push_str(&format!("M{} {}h{}v1h-{}z", x + quiet_zone, y + quiet_zone, len, len));
+    }
+    format!(
+        "<?xml version=\"1.0\" standalone=\"yes\"?><svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{px}\" height=\"{px}\" viewBox=\"0 0 {size} {size}\" shape-rendering=\"crispEdges\"><rect width=\"{size}\" height=\"{size}\" {background}/><path d=\"{path}\" {foreground}/></svg>")
+}
+
+// dark modules row by row, quiet zone included, along with the side length
+pub(crate) fn modules(code: &QrCode, quiet_zone: usize) -> (usize, Vec<bool>) {
+    let width = code.width();
+    let size = width + 2 * quiet_zone;
+    let mut dark = vec![false; size * size];
+    for (i, color) in code.to_colors().iter().enumerate() {
+        if *color == Color::Dark {
+            dark[(i / width + quiet_zone) * size + i % width + quiet_zone] = true;
+        }
+    }
+    (size, dark)
+}
@@ -0,0 +1,164 @@
+// multi-page tiff with one bilevel page per code, which document scanners, fax and print
+// workflows handle better than a folder of pngs. written by hand since the image crate only
+// writes single images. rows are packbits compressed, 1 bits are black
+use qrcode::{Color, QrCode};
+use crate::MqrError;
+use crate::pdf::QUIET_ZONE;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiffOptions {
+    // resolution stored in every page, so the codes print at their intended size
+    pub dpi: u32,
+    // dots per module
+    pub module_px: u32,
+    // light border around every code in modules
+    pub quiet_zone: usize
+}
+
+impl Default for TiffOptions {
+    fn default() -> Self {
+        TiffOptions { dpi: 300, module_px: 8, quiet_zone: QUIET_ZONE }
+    }
+}
+
+const COMPRESSION_PACKBITS: u16 = 32773;
+const PHOTOMETRIC_WHITE_IS_ZERO: u16 = 0;
+const RESOLUTION_INCH: u16 = 2;
+const SUBFILE_PAGE: u32 = 2;
+
+// field types
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const RATIONAL: u16 = 5;
+
+pub(crate) fn render(codes: &[QrCode], options: &TiffOptions) -> Result<Vec<u8>, MqrError> {
+    let mut res = b"II*\0".to_vec();
+    // where the offset of the next page's directory goes, the first one right after the magic
+    let mut next = res.len();
+    res.extend([0; 4]);
+    for (page, code) in codes.iter().enumerate() {
+        let (side, strip) = page_strip(code, options);
+        let strip_offset = res.len() as u32;
+        res.extend(&strip);
+        if res.len() % 2 == 1 {
+            res.push(0);
+        }
+
+        let ifd = res.len() as u32;
+        res[next..next + 4].copy_from_slice(&ifd.to_le_bytes());
+        let entries: [(u16, u16, u32, u32); 14] = [
+            (254, LONG, 1, SUBFILE_PAGE),
+            (256, LONG, 1, side as u32),
+            (257, LONG, 1, side as u32),
+            (258, SHORT, 1, 1),
+            (259, SHORT, 1, COMPRESSION_PACKBITS as u32),
+            (262, SHORT, 1, PHOTOMETRIC_WHITE_IS_ZERO as u32),
+            (273, LONG, 1, strip_offset),
+            (277, SHORT, 1, 1),
+            (278, LONG, 1, side as u32),
+            (279, LONG, 1, strip.len() as u32),
+            // the two rationals follow the directory
+            (282, RATIONAL, 1, ifd + 2 + 12 * 14 + 4),
+            (283, RATIONAL, 1, ifd + 2 + 12 * 14 + 4 + 8),
+            (296, SHORT, 1, RESOLUTION_INCH as u32),
+            // page number and page count as two shorts
+            (297, SHORT, 2, page as u32 & 0xffff | (codes.len() as u32) << 16)
+        ];
+        res.extend((entries.len() as u16).to_le_bytes());
+        for (tag, kind, count, value) in entries {
+            res.extend(tag.to_le_bytes());
+            res.extend(kind.to_le_bytes());
+            res.extend(count.to_le_bytes());
+            // a single short sits in the low bytes of the value field
+            res.extend(value.to_le_bytes());
+        }
+        next = res.len();
+        res.extend([0; 4]);
+        for _ in 0..2 {
+            res.extend(options.dpi.to_le_bytes());
+            res.extend(1u32.to_le_bytes());
+        }
+    }
+    if u32::try_from(res.len()).is_err() {
+        return Err(MqrError::InvalidOptions("too many codes for a single tiff file"))
+    }
+    Ok(res)
+}
+
+// side of the page in pixels and its rows packbits compressed
+fn page_strip(code: &QrCode, options: &TiffOptions) -> (usize, Vec<u8>) {
+    let scale = options.module_px as usize;
+    let side = (code.width() + 2 * options.quiet_zone) * scale;
+    let colors = code.to_colors();
+    let mut strip = Vec::new();
+    for y in 0..side {
+        let mut row = vec![0u8; side.div_ceil(8)];
+        let module_y = (y / scale).checked_sub(options.quiet_zone).filter(|&my| my < code.width());
+        if let Some(my) = module_y {
+            for mx in 0..code.width() {
+                if colors[my * code.width() + mx] != Color::Dark {
+                    continue
+                }
+                let start = (options.quiet_zone + mx) * scale;
+                for x in start..start + scale {
+                    row[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+        }
+        packbits(&row, &mut strip);
+    }
+    (side, strip)
+}
+
+// runs of 2 to 128 equal bytes as 1 - n and the byte, everything else as up to 128 literals
+// behind n - 1. rows are packed on their own as the spec asks
+fn packbits(row: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < row.len() {
+        let run = row[i..].iter().take(128).take_while(|&&b| b == row[i]).count();
+        if run > 1 {
+            out.push((257 - run) as u8);
+            out.push(row[i]);
+            i += run;
+            continue
+        }
+        let start = i;
+        while i < row.len() && i - start < 128 && (i + 1 == row.len() || row[i] != row[i + 1]) {
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        out.extend(&row[start..i]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::packbits;
+
+    fn unpack(mut packed: &[u8]) -> Vec<u8> {
+        let mut res = Vec::new();
+        while let Some((&n, rest)) = packed.split_first() {
+            if n < 128 {
+                res.extend(&rest[..n as usize + 1]);
+                packed = &rest[n as usize + 1..];
+            } else {
+                res.extend(std::iter::repeat_n(rest[0], 257 - n as usize));
+                packed = &rest[1..];
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn packbits_round_trip() {
+        let rows: [&[u8]; 5] = [&[], &[7], &[1, 2, 3, 3, 3, 4], &[0; 300], &(0..=255).collect::<Vec<u8>>()];
+        for row in rows {
+            let mut packed = Vec::new();
+            packbits(row, &mut packed);
+            assert_eq!(unpack(&packed), row);
+        }
+        let mut packed = Vec::new();
+        packbits(&[0; 300], &mut packed);
+        assert_eq!(packed, [129, 0, 129, 0, 213, 0]);
+    }
+}
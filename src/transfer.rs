@@ -0,0 +1,163 @@
+// one way screen to screen transfer: the sender cycles through the codes with no back channel
+// and the receiver's camera watches until it has caught every one. repeating the set and
+// spreading neighbouring chunks apart gives a camera that misses frames more chances
+use std::{thread, time::Duration};
+#[cfg(feature = "rqrr")]
+use image::GrayImage;
+use qrcode::QrCode;
+use crate::{DecodeSession, HeaderFormat, LiveDecoder, MultiQrCode, MqrError};
+
+// order the codes are shown in within one pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interleave {
+    #[default]
+    Sequential,
+    // every nth code starting at 0, then every nth starting at 1 and so on, e.g. Stride(2)
+    // shows the even chunks and then the odd ones
+    Stride(usize),
+    // a fixed pseudo random order, the same for a given seed
+    Shuffled { seed: u64 }
+}
+
+impl Interleave {
+    // indices 0..len in the order of one pass
+    pub fn order(&self, len: usize) -> Vec<usize> {
+        match *self {
+            Interleave::Sequential => (0..len).collect(),
+            Interleave::Stride(stride) => {
+                let stride = stride.max(1);
+                (0..stride.min(len)).flat_map(|start| (start..len).step_by(stride)).collect()
+            }
+            Interleave::Shuffled { seed } => {
+                // xorshift64, which gets stuck on 0
+                let mut state = seed.max(1);
+                let mut order: Vec<usize> = (0..len).collect();
+                for i in (1..len).rev() {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    order.swap(i, (state % (i as u64 + 1)) as usize);
+                }
+                order
+            }
+        }
+    }
+}
+
+pub struct Transmitter<'a> {
+    qr: &'a MultiQrCode,
+    fps: u32,
+    // passes over the set, None to loop until run is stopped
+    repeat: Option<usize>,
+    interleave: Interleave
+}
+
+impl<'a> Transmitter<'a> {
+    // 10 frames a second, three passes in order
+    pub fn new(qr: &'a MultiQrCode) -> Self {
+        Transmitter { qr, fps: 10, repeat: Some(3), interleave: Interleave::Sequential }
+    }
+
+    pub fn fps(mut self, fps: u32) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    pub fn repeat(mut self, passes: Option<usize>) -> Self {
+        self.repeat = passes;
+        self
+    }
+
+    pub fn interleave(mut self, interleave: Interleave) -> Self {
+        self.interleave = interleave;
+        self
+    }
+
+    pub fn frame_duration(&self) -> Duration {
+        Duration::from_secs(1) / self.fps.max(1)
+    }
+
+    // code indices in the order they're shown, endless without a repeat count
+    pub fn schedule(&self) -> impl Iterator<Item = usize> + 'a {
+        let order = self.interleave.order(self.qr.len());
+        // an empty set would otherwise spin through endless empty passes
+        let passes = if order.is_empty() { 0 } else { self.repeat.unwrap_or(usize::MAX) };
+        let len = order.len();
+        (0..passes).flat_map(move |_| 0..len).map(move |i| order[i])
+    }
+
+    // the codes in schedule order, with their index
+    pub fn frames(&self) -> impl Iterator<Item = (usize, &'a QrCode)> + 'a {
+        let codes = &self.qr.codes;
+        self.schedule().map(move |i| (i, &codes[i]))
+    }
+
+    // calls show for every frame at the frame rate until the schedule ends or show returns false
+    pub fn run<F: FnMut(usize, &QrCode) -> bool>(&self, mut show: F) -> Result<(), MqrError> {
+        if self.fps == 0 {
+            return Err(MqrError::InvalidOptions("fps must be at least 1"))
+        }
+        let frame = self.frame_duration();
+        for (index, code) in self.frames() {
+            if !show(index, code) {
+                break
+            }
+            thread::sleep(frame);
+        }
+        Ok(())
+    }
+}
+
+// collects what a camera sees of a Transmitter, a LiveDecoder that keeps the data once it's
+// complete. the header format needs a total, with the legacy header the receiver can't tell
+// when it's done
+#[derive(Debug, Clone)]
+pub struct Receiver {
+    decoder: LiveDecoder,
+    data: Option<Vec<u8>>
+}
+
+impl Receiver {
+    pub fn new(format: HeaderFormat) -> Self {
+        Receiver { decoder: LiveDecoder::new(format), data: None }
+    }
+
+    // adds one scanned payload, returning the data once every chunk is in, see LiveDecoder::push
+    pub fn push(&mut self, payload: &[u8]) -> Result<Option<&[u8]>, MqrError> {
+        if let Some(data) = self.decoder.push(payload)? {
+            self.data = Some(data);
+        }
+        Ok(self.data.as_deref())
+    }
+
+    // scans a camera frame and pushes every payload in it
+    #[cfg(feature = "rqrr")]
+    pub fn feed(&mut self, frame: &GrayImage) -> Result<Option<&[u8]>, MqrError> {
+        if let Some(data) = self.decoder.feed(frame)? {
+            self.data = Some(data);
+        }
+        Ok(self.data.as_deref())
+    }
+
+    pub fn session(&self) -> &DecodeSession {
+        self.decoder.session()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.data.is_some()
+    }
+
+    // payloads seen again after they were first received
+    pub fn duplicates(&self) -> usize {
+        self.decoder.duplicates()
+    }
+
+    // payloads that didn't fit the set
+    pub fn rejected(&self) -> usize {
+        self.decoder.rejected()
+    }
+
+    pub fn into_data(self) -> Option<Vec<u8>> {
+        self.data
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use qrcode::{Version, EcLevel, types::QrError};
+use crate::{Capacity, DecodeError};
+use crate::manifest::read_string;
+
+// BC-UR (Blockchain Commons Uniform Resources) "bytes" parts, as read by airgapped wallets.
+// only the plain fragments 1..=seqLen are emitted, which every UR decoder accepts;
+// fountain mixed parts are not generated and are skipped when decoding, the plain ones are enough
+
+// first and last letter of each of the 256 bytewords
+const BYTEWORDS: &str = concat!(
+    "aeadaoaxaaahamatayasbkbdbnbtbabsbebybgbwbbbzcmchcscfcycwcecackct",
+    "cxclcpcndkdadsdidedtdrdndwdpdmdldyeheyeoeeecenemetesftfrfnfsfmfh",
+    "fzfpfwfxfyfefgflfdgagegrgsgtglgwgdgygmgughgohfhghdhkhthphhhlhyhe",
+    "hnhsidiaieihiyioisinimjejzjnjtjljojsjpjkjykpkoktkskkknkgkekikblb",
+    "lalylflslrlplnltloldlelulklgmnmymhmemomumwmdmtmsmknlnyndnsntnnne",
+    "nboyoeotoxonolospdptpkpypspmplpepfpaprqdqzrerprlrorhrdrkrfryrnrs",
+    "rtsesasrssskswstspsosgsbsfsntotktitttdtetytltbtstptatnuyuoutueur",
+    "vtvyvovlvevwvavdvswlwdwmwpwewywswtwnwzwfwkykynylyaytzszoztzczezm"
+);
+
+const PREFIX: &str = "ur:bytes/";
+
+// UR strings for data, uppercased so qr codes can use the denser alphanumeric mode
+pub fn encode(data: &[u8], version: Version, ec: EcLevel) -> Result<Vec<String>, QrError> {
+    if let Version::Micro(_) = version {
+        return Err(QrError::InvalidVersion)
+    }
+
+    let mut message = Vec::with_capacity(data.len() + 9);
+    cbor_head(&mut message, 2, data.len() as u64);
+    message.extend_from_slice(data);
+
+    let max_chars = alphanumeric_capacity(version, ec);
+    if PREFIX.len() + 2 * (message.len() + 4) <= max_chars {
+        return Ok(vec![format!("{}{}", PREFIX, bytewords(&message)).to_uppercase()])
+    }
+
+    // largest fragment that still fits, then spread the message evenly over that many parts
+    let checksum = crc32fast::hash(&message);
+    let fits = |len: usize| {
+        let total = message.len().div_ceil(len);
+        part(&message, total, total, len, checksum).len() <= max_chars
+    };
+    let max_fragment = (1..=max_chars / 2).rev().find(|&len| fits(len)).ok_or(QrError::DataTooLong)?;
+    let total = message.len().div_ceil(max_fragment);
+    let fragment = message.len().div_ceil(total);
+
+    Ok((1..=total).map(|seq| part(&message, seq, total, fragment, checksum).to_uppercase()).collect())
+}
+
+// rebuild data from UR parts in any order, repeats are ignored
+pub fn decode<I, S>(parts: I) -> Result<Vec<u8>, DecodeError>
+where I: IntoIterator<Item = S>, S: AsRef<str> {
+    let mut fragments: BTreeMap<u64, Vec<u8>> = BTreeMap::new();
+    let mut meta: Option<(u64, u64, u64)> = None;
+
+    for part in parts {
+        let part = part.as_ref().to_lowercase();
+        let body = part.strip_prefix(PREFIX).ok_or(DecodeError::Malformed)?;
+        let (seq, body) = match body.split_once('/') {
+            // a single part ur holds the whole message
+            None => return message_data(&from_bytewords(body)?),
+            Some(split) => split
+        };
+        if seq.split_once('-').is_none() {
+            return Err(DecodeError::Malformed)
+        }
+
+        let cbor = from_bytewords(body)?;
+        let mut pos = 0;
+        if cbor_read_head(&cbor, &mut pos)? != (4, 5) {
+            return Err(DecodeError::Malformed)
+        }
+        let mut fields = [0u64; 4];
+        for field in fields.iter_mut() {
+            *field = match cbor_read_head(&cbor, &mut pos)? {
+                (0, value) => value,
+                _ => return Err(DecodeError::Malformed)
+            };
+        }
+        let [seq_num, seq_len, message_len, checksum] = fields;
+        let fragment = read_string(&cbor, &mut pos, 2)?;
+        if seq_num == 0 {
+            return Err(DecodeError::Malformed)
+        }
+        // a mixed part xors several fragments together
+        if seq_num > seq_len {
+            continue
+        }
+
+        match meta {
+            None => meta = Some((seq_len, message_len, checksum)),
+            Some(m) if m != (seq_len, message_len, checksum) => return Err(DecodeError::InconsistentHeader),
+            _ => {}
+        }
+        fragments.entry(seq_num).or_insert_with(|| fragment.to_vec());
+    }
+
+    let (seq_len, message_len, checksum) = meta.ok_or(DecodeError::MissingChunk(0))?;
+    let mut message = Vec::new();
+    for seq in 1..=seq_len {
+        message.extend(fragments.remove(&seq).ok_or(DecodeError::MissingChunk(seq as usize - 1))?);
+    }
+    message.truncate(message_len as usize);
+    if crc32fast::hash(&message) as u64 != checksum {
+        return Err(DecodeError::ChecksumMismatch)
+    }
+    message_data(&message)
+}
+
+fn part(message: &[u8], seq: usize, total: usize, fragment: usize, checksum: u32) -> String {
+    let start = ((seq - 1) * fragment).min(message.len());
+    let end = (start + fragment).min(message.len());
+    let mut bytes = message[start..end].to_vec();
+    bytes.resize(fragment, 0);
+
+    let mut cbor = vec![0x85];
+    cbor_head(&mut cbor, 0, seq as u64);
+    cbor_head(&mut cbor, 0, total as u64);
+    cbor_head(&mut cbor, 0, message.len() as u64);
+    cbor_head(&mut cbor, 0, checksum as u64);
+    cbor_head(&mut cbor, 2, fragment as u64);
+    cbor.extend(bytes);
+    format!("{}{}-{}/{}", PREFIX, seq, total, bytewords(&cbor))
+}
+
+// the message of a "bytes" ur is a single cbor byte string
+fn message_data(message: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut pos = 0;
+    match read_string(message, &mut pos, 2)? {
+        data if pos == message.len() => Ok(data.to_vec()),
+        _ => Err(DecodeError::Malformed)
+    }
+}
+
+// minimal bytewords with the trailing crc32
+fn bytewords(bytes: &[u8]) -> String {
+    let mut res = String::with_capacity(2 * (bytes.len() + 4));
+    for &b in bytes.iter().chain(crc32fast::hash(bytes).to_be_bytes().iter()) {
+        res.push_str(&BYTEWORDS[2 * b as usize..2 * b as usize + 2]);
+    }
+    res
+}
+
+fn from_bytewords(s: &str) -> Result<Vec<u8>, DecodeError> {
+    if s.len() & 1 == 1 || s.len() < 8 || !s.is_ascii() {
+        return Err(DecodeError::Malformed)
+    }
+    let mut bytes = Vec::with_capacity(s.len() / 2);
+    for pair in s.as_bytes().chunks(2) {
+        let index = BYTEWORDS.as_bytes().chunks(2).position(|w| w == pair).ok_or(DecodeError::Malformed)?;
+        bytes.push(index as u8);
+    }
+    let checksum = bytes.split_off(bytes.len() - 4);
+    if crc32fast::hash(&bytes).to_be_bytes()[..] != checksum[..] {
+        return Err(DecodeError::ChecksumMismatch)
+    }
+    Ok(bytes)
+}
+
+pub(crate) fn cbor_head(buf: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    match value {
+        0..=23 => buf.push(major | value as u8),
+        24..=0xff => buf.extend_from_slice(&[major | 24, value as u8]),
+        0x100..=0xffff => {
+            buf.push(major | 25);
+            buf.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x10000..=0xffff_ffff => {
+            buf.push(major | 26);
+            buf.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            buf.push(major | 27);
+            buf.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+pub(crate) fn cbor_read_head(bytes: &[u8], pos: &mut usize) -> Result<(u8, u64), DecodeError> {
+    let first = *bytes.get(*pos).ok_or(DecodeError::Malformed)?;
+    *pos += 1;
+    let size = match first & 0x1f {
+        info @ 0..=23 => return Ok((first >> 5, info as u64)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => return Err(DecodeError::Malformed)
+    };
+    let end = pos.checked_add(size).ok_or(DecodeError::Malformed)?;
+    let value = bytes.get(*pos..end).ok_or(DecodeError::Malformed)?;
+    *pos = end;
+    Ok((first >> 5, value.iter().fold(0, |acc, &b| (acc << 8) | b as u64)))
+}
+
+// characters that fit in one alphanumeric segment of the given version
+fn alphanumeric_capacity(version: Version, ec: EcLevel) -> usize {
+    let bits = Capacity::for_(version, ec) * 8;
+    let count_bits = match version {
+        Version::Normal(1..=9) => 9,
+        Version::Normal(10..=26) => 11,
+        _ => 13
+    };
+    let available = bits.saturating_sub(4 + count_bits);
+    available / 11 * 2 + usize::from(available % 11 >= 6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_part() {
+        let parts = encode(b"Hello world!", Version::Normal(10), EcLevel::L).unwrap();
+        assert_eq!(parts.len(), 1);
+        assert!(parts[0].starts_with("UR:BYTES/"));
+        assert_eq!(decode(&parts).unwrap(), b"Hello world!");
+    }
+
+    #[test]
+    fn multi_part() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 13 % 256) as u8).collect();
+        let mut parts = encode(&data, Version::Normal(5), EcLevel::M).unwrap();
+        assert!(parts.len() > 1);
+        assert!(parts.iter().all(|p| p.len() <= alphanumeric_capacity(Version::Normal(5), EcLevel::M)));
+        parts.reverse();
+        assert_eq!(decode(&parts).unwrap(), data);
+        assert_eq!(decode(&parts[1..]), Err(DecodeError::MissingChunk(parts.len() - 1)));
+    }
+
+    // lengths near u64::MAX used to overflow instead of failing
+    #[test]
+    fn hostile_parts() {
+        assert_eq!(decode(["ur:bytes/1-1/lpadadaeaehpzmzmzmzmzmzmzmzmdkesprke"]), Err(DecodeError::Malformed));
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 256) as u8).collect();
+        let mut parts = encode(&data, Version::Normal(5), EcLevel::M).unwrap();
+        let total = parts.len();
+        // a mixed part of the same message is skipped
+        let cbor = from_bytewords(&parts[0].to_lowercase()[PREFIX.len() + format!("1-{}/", total).len()..]).unwrap();
+        let mut pos = 1;
+        cbor_read_head(&cbor, &mut pos).unwrap();
+        let mut mixed = vec![0x85];
+        cbor_head(&mut mixed, 0, total as u64 + 1);
+        mixed.extend_from_slice(&cbor[pos..]);
+        parts.insert(1, format!("{}{}-{}/{}", PREFIX, total + 1, total, bytewords(&mixed)));
+        assert_eq!(decode(&parts).unwrap(), data);
+    }
+}
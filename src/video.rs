@@ -0,0 +1,99 @@
+// decoding codes shown one after another in a video, like the animations and the terminal
+// player make. gifs are read directly, anything else is decoded by the ffmpeg and ffprobe
+// command line tools, which have to be on the PATH
+use std::{fs::File, io::{self, BufReader, Read}, path::Path, process::{Command, Stdio}};
+use image::{AnimationDecoder, GrayImage, codecs::gif::GifDecoder};
+use crate::{HeaderFormat, LiveDecoder, MqrError};
+
+// scans every frame until the set is complete. repeated codes are skipped and misreads, like a
+// code from another set in view, are ignored. with the legacy header, which doesn't know the
+// total, the whole video is scanned
+pub fn decode_video<P: AsRef<Path>>(path: P, format: HeaderFormat) -> Result<Vec<u8>, MqrError> {
+    let mut decoder = LiveDecoder::new(format);
+    let mut result = Ok(None);
+    // frames are scanned in batches, on all cores with the parallel feature
+    let batch_size = batch_size();
+    let mut batch = Vec::with_capacity(batch_size);
+    for_each_frame(path.as_ref(), |frame| {
+        batch.push(frame);
+        if batch.len() < batch_size {
+            return true
+        }
+        result = decoder.feed_all(&batch);
+        batch.clear();
+        matches!(result, Ok(None))
+    })?;
+    if matches!(result, Ok(None)) && !batch.is_empty() {
+        result = decoder.feed_all(&batch);
+    }
+    match result? {
+        Some(data) => Ok(data),
+        None => decoder.finish()
+    }
+}
+
+// frames worth scanning at once to keep every core busy, 1 without the parallel feature
+fn batch_size() -> usize {
+    #[cfg(feature = "parallel")]
+    {
+        2 * rayon::current_num_threads()
+    }
+    #[cfg(not(feature = "parallel"))]
+    1
+}
+
+// f gets frames in order until it returns false or the video ends
+fn for_each_frame<F: FnMut(GrayImage) -> bool>(path: &Path, mut f: F) -> Result<(), MqrError> {
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gif")) {
+        let decoder = GifDecoder::new(BufReader::new(File::open(path)?))?;
+        for frame in decoder.into_frames() {
+            if !f(image::DynamicImage::ImageRgba8(frame?.into_buffer()).to_luma8()) {
+                break
+            }
+        }
+        return Ok(())
+    }
+
+    // frames come out as stored, the size ffprobe reports, rather than turned by the rotation
+    // metadata. scanning doesn't care which way up a code is
+    let (width, height) = dimensions(path)?;
+    let mut child = Command::new("ffmpeg")
+        .args(["-v", "error", "-noautorotate", "-i"]).arg(path).args(["-f", "rawvideo", "-pix_fmt", "gray", "-"])
+        .stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null())
+        .spawn()?;
+    let mut stdout = child.stdout.take().ok_or_else(|| io::Error::other("ffmpeg has no output"))?;
+    let mut buffer = vec![0; width as usize * height as usize];
+    let mut stopped = false;
+    loop {
+        match stdout.read_exact(&mut buffer) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into())
+        }
+        let frame = GrayImage::from_raw(width, height, buffer.clone()).ok_or_else(|| io::Error::other("short frame"))?;
+        if !f(frame) {
+            // the rest of the video isn't needed
+            let _ = child.kill();
+            stopped = true;
+            break
+        }
+    }
+    let status = child.wait()?;
+    if !stopped && !status.success() {
+        return Err(io::Error::other(format!("ffmpeg failed to decode {}: {}", path.display(), status)).into())
+    }
+    Ok(())
+}
+
+// width and height of the first video stream
+fn dimensions(path: &Path) -> Result<(u32, u32), MqrError> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0", "-show_entries", "stream=width,height", "-of", "csv=p=0:s=x"])
+        .arg(path)
+        .output()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.trim().split_once('x')
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+        .filter(|&(width, height)| output.status.success() && width > 0 && height > 0)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("no video stream in {}", path.display())).into())
+}
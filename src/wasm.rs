@@ -0,0 +1,46 @@
+use js_sys::{Array, Uint8Array};
+use qrcode::{EcLevel, Version};
+use wasm_bindgen::prelude::*;
+use crate::{MultiQrCode, QrData, decode};
+
+// browser bindings over the legacy header, build with --no-default-features --features wasm.
+// version is a normal version from 1 to 40, ec one of "L", "M", "Q" or "H"
+
+// one png file per code, as an array of Uint8Array
+#[wasm_bindgen]
+pub fn encode_to_pngs(data: &[u8], version: i16, ec: &str) -> Result<Array, JsError> {
+    let qr = encode(data, version, ec)?;
+    let pngs = qr.to_png_bytes().map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(pngs.iter().map(|png| Uint8Array::from(&png[..])).collect())
+}
+
+// one svg document per code, as an array of strings
+#[wasm_bindgen]
+pub fn encode_to_svgs(data: &[u8], version: i16, ec: &str) -> Result<Array, JsError> {
+    let qr = encode(data, version, ec)?;
+    Ok(qr.to_svgs().into_iter().filter_map(|svg| match svg {
+        QrData::Svg(svg) => Some(JsValue::from(svg)),
+        _ => None
+    }).collect())
+}
+
+// scanned payloads (Uint8Array or anything Uint8Array accepts) in any order back to the data
+#[wasm_bindgen]
+pub fn reassemble(payloads: Array) -> Result<Vec<u8>, JsError> {
+    let payloads: Vec<Vec<u8>> = payloads.iter().map(|payload| Uint8Array::new(&payload).to_vec()).collect();
+    decode::reassemble(&payloads).map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn encode(data: &[u8], version: i16, ec: &str) -> Result<MultiQrCode, JsError> {
+    if !(1..=40).contains(&version) {
+        return Err(JsError::new("version must be from 1 to 40"))
+    }
+    let ec = match ec {
+        "L" | "l" => EcLevel::L,
+        "M" | "m" => EcLevel::M,
+        "Q" | "q" => EcLevel::Q,
+        "H" | "h" => EcLevel::H,
+        _ => return Err(JsError::new("ec must be one of L, M, Q or H"))
+    };
+    MultiQrCode::new(data, Version::Normal(version), ec).map_err(|e| JsError::new(&e.to_string()))
+}